@@ -0,0 +1,68 @@
+//! Counts the allocations of loading a mesh of one million triangles, to compare the prim vertex
+//! lists with and without the `smallvec` feature.
+//!
+//! Run with `cargo bench --bench prim_vertices` and
+//! `cargo bench --bench prim_vertices --features smallvec`.
+
+use houdini_node::{RawAttributeData, load_raw_from_reader};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// The system allocator, counting the allocations.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const NUM_TRIANGLES: usize = 1_000_000;
+
+fn input() -> String {
+    let mut prims = String::new();
+    for i in 0..NUM_TRIANGLES {
+        if i > 0 {
+            prims.push(',');
+        }
+        let v = i * 3;
+        write!(prims, "[{},{},{}]", v, v + 1, v + 2).unwrap();
+    }
+    format!(
+        r#"[{{"points":{{}},"vertices":{{}},"prims":{{"vertices":{{"tuple_size":1,"data":{{"prim_vertex":[{prims}]}}}}}},"detail":{{}}}}]"#
+    )
+}
+
+fn main() {
+    let input = input();
+    println!("input size: {} MB", input.len() / 1_000_000);
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let raw = load_raw_from_reader(input.as_bytes()).unwrap();
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    let RawAttributeData::PrimVertex(prims) = &raw[0].prims["vertices"].data else {
+        panic!("expected prim vertices");
+    };
+    assert_eq!(prims.len(), NUM_TRIANGLES);
+    println!("load: {allocations} allocations in {elapsed:?}");
+}
@@ -0,0 +1,73 @@
+//! Compares parsing a large input in full against parsing only the attributes a node reads.
+//!
+//! Run with `cargo bench --bench projection`.
+
+use glam::Vec3;
+use houdini_node::{FromRawGeometry, Geometry, InAttrs, RawGeometry, load_raw_projected};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+#[derive(InAttrs)]
+#[allow(dead_code)]
+struct Point {
+    #[attr(name = "P")]
+    position: Vec3,
+}
+
+const NUM_POINTS: usize = 200_000;
+const ITERATIONS: u32 = 5;
+
+fn float_attr(tuple_size: usize) -> String {
+    let mut values = String::new();
+    for i in 0..NUM_POINTS * tuple_size {
+        if i > 0 {
+            values.push(',');
+        }
+        write!(values, "{}", i as f32 * 0.001).unwrap();
+    }
+    format!(r#"{{"tuple_size":{tuple_size},"data":{{"float":[{values}]}}}}"#)
+}
+
+fn string_attr() -> String {
+    let values: Vec<_> = (0..NUM_POINTS).map(|i| format!(r#""piece{i}""#)).collect();
+    format!(
+        r#"{{"tuple_size":1,"data":{{"string":[{}]}}}}"#,
+        values.join(",")
+    )
+}
+
+fn input() -> String {
+    format!(
+        r#"[{{"points":{{"P":{},"N":{},"Cd":{},"uv":{},"name":{}}},"vertices":{{}},"prims":{{}},"detail":{{}}}}]"#,
+        float_attr(3),
+        float_attr(3),
+        float_attr(3),
+        float_attr(3),
+        string_attr(),
+    )
+}
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        f();
+        total += start.elapsed();
+    }
+    println!("{name}: {:?} per iteration", total / ITERATIONS);
+}
+
+fn main() {
+    let input = input();
+    println!("input size: {} MB", input.len() / 1_000_000);
+
+    bench("full", || {
+        let raw: Vec<RawGeometry> = serde_json::from_reader(input.as_bytes()).unwrap();
+        Geometry::<Point>::from_raw(raw.into_iter().next().unwrap(), 0).unwrap();
+    });
+
+    bench("projected", || {
+        let raw = load_raw_projected(input.as_bytes(), &[Geometry::<Point>::projection()]).unwrap();
+        Geometry::<Point>::from_raw(raw.into_iter().next().unwrap(), 0).unwrap();
+    });
+}
@@ -0,0 +1,67 @@
+//! Compares the parse throughput of `serde_json` and `simd-json` on a ~100MB input.
+//!
+//! Run with `cargo bench --bench simd --features simd`.
+
+use houdini_node::{RawGeometry, load_raw_from_reader};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+const NUM_POINTS: usize = 1_000_000;
+const ITERATIONS: u32 = 3;
+
+fn float_attr(tuple_size: usize) -> String {
+    let mut values = String::new();
+    for i in 0..NUM_POINTS * tuple_size {
+        if i > 0 {
+            values.push(',');
+        }
+        write!(values, "{}", i as f32 * 0.001).unwrap();
+    }
+    format!(r#"{{"tuple_size":{tuple_size},"data":{{"float":[{values}]}}}}"#)
+}
+
+fn string_attr() -> String {
+    let values: Vec<_> = (0..NUM_POINTS).map(|i| format!(r#""piece{i}""#)).collect();
+    format!(
+        r#"{{"tuple_size":1,"data":{{"string":[{}]}}}}"#,
+        values.join(",")
+    )
+}
+
+fn input() -> String {
+    format!(
+        r#"[{{"points":{{"P":{},"N":{},"Cd":{},"uv":{},"name":{}}},"vertices":{{}},"prims":{{}},"detail":{{}}}}]"#,
+        float_attr(3),
+        float_attr(3),
+        float_attr(3),
+        float_attr(3),
+        string_attr(),
+    )
+}
+
+fn bench(name: &str, bytes: usize, mut f: impl FnMut()) {
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        f();
+        total += start.elapsed();
+    }
+    let per_iteration = total / ITERATIONS;
+    let throughput = bytes as f64 / 1e6 / per_iteration.as_secs_f64();
+    println!("{name}: {per_iteration:?} per iteration, {throughput:.0} MB/s");
+}
+
+fn main() {
+    let input = input();
+    println!("input size: {} MB", input.len() / 1_000_000);
+
+    bench("serde_json", input.len(), || {
+        let raw: Vec<RawGeometry> = serde_json::from_reader(input.as_bytes()).unwrap();
+        assert_eq!(raw.len(), 1);
+    });
+
+    bench("simd-json", input.len(), || {
+        let raw = load_raw_from_reader(input.as_bytes()).unwrap();
+        assert_eq!(raw.len(), 1);
+    });
+}
@@ -1,4 +1,4 @@
-use glam::{Mat3, Vec3};
+use glam::Vec3;
 use houdini_node::Geometry;
 use houdini_node_macro::{InAttrs, OutAttrs, houdini_node_main};
 
@@ -45,7 +45,8 @@ fn my_cool_node(
         prims: geo.prims,
         detail: OutDetail {
             some_data: vec![1.0, 2.0, 3.0],
-            other_data: "hello".to_string(),
+            other_data: geo.detail.my_data,
         },
+        extra: geo.extra,
     })
 }
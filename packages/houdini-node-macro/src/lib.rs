@@ -1,10 +1,29 @@
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{Data, DeriveInput, Expr, ExprLit, Fields, Lit, Meta, parse_macro_input};
+use syn::punctuated::Punctuated;
+use syn::{Data, DeriveInput, Fields, LitInt, LitStr, Token, parse_macro_input};
 
 /// Proc macro to generate a main function.
+///
+/// `#[houdini_node_main(format = "binary")]` selects the transport, one of `json` (the default),
+/// `ndjson` or `binary`. It has to match the format selected on the node in Houdini. The `ndjson`
+/// input starts with the number of geometries, see `houdini_node::load_raw_ndjson_counted`.
+///
+/// The node is cancelled (see `houdini_node::cancel`) on `SIGINT`/`SIGTERM` and when stdin is
+/// closed.
 #[proc_macro_attribute]
-pub fn houdini_node_main(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn houdini_node_main(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut format = Transport::Json;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("format") {
+            let value: LitStr = meta.value()?.parse()?;
+            format = Transport::parse(&value)?;
+            Ok(())
+        } else {
+            Err(meta.error("unsupported houdini_node_main option, expected `format`"))
+        }
+    });
+    parse_macro_input!(args with parser);
     let mut input_fn = parse_macro_input!(input as syn::ItemFn);
 
     if input_fn.sig.ident == "main" {
@@ -29,20 +48,23 @@ pub fn houdini_node_main(_args: TokenStream, input: TokenStream) -> TokenStream
         })
         .collect();
 
+    let (load, generate) = format.codec();
     let expanded = quote! {
         #input_fn
 
         fn main() {
             let run = || {
-                let raw_geos = houdini_node::load_raw_from_stdin()?;
+                houdini_node::cancel::cancel_on_signal();
+                let raw_geos = #load;
+                houdini_node::cancel::cancel_on_stdin_close();
                 let mut iter = raw_geos.into_iter();
 
                 let out_geo = #fn_name(#(#input_params),*)
                     .map_err(|e| houdini_node::Error::UserError(e.to_string()))?;
-                houdini_node::generate_to_stdout(out_geo)
+                #generate
             };
             if let Err(e) = run() {
-                eprintln!("{}", e);
+                e.print_json();
                 std::process::exit(1);
             }
         }
@@ -51,45 +73,228 @@ pub fn houdini_node_main(_args: TokenStream, input: TokenStream) -> TokenStream
     TokenStream::from(expanded)
 }
 
+/// The transport between Houdini and the node, see `#[houdini_node_main(format = "..")]`.
+#[derive(Copy, Clone)]
+enum Transport {
+    Json,
+    Ndjson,
+    Binary,
+}
+
+impl Transport {
+    fn parse(value: &LitStr) -> syn::Result<Self> {
+        match value.value().as_str() {
+            "json" => Ok(Transport::Json),
+            "ndjson" => Ok(Transport::Ndjson),
+            "binary" => Ok(Transport::Binary),
+            _ => Err(syn::Error::new(
+                value.span(),
+                "unknown format, expected `json`, `ndjson` or `binary`",
+            )),
+        }
+    }
+
+    /// The expressions reading the input geometries from stdin and writing `out_geo` to stdout.
+    /// The input is read up to its end, as stdin stays open until the node is cancelled.
+    fn codec(self) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        match self {
+            Transport::Json => (
+                quote! { houdini_node::load_raw_from_stdin()? },
+                quote! { houdini_node::generate_to_stdout(out_geo) },
+            ),
+            Transport::Ndjson => (
+                quote! { houdini_node::load_raw_ndjson_counted(std::io::stdin().lock())? },
+                quote! { houdini_node::generate_ndjson_to_writer(std::io::stdout().lock(), out_geo) },
+            ),
+            Transport::Binary => (
+                quote! { houdini_node::binary::load_raw_binary(std::io::stdin().lock())? },
+                quote! { houdini_node::binary::generate_binary_to_stdout(out_geo) },
+            ),
+        }
+    }
+}
+
 #[proc_macro_derive(InAttrs, attributes(attr))]
 pub fn derive_in_attrs(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     impl_in_attrs(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
 #[proc_macro_derive(OutAttrs, attributes(attr))]
 pub fn derive_out_attrs(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     impl_out_attrs(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(PrimTopology, attributes(attr))]
+pub fn derive_prim_topology(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_prim_topology(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
-fn impl_in_attrs(ast: &DeriveInput) -> TokenStream {
+#[proc_macro_derive(VertexTopology, attributes(attr))]
+pub fn derive_vertex_topology(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_vertex_topology(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(PointPosition, attributes(attr))]
+pub fn derive_point_position(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    impl_point_position(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn impl_in_attrs(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let name = &ast.ident;
-    let fields = match &ast.data {
-        Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => &fields.named,
-            _ => panic!("Only named fields are supported"),
-        },
-        _ => panic!("Only structs are supported"),
-    };
+    let fields = named_fields(ast);
+    let options = fields
+        .iter()
+        .map(FieldOptions::parse)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    check_reserved_names(fields, &options, false)?;
+    let struct_options = StructOptions::parse(ast)?;
+    let groups = ComponentGroup::collect(fields, &options, true)?;
+
+    // Attributes that are split into components are removed once, before any component is read.
+    let group_removals: Vec<_> = groups
+        .iter()
+        .map(|group| {
+            let ident = &group.ident;
+            let attr_name = &group.name;
+            quote! { let #ident = attrs.remove(#attr_name); }
+        })
+        .collect();
 
     let field_loads: Vec<_> = fields
         .iter()
-        .map(|field| {
+        .zip(&options)
+        .map(|(field, options)| {
             let field_name = format_ident!("v_{}", field.ident.as_ref().unwrap());
-            let attr_name = get_attr_name(field);
+            let attr_name = &options.name;
+            // With a `default_fn`, the attribute is removed up front to check whether it exists.
+            let source = match options.default_fn {
+                Some(_) => quote! { attr },
+                None => quote! { attrs.remove(#attr_name) },
+            };
+            let load = match &options.tuple_size {
+                _ if let Some(component) = options.component => {
+                    let group = ComponentGroup::find(&groups, attr_name);
+                    quote! {
+                        houdini_node::load_component_from_attr(
+                            #group.as_ref(),
+                            #component,
+                            num_elements,
+                            #attr_name,
+                            err_context
+                        )?
+                    }
+                }
+                _ if options.parse_string => quote! {
+                    houdini_node::load_from_string_attr(
+                        #source,
+                        num_elements,
+                        #attr_name,
+                        err_context
+                    )?
+                },
+                _ if options.json => quote! {
+                    houdini_node::load_from_json_attr(
+                        #source,
+                        num_elements,
+                        #attr_name,
+                        err_context
+                    )?
+                },
+                Some(tuple_size) => quote! {
+                    houdini_node::load_from_attr_resized(
+                        #source,
+                        #tuple_size,
+                        num_elements,
+                        #attr_name,
+                        err_context
+                    )?
+                },
+                None => quote! {
+                    houdini_node::load_from_attr(
+                        #source,
+                        num_elements,
+                        #attr_name,
+                        err_context
+                    )?
+                },
+            };
+            let load = match &options.default_fn {
+                Some(default_fn) => quote! {
+                    match attrs.remove(#attr_name) {
+                        None => houdini_node::itertools::Either::Right(
+                            (0..num_elements).map(#default_fn)
+                        ),
+                        attr => houdini_node::itertools::Either::Left(#load),
+                    }
+                },
+                None => load,
+            };
+            let ty = &field.ty;
+            let load = match &options.clamp {
+                Some((min, max)) if options.reject_out_of_range => quote! {
+                    #load
+                        .map(|v| houdini_node::check_range(v, #min..=#max, #attr_name))
+                        .collect::<houdini_node::Result<Vec<#ty>>>()?
+                        .into_iter()
+                },
+                Some((min, max)) => quote! { #load.map(|v: #ty| v.clamp(#min, #max)) },
+                None => load,
+            };
+            let validation = match &struct_options.prim_size {
+                Some(prim_size) if attr_name == "vertices" => quote! {
+                    let #field_name: Vec<#ty> = #field_name.collect();
+                    for (prim_index, vertices) in #field_name.iter().enumerate() {
+                        let actual = <#ty as AsRef<[usize]>>::as_ref(vertices).len();
+                        if actual != #prim_size {
+                            return Err(houdini_node::Error::InvalidPrimSize {
+                                prim_index,
+                                expected: #prim_size,
+                                actual,
+                            });
+                        }
+                    }
+                    let #field_name = #field_name.into_iter();
+                },
+                _ => quote! {},
+            };
             quote! {
-                let #field_name = houdini_node::load_from_attr(
-                    attrs.remove(#attr_name),
-                    num_elements,
-                    #attr_name,
-                    err_context
-                )?;
+                let #field_name = #load;
+                #validation
             }
         })
         .collect();
 
+    if let Some(prim_size) = &struct_options.prim_size
+        && !options.iter().any(|o| o.name == "vertices")
+    {
+        return Err(syn::Error::new_spanned(
+            prim_size,
+            "`prim_size` requires a field for the `vertices` attribute",
+        ));
+    }
+
     let field_names: Vec<_> = fields.iter().map(|f| &f.ident).collect();
+    let attr_list = attr_list(fields, &options, &groups, |ty| {
+        quote! {
+            <<#ty as houdini_node::FromAttributeData>::DataType as houdini_node::FromAttributeDataSource>::LEN
+        }
+    });
 
     // Prefix field names in order to prevent possible (future) name collisions.
     let prefixed_field_names: Vec<_> = fields
@@ -102,28 +307,31 @@ fn impl_in_attrs(ast: &DeriveInput) -> TokenStream {
 
     let generated = quote! {
         impl houdini_node::InAttrs for #name {
+            const IN_ATTRS: &'static [(&'static str, usize)] = &[#(#attr_list),*];
+
             fn from_attr(
                 mut attrs: std::collections::HashMap<String, houdini_node::RawAttribute>,
                 err_context: houdini_node::ErrContext,
             ) -> houdini_node::Result<impl Iterator<Item = Self>> {
-                let num_elements = attrs.values().next().map(|a| a.data.len()).unwrap_or(0);
+                let num_elements = houdini_node::element_count(&attrs, Self::IN_ATTRS, err_context)?;
+                #(#group_removals)*
                 #(#field_loads)*
                 Ok(#field_construction)
             }
         }
     };
-    generated.into()
+    Ok(generated)
 }
 
-fn impl_out_attrs(ast: &DeriveInput) -> TokenStream {
+fn impl_out_attrs(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let name = &ast.ident;
-    let fields = match &ast.data {
-        Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => &fields.named,
-            _ => panic!("Only named fields are supported"),
-        },
-        _ => panic!("Only structs are supported"),
-    };
+    let fields = named_fields(ast);
+    let options = fields
+        .iter()
+        .map(FieldOptions::parse)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    check_reserved_names(fields, &options, true)?;
 
     let field_names: Vec<_> = fields.iter().map(|f| &f.ident).collect();
 
@@ -132,59 +340,588 @@ fn impl_out_attrs(ast: &DeriveInput) -> TokenStream {
         .iter()
         .map(|f| format_ident!("v_{}", f.ident.as_ref().unwrap()))
         .collect();
-    let attr_names: Vec<_> = fields.iter().map(get_attr_name).collect();
+    let groups = ComponentGroup::collect(fields, &options, false)?;
+    let attr_list = attr_list(fields, &options, &groups, |ty| {
+        quote! {
+            <<#ty as houdini_node::IntoAttributeData>::DataType as houdini_node::IntoAttributeDataSource>::LEN
+        }
+    });
 
-    let vec_types = fields.iter().map(|_| quote! { Vec<_>});
+    let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
 
     let multiunzip_pattern = quote! { (#(#prefixed_field_names,)*) };
-    let multiunzip_types = quote! { #(#vec_types,)* };
+    let multiunzip_types = quote! { #(Vec<#field_types>,)* };
 
     let entity_map = quote! { |entity| (#(entity.#field_names,)*) };
 
-    let hashmap_entries: Vec<_> = prefixed_field_names
+    // The values of a field of the borrowed entities, see `houdini_node::derive_support`.
+    let ref_bindings: Vec<_> = field_names
+        .iter()
+        .zip(&prefixed_field_names)
+        .map(|(field, prefixed)| {
+            quote! {
+                let #prefixed = (&&houdini_node::derive_support::FieldValues {
+                    entities,
+                    by_ref: |entity: &Self| &entity.#field,
+                    by_value: |entity: Self| entity.#field,
+                })
+                    .values();
+            }
+        })
+        .collect();
+
+    // The attributes as `(name, indices of the fields they are built from, expression)`.
+    let attr_exprs: Vec<_> = prefixed_field_names
+        .iter()
+        .zip(&options)
+        .zip(fields)
+        .enumerate()
+        .filter_map(|(i, ((name, options), field))| {
+            let name_str = &options.name;
+            if options.component.is_some() {
+                // All components of an attribute are written at once, at the first of its fields.
+                let group = groups.iter().find(|g| g.first_field == i)?;
+                let components = group.fields.iter().map(|&f| &prefixed_field_names[f]);
+                let expr = quote! {
+                    houdini_node::RawAttribute::from_components(vec![
+                        #(houdini_node::generate_to_attr(#components)),*
+                    ])?
+                };
+                return Some((name_str, group.fields.clone(), expr));
+            }
+            let expr = match &options.tuple_size {
+                _ if options.parse_string => {
+                    let ty = &field.ty;
+                    quote! { houdini_node::generate_to_string_attr::<#ty, _>(#name) }
+                }
+                _ if options.json => {
+                    let ty = &field.ty;
+                    quote! { houdini_node::generate_to_json_attr::<#ty, _>(#name, #name_str)? }
+                }
+                Some(tuple_size) => quote! {
+                    houdini_node::generate_to_attr_resized(#name, #tuple_size)?
+                },
+                None => quote! { houdini_node::generate_to_attr(#name) },
+            };
+            let expr = match &options.semantic {
+                Some(semantic) => quote! {
+                    #expr.with_semantic(houdini_node::AttrSemantic::#semantic)
+                },
+                None => expr,
+            };
+            Some((name_str, vec![i], expr))
+        })
+        .collect();
+
+    let hashmap_entries: Vec<_> = attr_exprs
+        .iter()
+        .map(|(name_str, _, expr)| quote! { (#name_str, #expr) })
+        .collect();
+
+    // Only collects the fields that the requested attribute is built from.
+    let single_attr_arms: Vec<_> = attr_exprs
         .iter()
-        .zip(attr_names.iter())
-        .map(|(name, name_str)| {
-            quote! { (#name_str, houdini_node::generate_to_attr(#name)) }
+        .map(|(name_str, field_indices, expr)| {
+            let bindings = field_indices.iter().map(|&f| &ref_bindings[f]);
+            quote! {
+                #name_str => {
+                    #(#bindings)*
+                    Ok(Some(#expr))
+                }
+            }
         })
         .collect();
 
     let generated = quote! {
         impl houdini_node::OutAttrs for #name {
-            fn into_attr(entities: Vec<Self>) -> ::std::collections::HashMap<&'static str, houdini_node::RawAttribute> {
+            const OUT_ATTRS: &'static [(&'static str, usize)] = &[#(#attr_list),*];
+
+            fn into_attr(entities: Vec<Self>) -> houdini_node::Result<::std::collections::BTreeMap<&'static str, houdini_node::RawAttribute>> {
                 let #multiunzip_pattern: (#multiunzip_types) =
                     houdini_node::itertools::multiunzip(entities.into_iter().map(#entity_map));
 
-                std::collections::HashMap::from([
+                Ok(std::collections::BTreeMap::from([
+                    #(#hashmap_entries),*
+                ]))
+            }
+
+            // The bound has a lifetime, so that it is only checked where the method is used and
+            // entities that aren't `Clone` can still derive `OutAttrs`.
+            fn into_attr_ref(entities: &[Self]) -> houdini_node::Result<::std::collections::BTreeMap<&'static str, houdini_node::RawAttribute>>
+            where
+                for<'a> Self: Clone,
+            {
+                use houdini_node::derive_support::{Borrowed as _, Cloned as _};
+                #(#ref_bindings)*
+
+                Ok(std::collections::BTreeMap::from([
                     #(#hashmap_entries),*
-                ])
+                ]))
+            }
+
+            fn single_attr_ref(entities: &[Self], name: &str) -> houdini_node::Result<Option<houdini_node::RawAttribute>>
+            where
+                for<'a> Self: Clone,
+            {
+                use houdini_node::derive_support::{Borrowed as _, Cloned as _};
+                match name {
+                    #(#single_attr_arms)*
+                    _ => Ok(None),
+                }
             }
         }
     };
-    generated.into()
-}
-
-fn get_attr_name(field: &syn::Field) -> String {
-    // Check for #[attr(name = "custom_name")] attribute
-    for attr in &field.attrs {
-        if attr.path().is_ident("attr") {
-            if let Meta::List(meta_list) = &attr.meta {
-                // Parse name = "value" format
-                if let Ok(Meta::NameValue(name_value)) = syn::parse2(meta_list.tokens.clone()) {
-                    if name_value.path.is_ident("name") {
-                        if let Expr::Lit(ExprLit {
-                            lit: Lit::Str(lit_str),
-                            ..
-                        }) = name_value.value
-                        {
-                            return lit_str.value();
-                        }
+    Ok(generated)
+}
+
+fn impl_prim_topology(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let field = find_field_by_attr_name(ast, "vertices")?;
+
+    let generated = quote! {
+        impl houdini_node::PrimTopology for #name {
+            fn vertices(&self) -> &[usize] {
+                &self.#field
+            }
+
+            fn vertices_mut(&mut self) -> &mut Vec<usize> {
+                &mut self.#field
+            }
+        }
+    };
+    Ok(generated)
+}
+
+fn impl_vertex_topology(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+    let field = find_field_by_attr_name(ast, "ptnum")?;
+
+    let generated = quote! {
+        impl houdini_node::VertexTopology for #name {
+            fn ptnum(&self) -> usize {
+                self.#field
+            }
+
+            fn ptnum_mut(&mut self) -> &mut usize {
+                &mut self.#field
+            }
+        }
+    };
+    Ok(generated)
+}
+
+fn impl_point_position(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &ast.ident;
+
+    let mut field = None;
+    for f in named_fields(ast) {
+        if FieldOptions::parse(f)?.position {
+            field = f.ident.as_ref();
+        }
+    }
+    let Some(field) = field else {
+        return Err(syn::Error::new_spanned(
+            &ast.ident,
+            "Missing field marked with `#[attr(position)]`",
+        ));
+    };
+
+    let generated = quote! {
+        impl houdini_node::PointPosition for #name {
+            fn position(&self) -> houdini_node::glam::Vec3 {
+                self.#field
+            }
+        }
+
+        impl houdini_node::PointPositionMut for #name {
+            fn position_mut(&mut self) -> &mut houdini_node::glam::Vec3 {
+                &mut self.#field
+            }
+        }
+    };
+    Ok(generated)
+}
+
+/// Finds the field that maps to the given (pseudo-)attribute name.
+fn find_field_by_attr_name<'a>(
+    ast: &'a DeriveInput,
+    attr_name: &str,
+) -> syn::Result<&'a syn::Ident> {
+    for field in named_fields(ast) {
+        if FieldOptions::parse(field)?.name == attr_name {
+            return Ok(field.ident.as_ref().unwrap());
+        }
+    }
+
+    Err(syn::Error::new_spanned(
+        &ast.ident,
+        format!("Missing field for the `{attr_name}` attribute"),
+    ))
+}
+
+fn named_fields(ast: &DeriveInput) -> &Punctuated<syn::Field, Token![,]> {
+    match &ast.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => panic!("Only named fields are supported"),
+        },
+        _ => panic!("Only structs are supported"),
+    }
+}
+
+/// Rejects fields that map to names the crate uses for pseudo-attributes. The prim `points`
+/// attribute is only written, so it's only checked for output. Prim structs are recognized by
+/// their `vertices` field.
+fn check_reserved_names(
+    fields: &Punctuated<syn::Field, Token![,]>,
+    options: &[FieldOptions],
+    output: bool,
+) -> syn::Result<()> {
+    let is_prim = options.iter().any(|o| o.name == "vertices");
+    for (field, options) in fields.iter().zip(options) {
+        if options.name == "__elemnum" {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`__elemnum` is reserved for the element numbers of the input",
+            ));
+        }
+        if output && is_prim && options.name == "points" {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`points` is reserved on prims, it is written in place of `vertices`",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The `(name, tuple size)` entries of the `IN_ATTRS` and `OUT_ATTRS` listings. Attributes that
+/// are split into components are listed once, at the position of their first field.
+fn attr_list(
+    fields: &Punctuated<syn::Field, Token![,]>,
+    options: &[FieldOptions],
+    groups: &[ComponentGroup],
+    type_len: impl Fn(&syn::Type) -> proc_macro2::TokenStream,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .iter()
+        .zip(options)
+        .enumerate()
+        .filter_map(|(i, (field, options))| {
+            let name = &options.name;
+            let tuple_size = if options.component.is_some() {
+                let size = groups.iter().find(|g| g.first_field == i)?.tuple_size;
+                quote! { #size }
+            } else if options.parse_string || options.json {
+                quote! { 1 }
+            } else if let Some(tuple_size) = &options.tuple_size {
+                quote! { #tuple_size }
+            } else {
+                type_len(&field.ty)
+            };
+            Some(quote! { (#name, #tuple_size) })
+        })
+        .collect()
+}
+
+/// The fields that each read one component of the same attribute via `#[attr(component = N)]`.
+struct ComponentGroup {
+    name: String,
+    /// Holds the removed attribute while its components are read.
+    ident: syn::Ident,
+    /// Field indices, ordered by component.
+    fields: Vec<usize>,
+    first_field: usize,
+    /// One more than the highest component that is read.
+    tuple_size: usize,
+}
+
+impl ComponentGroup {
+    /// With `allow_gaps`, only some of the components need to have a field, which is fine for
+    /// reading, but not for writing.
+    fn collect(
+        fields: &Punctuated<syn::Field, Token![,]>,
+        options: &[FieldOptions],
+        allow_gaps: bool,
+    ) -> syn::Result<Vec<Self>> {
+        let mut groups: Vec<(ComponentGroup, Vec<(usize, usize)>)> = Vec::new();
+        for (i, options) in options.iter().enumerate() {
+            let Some(component) = options.component else {
+                continue;
+            };
+            match groups.iter_mut().find(|(g, _)| g.name == options.name) {
+                Some((_, components)) => components.push((component, i)),
+                None => {
+                    let group = ComponentGroup {
+                        name: options.name.clone(),
+                        ident: format_ident!("c_{}", groups.len()),
+                        fields: Vec::new(),
+                        first_field: i,
+                        tuple_size: 0,
+                    };
+                    groups.push((group, vec![(component, i)]));
+                }
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(mut group, mut components)| {
+                components.sort();
+                for (expected, &(component, field)) in components.iter().enumerate() {
+                    let duplicate = expected > 0 && components[expected - 1].0 == component;
+                    if duplicate && allow_gaps {
+                        return Err(syn::Error::new_spanned(
+                            &fields[field],
+                            format!("Component {component} of `{}` is read twice", group.name),
+                        ));
+                    }
+                    if component != expected && !allow_gaps {
+                        return Err(syn::Error::new_spanned(
+                            &fields[field],
+                            format!(
+                                "The components of `{}` must be 0 to {} without gaps or duplicates",
+                                group.name,
+                                components.len() - 1
+                            ),
+                        ));
                     }
                 }
+                group.tuple_size = components.last().map_or(0, |&(component, _)| component + 1);
+                group.fields = components.into_iter().map(|(_, field)| field).collect();
+                Ok(group)
+            })
+            .collect()
+    }
+
+    fn find<'a>(groups: &'a [Self], name: &str) -> &'a syn::Ident {
+        &groups.iter().find(|g| g.name == name).unwrap().ident
+    }
+}
+
+/// Whether the type is written as an array attribute, i.e. a `Vec` or an optional `Vec`.
+fn is_array(ty: &syn::Type) -> bool {
+    let syn::Type::Path(path) = ty else {
+        return false;
+    };
+    let Some(last) = path.path.segments.last() else {
+        return false;
+    };
+    match &last.arguments {
+        _ if last.ident == "Vec" => true,
+        syn::PathArguments::AngleBracketed(args) if last.ident == "Option" => {
+            matches!(args.args.first(), Some(syn::GenericArgument::Type(inner)) if is_array(inner))
+        }
+        _ => false,
+    }
+}
+
+/// The value of a numeric literal like `-1` or `0.5`, `None` for other expressions.
+fn literal_value(expr: &syn::Expr) -> Option<f64> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) => int.base10_parse().ok(),
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Float(float),
+            ..
+        }) => float.base10_parse().ok(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => literal_value(expr).map(|v| -v),
+        syn::Expr::Paren(syn::ExprParen { expr, .. }) => literal_value(expr),
+        _ => None,
+    }
+}
+
+/// Options from the `#[attr(...)]` attributes of a struct.
+struct StructOptions {
+    /// The number of vertices every prim must have, from `prim_size = N`. Checked on read.
+    prim_size: Option<LitInt>,
+}
+
+impl StructOptions {
+    fn parse(ast: &DeriveInput) -> syn::Result<Self> {
+        let mut options = StructOptions { prim_size: None };
+
+        for attr in &ast.attrs {
+            if !attr.path().is_ident("attr") {
+                continue;
             }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("prim_size") {
+                    options.prim_size = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("unknown attribute option"));
+                }
+                Ok(())
+            })?;
         }
+
+        Ok(options)
     }
+}
+
+/// Options from the `#[attr(...)]` attributes of a field.
+struct FieldOptions {
+    /// The name of the attribute, from `name = "..."`. Falls back to the field name.
+    name: String,
+    /// Overrides the tuple size of the attribute, from `tuple_size = N`. Surplus components are
+    /// dropped on read and missing ones are padded on write.
+    tuple_size: Option<LitInt>,
+    /// Stores the value as text in a string attribute, from `parse_string`, or `bool_from_string`
+    /// for flags stored as `"true"`/`"false"` or `"1"`/`"0"`.
+    parse_string: bool,
+    /// Stores the value as JSON in a string attribute, from `json`.
+    json: bool,
+    /// Marks the field as the position of the point, from `position`.
+    position: bool,
+    /// Reads and writes a single component of the attribute, from `component = N`. The other
+    /// components are provided by other fields with the same attribute name. When reading, the
+    /// remaining components may also be left out.
+    component: Option<usize>,
+    /// Computes the value of every element from its index if the attribute is missing, from
+    /// `default_fn = "path"`. The function takes the index and returns the field type.
+    default_fn: Option<syn::Path>,
+    /// Sets the `typeinfo` metadata of the output attribute, from `semantic = "..."`. One of
+    /// `position`, `normal`, `vector` or `color`.
+    semantic: Option<syn::Ident>,
+    /// Clamps the values read from the input into the range, from `clamp = "min..=max"`.
+    clamp: Option<(syn::Expr, syn::Expr)>,
+    /// Fails on values outside of the `clamp` range instead, from `reject_out_of_range`.
+    reject_out_of_range: bool,
+}
+
+impl FieldOptions {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut options = FieldOptions {
+            name: field.ident.as_ref().unwrap().to_string(),
+            tuple_size: None,
+            parse_string: false,
+            json: false,
+            position: false,
+            component: None,
+            default_fn: None,
+            semantic: None,
+            clamp: None,
+            reject_out_of_range: false,
+        };
+
+        for attr in &field.attrs {
+            if !attr.path().is_ident("attr") {
+                continue;
+            }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    options.name = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("tuple_size") {
+                    options.tuple_size = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("parse_string")
+                    || meta.path.is_ident("bool_from_string")
+                {
+                    options.parse_string = true;
+                } else if meta.path.is_ident("json") {
+                    options.json = true;
+                } else if meta.path.is_ident("position") {
+                    options.position = true;
+                } else if meta.path.is_ident("component") {
+                    options.component = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                } else if meta.path.is_ident("semantic") {
+                    let semantic = meta.value()?.parse::<LitStr>()?;
+                    let variant = match semantic.value().as_str() {
+                        "position" => "Position",
+                        "normal" => "Normal",
+                        "vector" => "Vector",
+                        "color" => "Color",
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                semantic,
+                                "expected `position`, `normal`, `vector` or `color`",
+                            ));
+                        }
+                    };
+                    options.semantic = Some(format_ident!("{variant}", span = semantic.span()));
+                } else if meta.path.is_ident("clamp") {
+                    let range = meta.value()?.parse::<LitStr>()?;
+                    let syn::ExprRange {
+                        start: Some(start),
+                        limits: syn::RangeLimits::Closed(_),
+                        end: Some(end),
+                        ..
+                    } = range.parse()?
+                    else {
+                        return Err(syn::Error::new_spanned(range, "expected `min..=max`"));
+                    };
+                    // `clamp` panics on an empty range, so reject it here where that's possible.
+                    if let (Some(min), Some(max)) = (literal_value(&start), literal_value(&end))
+                        && min > max
+                    {
+                        return Err(syn::Error::new_spanned(
+                            range,
+                            "`clamp` range is empty, `min` must not be greater than `max`",
+                        ));
+                    }
+                    options.clamp = Some((*start, *end));
+                } else if meta.path.is_ident("reject_out_of_range") {
+                    options.reject_out_of_range = true;
+                } else if meta.path.is_ident("default_fn") {
+                    options.default_fn = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else {
+                    return Err(meta.error("unknown attribute option"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if options.parse_string && options.json {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`parse_string` cannot be combined with `json`",
+            ));
+        }
+        if (options.parse_string || options.json)
+            && let Some(tuple_size) = &options.tuple_size
+        {
+            return Err(syn::Error::new_spanned(
+                tuple_size,
+                "`tuple_size` cannot be combined with `parse_string` or `json`",
+            ));
+        }
+        if let Some(tuple_size) = &options.tuple_size
+            && is_array(&field.ty)
+        {
+            return Err(syn::Error::new_spanned(
+                tuple_size,
+                "`tuple_size` is only supported for non-array fields",
+            ));
+        }
+        if options.component.is_some()
+            && (options.parse_string || options.json || options.tuple_size.is_some())
+        {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`component` cannot be combined with `tuple_size`, `parse_string` or `json`",
+            ));
+        }
+
+        if options.reject_out_of_range && options.clamp.is_none() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`reject_out_of_range` requires a `clamp` range",
+            ));
+        }
+        if let (Some(default_fn), Some(_)) = (&options.default_fn, options.component) {
+            return Err(syn::Error::new_spanned(
+                default_fn,
+                "`default_fn` cannot be combined with `component`",
+            ));
+        }
 
-    // Fall back to field name
-    field.ident.as_ref().unwrap().to_string()
+        Ok(options)
+    }
 }
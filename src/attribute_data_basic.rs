@@ -1,7 +1,8 @@
 use crate::{
     FromAttributeData, FromAttributeDataSource, IntoAttributeData, IntoAttributeDataSource,
-    RawAttribute, RawAttributeData, Result,
+    PrimVertices, RawAttribute, RawAttributeData, Result,
 };
+use glam::Vec3;
 use itertools::Itertools;
 use std::iter;
 
@@ -50,11 +51,31 @@ macro_rules! impl_attribute_data_source {
 impl_attribute_data_source!(f32, Float, float);
 impl_attribute_data_source!(Vec<f32>, FloatArray, float_array);
 impl_attribute_data_source!(i32, Int, int);
+impl_attribute_data_source!(i64, Int64, int64);
+impl_attribute_data_source!(f64, Float64, float64);
 impl_attribute_data_source!(Vec<i32>, IntArray, int_array);
 impl_attribute_data_source!(String, String, string);
 impl_attribute_data_source!(Vec<String>, StringArray, string_array);
 impl_attribute_data_source!(usize, Index, index);
-impl_attribute_data_source!(Vec<usize>, PrimVertex, prim_vertex);
+impl_attribute_data_source!(PrimVertices, PrimVertex, prim_vertex);
+
+/// Prim vertex lists as plain `Vec`s, as used by the `PrimTopology` derive, so that entities are
+/// the same with and without the `smallvec` feature.
+#[cfg(feature = "smallvec")]
+impl FromAttributeData for Vec<usize> {
+    type DataType = PrimVertices;
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(PrimVertices::into_vec)
+    }
+}
+
+#[cfg(feature = "smallvec")]
+impl IntoAttributeData for Vec<usize> {
+    type DataType = PrimVertices;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(PrimVertices::from_vec)
+    }
+}
 
 macro_rules! impl_touple_attribute_data_source {
     ($type:ty, $variant:ident, $method:ident) => {
@@ -76,9 +97,41 @@ macro_rules! impl_touple_attribute_data_source {
 
 impl_touple_attribute_data_source!(f32, Float, float);
 impl_touple_attribute_data_source!(i32, Int, int);
+impl_touple_attribute_data_source!(i64, Int64, int64);
+impl_touple_attribute_data_source!(f64, Float64, float64);
 impl_touple_attribute_data_source!(String, String, string);
 impl_touple_attribute_data_source!(usize, Index, index);
 
+/// A fixed set of vectors per element, stored as a single float attribute with `3 * N` components.
+impl<const N: usize> FromAttributeDataSource for [Vec3; N] {
+    const LEN: usize = 3 * N;
+    fn from_attr_data(data: RawAttribute) -> Result<impl Iterator<Item = Self>> {
+        let mut vectors = into_array_iter(data.data.float()?).map(Vec3::from_array);
+        Ok(iter::from_fn(move || vectors.next_array()))
+    }
+}
+
+impl<const N: usize> IntoAttributeDataSource for [Vec3; N] {
+    const LEN: usize = 3 * N;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> RawAttributeData {
+        RawAttributeData::Float(data.flatten().flat_map(<[f32; 3]>::from).collect())
+    }
+}
+
+impl<const N: usize> FromAttributeData for [Vec3; N] {
+    type DataType = Self;
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data
+    }
+}
+
+impl<const N: usize> IntoAttributeData for [Vec3; N] {
+    type DataType = Self;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data
+    }
+}
+
 fn into_array_iter<T, const N: usize>(v: Vec<T>) -> impl Iterator<Item = [T; N]> {
     let mut v = v.into_iter();
     iter::from_fn(move || v.next_array())
@@ -1,10 +1,21 @@
 //! Extra high level attribute types that can be used for fields on the derive macro.
 
-use crate::{ErrContext, FromAttributeData, IntoAttributeData, RawAttribute};
-use glam::{Mat2, Mat3, Mat4, Quat, Vec2, Vec3, Vec4};
+use crate::{
+    ErrContext, Error, FromAttributeData, Geometry, IntoAttributeData, RawAttribute,
+    RawAttributeData, load_from_attr,
+};
+use glam::{
+    DAffine3, DMat4, I16Vec3, I64Vec3, IVec3, Mat2, Mat3, Mat4, Quat, U16Vec3, Vec2, Vec3, Vec4,
+};
 use itertools::Either;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
 // *****************************************
 
+/// Optional attributes: `None` for all entities if the attribute is missing, as many as the other
+/// attributes of the entity have. A present attribute is read like `T`, so an empty string stays
+/// `Some("")` and never becomes `None`. It must have the same length as the other attributes.
 impl<T: FromAttributeData> FromAttributeData for Option<T> {
     type DataType = T::DataType;
 
@@ -20,6 +31,12 @@ impl<T: FromAttributeData> FromAttributeData for Option<T> {
         err_context: ErrContext,
     ) -> crate::Result<impl Iterator<Item = Self>> {
         match attr {
+            Some(attr) if attr.num_elements() != num_elements => {
+                Err(Error::InvalidAttributeLength {
+                    expected: num_elements,
+                    actual: attr.num_elements(),
+                })
+            }
             Some(attr) => Ok(Either::Left(
                 T::from_attr_data_raw(Some(attr), num_elements, attr_name, err_context)?.map(Some),
             )),
@@ -32,6 +49,64 @@ impl<T: FromAttributeData> FromAttributeData for Option<T> {
 
 // *****************************************
 
+// Borrowing `&str` straight from the input is not possible yet, as the attributes are moved out
+// of the `RawAttribute` and the entities don't have a lifetime that could tie them to it.
+impl FromAttributeData for Box<str> {
+    type DataType = String;
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(String::into_boxed_str)
+    }
+}
+
+impl IntoAttributeData for Box<str> {
+    type DataType = String;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(String::from)
+    }
+}
+
+/// Always owned on read. On write, borrowed values (like string literals) are copied into the
+/// output, owned ones are moved.
+impl FromAttributeData for Cow<'static, str> {
+    type DataType = String;
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(Cow::Owned)
+    }
+}
+
+impl IntoAttributeData for Cow<'static, str> {
+    type DataType = String;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(Cow::into_owned)
+    }
+}
+
+/// Interned on read: equal values of the same attribute share one allocation, which saves a lot
+/// of memory for attributes with few distinct values, like material names.
+impl FromAttributeData for Arc<str> {
+    type DataType = String;
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        let mut interned = HashSet::<Arc<str>>::new();
+        data.map(move |value| match interned.get(value.as_str()) {
+            Some(shared) => shared.clone(),
+            None => {
+                let shared: Arc<str> = value.into();
+                interned.insert(shared.clone());
+                shared
+            }
+        })
+    }
+}
+
+impl IntoAttributeData for Arc<str> {
+    type DataType = String;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(|value| value.to_string())
+    }
+}
+
+// *****************************************
+
 impl FromAttributeData for bool {
     type DataType = i32;
     fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
@@ -46,6 +121,52 @@ impl IntoAttributeData for bool {
     }
 }
 
+// *****************************************
+// Homogeneous float tuples are stored like the equally sized float arrays. Tuples with mixed
+// types are not supported, as an attribute only has a single data type.
+
+impl FromAttributeData for (f32, f32) {
+    type DataType = [f32; 2];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(Self::from)
+    }
+}
+
+impl IntoAttributeData for (f32, f32) {
+    type DataType = [f32; 2];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(Into::into)
+    }
+}
+
+impl FromAttributeData for (f32, f32, f32) {
+    type DataType = [f32; 3];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(Self::from)
+    }
+}
+
+impl IntoAttributeData for (f32, f32, f32) {
+    type DataType = [f32; 3];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(Into::into)
+    }
+}
+
+impl FromAttributeData for (f32, f32, f32, f32) {
+    type DataType = [f32; 4];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(Self::from)
+    }
+}
+
+impl IntoAttributeData for (f32, f32, f32, f32) {
+    type DataType = [f32; 4];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(Into::into)
+    }
+}
+
 // *****************************************
 
 impl FromAttributeData for Vec2 {
@@ -97,6 +218,338 @@ impl IntoAttributeData for Vec4 {
 
 // *****************************************
 
+/// A float vector whose width is only known at runtime, picked from the tuple size of the incoming
+/// attribute. Useful for attributes like `uv`, which can be 2 or 3 wide depending on the source.
+/// Only supported for reading, as all elements of an output attribute need the same width.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GlamVec {
+    Vec2(Vec2),
+    Vec3(Vec3),
+    Vec4(Vec4),
+}
+
+impl GlamVec {
+    pub fn tuple_size(&self) -> usize {
+        match self {
+            GlamVec::Vec2(_) => 2,
+            GlamVec::Vec3(_) => 3,
+            GlamVec::Vec4(_) => 4,
+        }
+    }
+
+    /// Pads missing components with 0, drops `w` of a `Vec4`.
+    pub fn to_vec3(self) -> Vec3 {
+        match self {
+            GlamVec::Vec2(v) => v.extend(0.0),
+            GlamVec::Vec3(v) => v,
+            GlamVec::Vec4(v) => v.truncate(),
+        }
+    }
+}
+
+impl FromAttributeData for GlamVec {
+    type DataType = [f32; 3];
+
+    /// Only used for 3-wide data. The loader goes through `from_attr_data_raw`, which picks the
+    /// variant from the tuple size.
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(|v| GlamVec::Vec3(v.into()))
+    }
+
+    fn from_attr_data_raw(
+        attr: Option<RawAttribute>,
+        num_elements: usize,
+        attr_name: &'static str,
+        err_context: ErrContext,
+    ) -> crate::Result<impl Iterator<Item = Self>> {
+        let values: Vec<_> = match attr.as_ref().map(|attr| attr.tuple_size) {
+            Some(2) => load_from_attr(attr, num_elements, attr_name, err_context)?
+                .map(GlamVec::Vec2)
+                .collect(),
+            Some(4) => load_from_attr(attr, num_elements, attr_name, err_context)?
+                .map(GlamVec::Vec4)
+                .collect(),
+            // Everything else, including a missing attribute, errors like a `Vec3` field.
+            _ => load_from_attr(attr, num_elements, attr_name, err_context)?
+                .map(GlamVec::Vec3)
+                .collect(),
+        };
+        Ok(values.into_iter())
+    }
+}
+
+/// Texture coordinates as a `Vec2`. Houdini usually stores `uv` 3 wide with an unused `w`, so both
+/// 2 and 3 wide attributes are read, dropping `w`, and the output is 3 wide with `w = 0`. For a
+/// plain `Vec2` field, `#[attr(tuple_size = 3)]` does the same, but only accepts 3 wide input.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Uv(pub Vec2);
+
+impl FromAttributeData for Uv {
+    type DataType = [f32; 3];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(|[u, v, _]| Uv(Vec2::new(u, v)))
+    }
+
+    fn from_attr_data_raw(
+        attr: Option<RawAttribute>,
+        num_elements: usize,
+        attr_name: &'static str,
+        err_context: ErrContext,
+    ) -> crate::Result<impl Iterator<Item = Self>> {
+        let attr = match attr {
+            Some(mut attr) if attr.tuple_size == 2 => {
+                attr.resize_tuples(3)?;
+                Some(attr)
+            }
+            attr => attr,
+        };
+        Ok(
+            load_from_attr::<Vec3>(attr, num_elements, attr_name, err_context)?
+                .map(|uvw| Uv(uvw.truncate())),
+        )
+    }
+}
+
+impl IntoAttributeData for Uv {
+    type DataType = [f32; 3];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(|Uv(uv)| uv.extend(0.0).into())
+    }
+}
+
+/// A float vector whose width is only known at runtime, like a feature vector with one component
+/// per input channel. Reads float attributes of any tuple size, one element per tuple. On output,
+/// the tuple size is the width of the widest element, at least 1. Narrower elements are padded
+/// with zeros.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DynVec(pub Vec<f32>);
+
+impl FromAttributeData for DynVec {
+    type DataType = f32;
+
+    /// Only used for 1-wide data. The loader goes through `from_attr_data_raw`, which splits the
+    /// data by the tuple size.
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(|v| DynVec(vec![v]))
+    }
+
+    fn from_attr_data_raw(
+        attr: Option<RawAttribute>,
+        num_elements: usize,
+        attr_name: &'static str,
+        err_context: ErrContext,
+    ) -> crate::Result<impl Iterator<Item = Self>> {
+        let (tuple_size, attr) = match attr {
+            Some(mut attr) => {
+                let tuple_size = attr.tuple_size;
+                if tuple_size == 0 {
+                    return Err(Error::InvalidAttributeLength {
+                        expected: 1,
+                        actual: 0,
+                    });
+                }
+                // Read as a single component, then regrouped below.
+                attr.tuple_size = 1;
+                (tuple_size, Some(attr))
+            }
+            None => (1, None),
+        };
+        let values: Vec<f32> =
+            load_from_attr(attr, num_elements, attr_name, err_context)?.collect();
+        Ok(values
+            .chunks_exact(tuple_size)
+            .map(|tuple| DynVec(tuple.to_vec()))
+            .collect::<Vec<_>>()
+            .into_iter())
+    }
+}
+
+impl IntoAttributeData for DynVec {
+    type DataType = f32;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.flat_map(|v| v.0)
+    }
+
+    fn into_attr_raw(data: impl Iterator<Item = Self>) -> RawAttribute {
+        let elements: Vec<_> = data.collect();
+        let tuple_size = elements.iter().map(|v| v.0.len()).max().unwrap_or(0).max(1);
+        let values = elements
+            .into_iter()
+            .flat_map(|v| {
+                let padding = tuple_size - v.0.len();
+                v.0.into_iter().chain(std::iter::repeat_n(0.0, padding))
+            })
+            .collect();
+        RawAttribute {
+            tuple_size,
+            data: RawAttributeData::Float(values),
+            meta: None,
+        }
+    }
+}
+
+// *****************************************
+// Houdini only has 32-bit ints. The narrower vectors are stored widened and range-checked on read,
+// 64-bit vectors use the separate `Int64` data, which also accepts (and widens) plain ints.
+
+impl FromAttributeData for IVec3 {
+    type DataType = [i32; 3];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(Self::from)
+    }
+}
+
+impl IntoAttributeData for IVec3 {
+    type DataType = [i32; 3];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(Into::into)
+    }
+}
+
+impl FromAttributeData for I64Vec3 {
+    type DataType = [i64; 3];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(Self::from)
+    }
+}
+
+impl IntoAttributeData for I64Vec3 {
+    type DataType = [i64; 3];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(Into::into)
+    }
+}
+
+macro_rules! impl_narrow_int_vec {
+    ($type:ty, $component:ty) => {
+        impl FromAttributeData for $type {
+            type DataType = [i32; 3];
+
+            /// Truncates out of range values. The loader goes through `from_attr_data_raw`,
+            /// which rejects them instead.
+            fn from_attr_data(
+                data: impl Iterator<Item = Self::DataType>,
+            ) -> impl Iterator<Item = Self> {
+                data.map(|v| Self::from_array(v.map(|c| c as $component)))
+            }
+
+            fn from_attr_data_raw(
+                attr: Option<RawAttribute>,
+                num_elements: usize,
+                attr_name: &'static str,
+                err_context: ErrContext,
+            ) -> crate::Result<impl Iterator<Item = Self>> {
+                load_from_attr::<IVec3>(attr, num_elements, attr_name, err_context)?
+                    .map(|v| {
+                        Self::try_from(v).map_err(|_| Error::IntOutOfRange {
+                            attr: attr_name,
+                            value: v
+                                .to_array()
+                                .into_iter()
+                                .find(|&c| <$component>::try_from(c).is_err())
+                                .unwrap_or_default()
+                                .into(),
+                            target: stringify!($component),
+                        })
+                    })
+                    .collect::<crate::Result<Vec<_>>>()
+                    .map(Vec::into_iter)
+            }
+        }
+
+        impl IntoAttributeData for $type {
+            type DataType = [i32; 3];
+            fn into_attr_data(
+                data: impl Iterator<Item = Self>,
+            ) -> impl Iterator<Item = Self::DataType> {
+                data.map(|v| v.to_array().map(i32::from))
+            }
+        }
+    };
+}
+
+impl_narrow_int_vec!(I16Vec3, i16);
+impl_narrow_int_vec!(U16Vec3, u16);
+
+// *****************************************
+
+/// The index of a point, like the target of a constraint. Written as index data, but also read
+/// from int attributes, which is how Houdini sends them. Negative ints are rejected, so use plain
+/// ints if `-1` means "no point".
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PointRef(pub usize);
+
+impl PointRef {
+    /// Returns the index if it refers to one of `point_count` points.
+    pub fn checked(self, point_count: usize) -> crate::Result<usize> {
+        if self.0 < point_count {
+            Ok(self.0)
+        } else {
+            Err(Error::InvalidPointRef {
+                index: self.0,
+                point_count,
+            })
+        }
+    }
+}
+
+impl FromAttributeData for PointRef {
+    type DataType = usize;
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(PointRef)
+    }
+
+    fn from_attr_data_raw(
+        attr: Option<RawAttribute>,
+        num_elements: usize,
+        attr_name: &'static str,
+        err_context: ErrContext,
+    ) -> crate::Result<impl Iterator<Item = Self>> {
+        let attr = match attr {
+            Some(RawAttribute {
+                tuple_size,
+                data: RawAttributeData::Int(values),
+                meta,
+            }) => {
+                let values = values
+                    .into_iter()
+                    .map(|v| {
+                        usize::try_from(v).map_err(|_| Error::IntOutOfRange {
+                            attr: attr_name,
+                            value: v.into(),
+                            target: "PointRef",
+                        })
+                    })
+                    .collect::<crate::Result<_>>()?;
+                Some(RawAttribute {
+                    tuple_size,
+                    data: RawAttributeData::Index(values),
+                    meta,
+                })
+            }
+            attr => attr,
+        };
+        Ok(load_from_attr::<usize>(attr, num_elements, attr_name, err_context)?.map(PointRef))
+    }
+}
+
+impl IntoAttributeData for PointRef {
+    type DataType = usize;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(|r| r.0)
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Checks that all `refs` refer to existing points, see [`PointRef::checked`].
+    pub fn check_point_refs(&self, refs: impl IntoIterator<Item = PointRef>) -> crate::Result<()> {
+        refs.into_iter()
+            .try_for_each(|r| r.checked(self.points.len()).map(|_| ()))
+    }
+}
+
+// *****************************************
+
 impl FromAttributeData for Quat {
     type DataType = [f32; 4];
     fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
@@ -113,6 +566,27 @@ impl IntoAttributeData for Quat {
 
 // *****************************************
 
+/// A quaternion that is normalized when read. Houdini doesn't guarantee that stored quaternions
+/// are normalized, unlike the plain [`Quat`] which is read as-is.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct NormalizedQuat(pub Quat);
+
+impl FromAttributeData for NormalizedQuat {
+    type DataType = [f32; 4];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        Quat::from_attr_data(data).map(|q| Self(q.normalize()))
+    }
+}
+
+impl IntoAttributeData for NormalizedQuat {
+    type DataType = [f32; 4];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        Quat::into_attr_data(data.map(|q| q.0))
+    }
+}
+
+// *****************************************
+
 impl FromAttributeData for Mat2 {
     type DataType = [f32; 4];
     fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
@@ -158,3 +632,718 @@ impl IntoAttributeData for Mat4 {
         data.map(|m| m.to_cols_array())
     }
 }
+
+// *****************************************
+
+/// A [`Mat3`] that is stored row-major in the attribute, as Houdini does in some contexts. The
+/// plain [`Mat3`] is stored column-major.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct RowMajorMat3(pub Mat3);
+
+impl FromAttributeData for RowMajorMat3 {
+    type DataType = [f32; 9];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        Mat3::from_attr_data(data).map(|m| Self(m.transpose()))
+    }
+}
+
+impl IntoAttributeData for RowMajorMat3 {
+    type DataType = [f32; 9];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        Mat3::into_attr_data(data.map(|m| m.0.transpose()))
+    }
+}
+
+// *****************************************
+
+/// A [`Mat4`] that is stored row-major in the attribute, as Houdini does in some contexts. The
+/// plain [`Mat4`] is stored column-major.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct RowMajorMat4(pub Mat4);
+
+impl FromAttributeData for RowMajorMat4 {
+    type DataType = [f32; 16];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        Mat4::from_attr_data(data).map(|m| Self(m.transpose()))
+    }
+}
+
+impl IntoAttributeData for RowMajorMat4 {
+    type DataType = [f32; 16];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        Mat4::into_attr_data(data.map(|m| m.0.transpose()))
+    }
+}
+
+// *****************************************
+
+// Double precision transforms use the separate `Float64` data, with the same column-major layout
+// as [`Mat4`].
+impl FromAttributeData for DMat4 {
+    type DataType = [f64; 16];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(|arr| Self::from_cols_array(&arr))
+    }
+}
+
+impl IntoAttributeData for DMat4 {
+    type DataType = [f64; 16];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(|m| m.to_cols_array())
+    }
+}
+
+/// Stored as a full 4x4 matrix like [`DMat4`], the last row is dropped on read.
+impl FromAttributeData for DAffine3 {
+    type DataType = [f64; 16];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        DMat4::from_attr_data(data).map(Self::from_mat4)
+    }
+}
+
+impl IntoAttributeData for DAffine3 {
+    type DataType = [f64; 16];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        DMat4::into_attr_data(data.map(DMat4::from))
+    }
+}
+
+// *****************************************
+
+/// Variable-length lists of fixed-size tuples, stored as float arrays with a stride of `N`: the
+/// list `[[1, 2, 3], [4, 5, 6]]` becomes the array `[1, 2, 3, 4, 5, 6]`. Reading fails if the
+/// length of an array isn't a multiple of `N`.
+impl<const N: usize> FromAttributeData for Vec<[f32; N]> {
+    type DataType = Vec<f32>;
+
+    /// Drops incomplete trailing tuples, [`FromAttributeData::from_attr_data_raw`] rejects them.
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(|values| {
+            values
+                .chunks_exact(N)
+                .map(|tuple| std::array::from_fn(|i| tuple[i]))
+                .collect()
+        })
+    }
+
+    fn from_attr_data_raw(
+        attr: Option<RawAttribute>,
+        num_elements: usize,
+        attr_name: &'static str,
+        err_context: ErrContext,
+    ) -> crate::Result<impl Iterator<Item = Self>> {
+        let lists = load_from_attr::<Vec<f32>>(attr, num_elements, attr_name, err_context)?
+            .map(|values| {
+                if N == 0 || !values.len().is_multiple_of(N) {
+                    return Err(Error::InvalidArrayStride {
+                        attr: attr_name,
+                        len: values.len(),
+                        stride: N,
+                    });
+                }
+                Ok(Self::from_attr_data(std::iter::once(values))
+                    .next()
+                    .unwrap())
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(lists.into_iter())
+    }
+}
+
+impl<const N: usize> IntoAttributeData for Vec<[f32; N]> {
+    type DataType = Vec<f32>;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(|tuples| tuples.into_iter().flatten().collect())
+    }
+}
+
+/// Stored like `Vec<[f32; 3]>`.
+impl FromAttributeData for Vec<Vec3> {
+    type DataType = Vec<f32>;
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        Vec::<[f32; 3]>::from_attr_data(data).map(|list| list.into_iter().map(Vec3::from).collect())
+    }
+
+    fn from_attr_data_raw(
+        attr: Option<RawAttribute>,
+        num_elements: usize,
+        attr_name: &'static str,
+        err_context: ErrContext,
+    ) -> crate::Result<impl Iterator<Item = Self>> {
+        Ok(
+            load_from_attr::<Vec<[f32; 3]>>(attr, num_elements, attr_name, err_context)?
+                .map(|list| list.into_iter().map(Vec3::from).collect()),
+        )
+    }
+}
+
+impl IntoAttributeData for Vec<Vec3> {
+    type DataType = Vec<f32>;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        Vec::<[f32; 3]>::into_attr_data(
+            data.map(|list| list.into_iter().map(<[f32; 3]>::from).collect()),
+        )
+    }
+}
+
+// *****************************************
+
+/// Types that can be stored as text in a string attribute, for fields marked with
+/// `#[attr(parse_string)]`. Vectors use comma-separated components like `"1.0,2.0,3.0"`.
+pub trait StringEncoded: Sized {
+    /// Returns `None` if the string is malformed.
+    fn parse_attr_string(s: &str) -> Option<Self>;
+    fn format_attr_string(&self) -> String;
+}
+
+/// Implements [`StringEncoded`] for a glam vector via its array conversions.
+macro_rules! impl_string_encoded_vec {
+    ($type:ty, $len:literal) => {
+        impl StringEncoded for $type {
+            fn parse_attr_string(s: &str) -> Option<Self> {
+                let mut components = [0.0; $len];
+                let mut parts = s.split(',');
+                for c in &mut components {
+                    *c = parts.next()?.trim().parse().ok()?;
+                }
+                parts
+                    .next()
+                    .is_none()
+                    .then(|| <$type>::from_array(components))
+            }
+
+            fn format_attr_string(&self) -> String {
+                self.to_array().map(|c| c.to_string()).join(",")
+            }
+        }
+    };
+}
+
+/// Flags stored in string attributes, for fields marked with `#[attr(bool_from_string)]`. Reads
+/// `"true"`/`"false"` and `"1"`/`"0"`, writes `"true"`/`"false"`.
+impl StringEncoded for bool {
+    fn parse_attr_string(s: &str) -> Option<Self> {
+        match s {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn format_attr_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl_string_encoded_vec!(Vec2, 2);
+impl_string_encoded_vec!(Vec3, 3);
+impl_string_encoded_vec!(Vec4, 4);
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{EntityKind, InAttrs, load_from_attr};
+    use std::collections::HashMap;
+
+    const CONTEXT: ErrContext = ErrContext {
+        input_index: 0,
+        entity: EntityKind::Point,
+    };
+
+    fn float_attr(tuple_size: usize, values: Vec<f32>) -> Option<RawAttribute> {
+        Some(RawAttribute {
+            tuple_size,
+            data: RawAttributeData::Float(values),
+            meta: None,
+        })
+    }
+
+    #[test]
+    fn empty_vs_missing_string() {
+        let attr = Some(RawAttribute {
+            tuple_size: 1,
+            data: RawAttributeData::String(vec!["".to_string(), "a".to_string()]),
+            meta: None,
+        });
+        let read: Vec<Option<String>> = load_from_attr(attr.clone(), 2, "name", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, vec![Some("".to_string()), Some("a".to_string())]);
+        let read: Vec<String> = load_from_attr(attr, 2, "name", CONTEXT).unwrap().collect();
+        assert_eq!(read, vec!["", "a"]);
+
+        let read: Vec<Option<String>> = load_from_attr(None, 2, "name", CONTEXT).unwrap().collect();
+        assert_eq!(read, vec![None, None]);
+        let err = load_from_attr::<String>(None, 2, "name", CONTEXT)
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::MissingAttr { attr: "name", .. }));
+    }
+
+    #[test]
+    fn strided_float_arrays() {
+        let lists: Vec<Vec<Vec3>> = vec![
+            vec![Vec3::X, Vec3::Y, Vec3::Z],
+            vec![],
+            vec![Vec3::new(1.0, 2.0, 3.0)],
+        ];
+        let attr = crate::generate_to_attr(lists.clone());
+        assert_eq!(
+            attr.data,
+            RawAttributeData::FloatArray(vec![
+                vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+                vec![],
+                vec![1.0, 2.0, 3.0],
+            ])
+        );
+
+        let read: Vec<Vec<Vec3>> = load_from_attr(Some(attr.clone()), 3, "controls", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, lists);
+        let read: Vec<Vec<[f32; 3]>> = load_from_attr(Some(attr), 3, "controls", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read[2], vec![[1.0, 2.0, 3.0]]);
+
+        let ragged = RawAttribute {
+            tuple_size: 1,
+            data: RawAttributeData::FloatArray(vec![vec![1.0, 2.0, 3.0, 4.0]]),
+            meta: None,
+        };
+        let err = load_from_attr::<Vec<Vec3>>(Some(ragged), 1, "controls", CONTEXT)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::InvalidArrayStride {
+                attr: "controls",
+                len: 4,
+                stride: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn boxed_str() {
+        let values: Vec<Box<str>> = vec!["a".into(), "".into()];
+        let attr = crate::generate_to_attr(values.clone());
+        assert_eq!(attr.data.kind(), crate::AttributeType::String);
+
+        let read: Vec<Box<str>> = load_from_attr(Some(attr), 2, "name", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, values);
+    }
+
+    #[test]
+    fn interned_str() {
+        let values: Vec<Arc<str>> = vec!["stone".into(), "wood".into(), "stone".into()];
+        let attr = crate::generate_to_attr(values.clone());
+        assert_eq!(attr.data.kind(), crate::AttributeType::String);
+
+        let read: Vec<Arc<str>> = load_from_attr(Some(attr), 3, "material", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, values);
+        assert!(Arc::ptr_eq(&read[0], &read[2]));
+        assert!(!Arc::ptr_eq(&read[0], &read[1]));
+    }
+
+    #[test]
+    fn cow_str() {
+        let values: Vec<Cow<'static, str>> = vec![Cow::Borrowed("a"), Cow::Owned("b".to_string())];
+        let attr = crate::generate_to_attr(values.clone());
+        assert_eq!(attr.data.kind(), crate::AttributeType::String);
+
+        let read: Vec<Cow<'static, str>> = load_from_attr(Some(attr), 2, "name", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, values);
+        assert!(matches!(read[0], Cow::Owned(_)));
+    }
+
+    #[test]
+    fn float_tuple() {
+        let values = vec![(1.0, 2.0), (3.0, 4.0)];
+        let attr = crate::generate_to_attr(values.clone());
+        assert_eq!(attr.tuple_size, 2);
+
+        let read: Vec<(f32, f32)> = load_from_attr(Some(attr), 2, "uv", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, values);
+    }
+
+    #[test]
+    fn glam_vec_by_tuple_size() {
+        #[derive(InAttrs)]
+        struct Point {
+            uv: GlamVec,
+        }
+
+        let read = |tuple_size: usize| {
+            let values = (0..tuple_size * 2).map(|i| i as f32).collect();
+            let attrs =
+                HashMap::from([("uv".to_string(), float_attr(tuple_size, values).unwrap())]);
+            Point::from_attr(attrs, CONTEXT).map(|points| points.map(|p| p.uv).collect::<Vec<_>>())
+        };
+
+        assert_eq!(read(2).unwrap()[1], GlamVec::Vec2(Vec2::new(2.0, 3.0)));
+
+        let uv = read(3).unwrap()[1];
+        assert_eq!(uv, GlamVec::Vec3(Vec3::new(3.0, 4.0, 5.0)));
+        assert_eq!(uv.tuple_size(), 3);
+
+        assert!(matches!(
+            read(5),
+            Err(Error::InvalidAttributeLength {
+                expected: 3,
+                actual: 5
+            })
+        ));
+    }
+
+    #[test]
+    fn point_refs() {
+        let refs = vec![PointRef(0), PointRef(2)];
+        let attr = crate::generate_to_attr(refs.clone());
+        assert_eq!(attr.data.kind(), crate::AttributeType::Index);
+        let read: Vec<PointRef> = load_from_attr(Some(attr), 2, "target", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, refs);
+
+        let ints = |values: Vec<i32>| {
+            Some(RawAttribute {
+                tuple_size: 1,
+                data: RawAttributeData::Int(values),
+                meta: None,
+            })
+        };
+        let read: Vec<PointRef> = load_from_attr(ints(vec![0, 2]), 2, "target", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, refs);
+        let err = load_from_attr::<PointRef>(ints(vec![0, -1]), 2, "target", CONTEXT)
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::IntOutOfRange { value: -1, .. }));
+
+        let geo: Geometry<()> = vec![(), ()].into();
+        assert_eq!(PointRef(1).checked(2).unwrap(), 1);
+        let err = geo.check_point_refs(read).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidPointRef {
+                index: 2,
+                point_count: 2
+            }
+        ));
+        geo.check_point_refs([PointRef(0), PointRef(1)]).unwrap();
+    }
+
+    #[test]
+    fn narrow_int_vectors() {
+        let values = vec![I16Vec3::new(i16::MIN, 0, i16::MAX)];
+        let attr = crate::generate_to_attr(values.clone());
+        assert_eq!(attr.data.kind(), crate::AttributeType::Int);
+        let read: Vec<I16Vec3> = load_from_attr(Some(attr), 1, "id", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, values);
+
+        let values = vec![U16Vec3::new(u16::MIN, 1, u16::MAX)];
+        let attr = crate::generate_to_attr(values.clone());
+        let read: Vec<U16Vec3> = load_from_attr(Some(attr), 1, "id", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, values);
+
+        let attr = || {
+            Some(RawAttribute {
+                tuple_size: 3,
+                data: RawAttributeData::Int(vec![0, -1, 65536]),
+                meta: None,
+            })
+        };
+        let err = load_from_attr::<U16Vec3>(attr(), 1, "id", CONTEXT)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::IntOutOfRange {
+                attr: "id",
+                value: -1,
+                target: "u16"
+            }
+        ));
+        let err = load_from_attr::<I16Vec3>(attr(), 1, "id", CONTEXT)
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::IntOutOfRange { value: 65536, .. }));
+    }
+
+    #[test]
+    fn double_transforms() {
+        let transform = DAffine3::from_scale_rotation_translation(
+            glam::DVec3::new(1.0, 2.0, 3.0),
+            glam::DQuat::from_rotation_y(0.3),
+            glam::DVec3::new(1.0e8 + 0.123456789, -2.5, 1.0e-9),
+        );
+        let attr = crate::generate_to_attr(vec![transform]);
+        assert_eq!(attr.data.kind(), crate::AttributeType::Float64);
+        assert_eq!(attr.tuple_size, 16);
+
+        // Through JSON, to check that the precision survives the transport.
+        let json = serde_json::to_string(&attr).unwrap();
+        let attr: RawAttribute = serde_json::from_str(&json).unwrap();
+        let read: Vec<DAffine3> = load_from_attr(Some(attr.clone()), 1, "transform", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, vec![transform]);
+        assert_eq!(read[0].translation.x, 1.0e8 + 0.123456789);
+
+        let read: Vec<DMat4> = load_from_attr(Some(attr), 1, "transform", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read[0], DMat4::from(transform));
+
+        // Same layout as the f32 matrix.
+        let doubles = DMat4::into_attr_data(std::iter::once(read[0]))
+            .next()
+            .unwrap();
+        let floats = Mat4::into_attr_data(std::iter::once(read[0].as_mat4()))
+            .next()
+            .unwrap();
+        assert_eq!(doubles.map(|v| v as f32), floats);
+    }
+
+    #[test]
+    fn int64_vectors() {
+        let values = vec![I64Vec3::new(i64::MIN, 0, i64::MAX)];
+        let attr = crate::generate_to_attr(values.clone());
+        assert_eq!(attr.data.kind(), crate::AttributeType::Int64);
+        let read: Vec<I64Vec3> = load_from_attr(Some(attr), 1, "id", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, values);
+
+        // Plain ints coming from Houdini are widened.
+        let attr = Some(RawAttribute {
+            tuple_size: 3,
+            data: RawAttributeData::Int(vec![i32::MIN, 0, i32::MAX]),
+            meta: None,
+        });
+        let read: Vec<I64Vec3> = load_from_attr(attr, 1, "id", CONTEXT).unwrap().collect();
+        assert_eq!(read[0], I64Vec3::new(i32::MIN.into(), 0, i32::MAX.into()));
+    }
+
+    #[test]
+    fn normalized_quat() {
+        let attr = || float_attr(4, vec![0.0, 0.0, 0.0, 2.0]);
+
+        let q: Vec<Quat> = load_from_attr(attr(), 1, "orient", CONTEXT)
+            .unwrap()
+            .collect();
+        assert!(!q[0].is_normalized());
+
+        let q: Vec<NormalizedQuat> = load_from_attr(attr(), 1, "orient", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(q[0].0, Quat::IDENTITY);
+    }
+
+    #[test]
+    fn row_major_mat4() {
+        // A translation by (1, 2, 3), written row-major.
+        #[rustfmt::skip]
+        let row_major = vec![
+            1.0, 0.0, 0.0, 1.0,
+            0.0, 1.0, 0.0, 2.0,
+            0.0, 0.0, 1.0, 3.0,
+            0.0, 0.0, 0.0, 1.0,
+        ];
+
+        let m: Vec<RowMajorMat4> =
+            load_from_attr(float_attr(16, row_major.clone()), 1, "transform", CONTEXT)
+                .unwrap()
+                .collect();
+        assert_eq!(
+            m[0].0.transform_point3(Vec3::ZERO),
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+
+        // Reading it as column-major silently applies the transpose instead.
+        let m: Vec<Mat4> =
+            load_from_attr(float_attr(16, row_major.clone()), 1, "transform", CONTEXT)
+                .unwrap()
+                .collect();
+        assert_eq!(m[0].transform_point3(Vec3::ZERO), Vec3::ZERO);
+
+        let out = crate::generate_to_attr(vec![RowMajorMat4(Mat4::from_translation(Vec3::new(
+            1.0, 2.0, 3.0,
+        )))]);
+        assert_eq!(out.data.float().unwrap(), row_major);
+    }
+
+    #[test]
+    fn vec3_arrays() {
+        let frames = vec![[Vec3::X, Vec3::Y], [Vec3::new(1.0, 2.0, 3.0), Vec3::Z]];
+        let attr = crate::generate_to_attr(frames.clone());
+        assert_eq!(attr.tuple_size, 6);
+        assert_eq!(attr.num_elements(), 2);
+
+        let read: Vec<[Vec3; 2]> = load_from_attr(Some(attr), 2, "frame", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, frames);
+
+        let err = load_from_attr::<[Vec3; 2]>(float_attr(3, vec![0.0; 6]), 2, "frame", CONTEXT)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::InvalidAttributeLength {
+                expected: 6,
+                actual: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn optional_vec3() {
+        #[derive(InAttrs)]
+        struct Point {
+            #[attr(name = "P")]
+            position: Vec3,
+            #[attr(name = "N")]
+            normal: Option<Vec3>,
+        }
+
+        let positions = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+        let read = |normals: Option<Vec<Vec3>>| {
+            let mut attrs = HashMap::from([
+                ("P".to_string(), crate::generate_to_attr(positions.clone())),
+                // Not read, so its length doesn't matter.
+                ("Cd".to_string(), crate::generate_to_attr(vec![Vec3::ONE])),
+            ]);
+            if let Some(normals) = normals {
+                attrs.insert("N".to_string(), crate::generate_to_attr(normals));
+            }
+            Point::from_attr(attrs, CONTEXT).map(|points| points.collect::<Vec<_>>())
+        };
+
+        let points = read(None).unwrap();
+        assert_eq!(
+            points.iter().map(|p| p.position).collect::<Vec<_>>(),
+            positions
+        );
+        assert!(points.iter().all(|p| p.normal.is_none()));
+
+        let normals = vec![Vec3::Z, Vec3::NEG_Z, Vec3::Y];
+        let points = read(Some(normals.clone())).unwrap();
+        assert_eq!(
+            points.iter().map(|p| p.normal).collect::<Vec<_>>(),
+            normals.into_iter().map(Some).collect::<Vec<_>>()
+        );
+
+        let err = read(Some(vec![Vec3::Z])).err().unwrap();
+        assert!(matches!(
+            err,
+            Error::InconsistentInAttr {
+                attr: "N",
+                expected: 3,
+                actual: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn only_optional_vec3() {
+        #[derive(InAttrs)]
+        struct Point {
+            #[attr(name = "N")]
+            normal: Option<Vec3>,
+            #[attr(name = "v")]
+            velocity: Option<Vec3>,
+        }
+
+        // The count comes from `P`, which isn't read.
+        let attrs = HashMap::from([(
+            "P".to_string(),
+            crate::generate_to_attr(vec![Vec3::ZERO, Vec3::X, Vec3::Y]),
+        )]);
+        let points: Vec<_> = Point::from_attr(attrs, CONTEXT).unwrap().collect();
+        assert_eq!(points.len(), 3);
+        assert!(
+            points
+                .iter()
+                .all(|p| p.normal.is_none() && p.velocity.is_none())
+        );
+
+        let attrs = HashMap::from([
+            (
+                "P".to_string(),
+                crate::generate_to_attr(vec![Vec3::ZERO; 3]),
+            ),
+            ("N".to_string(), crate::generate_to_attr(vec![Vec3::Z; 3])),
+        ]);
+        let points: Vec<_> = Point::from_attr(attrs, CONTEXT).unwrap().collect();
+        assert_eq!(points.len(), 3);
+        assert!(
+            points
+                .iter()
+                .all(|p| p.normal == Some(Vec3::Z) && p.velocity.is_none())
+        );
+    }
+
+    #[test]
+    fn uv_round_trip() {
+        let read = |attr| -> crate::Result<Vec<Uv>> {
+            Ok(load_from_attr(attr, 2, "uv", CONTEXT)?.collect())
+        };
+        let uvs = read(float_attr(3, vec![0.25, 0.5, 7.0, 1.0, 0.0, 7.0])).unwrap();
+        assert_eq!(uvs, vec![Uv(Vec2::new(0.25, 0.5)), Uv(Vec2::new(1.0, 0.0))]);
+        assert_eq!(read(float_attr(2, vec![0.25, 0.5, 1.0, 0.0])).unwrap(), uvs);
+
+        let attr = crate::generate_to_attr(uvs.clone());
+        assert_eq!(attr.tuple_size, 3);
+        assert_eq!(
+            attr.data,
+            RawAttributeData::Float(vec![0.25, 0.5, 0.0, 1.0, 0.0, 0.0])
+        );
+        assert_eq!(read(Some(attr)).unwrap(), uvs);
+    }
+
+    #[test]
+    fn dyn_vec_round_trip() {
+        let width = "0.5,1,2,4".split(',').count();
+        let features: Vec<_> = (0..3)
+            .map(|i| DynVec((0..width).map(|c| (i * width + c) as f32).collect()))
+            .collect();
+
+        let attr = crate::generate_to_attr(features.clone());
+        assert_eq!(attr.tuple_size, 4);
+        assert_eq!(attr.num_elements(), 3);
+
+        let read: Vec<DynVec> = load_from_attr(Some(attr), 3, "features", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, features);
+
+        let read: Vec<DynVec> =
+            load_from_attr(float_attr(2, vec![1.0, 2.0, 3.0, 4.0]), 2, "f", CONTEXT)
+                .unwrap()
+                .collect();
+        assert_eq!(read, vec![DynVec(vec![1.0, 2.0]), DynVec(vec![3.0, 4.0])]);
+        // Mixed widths are padded to the widest element.
+        let attr = crate::generate_to_attr(vec![DynVec(vec![1.0]), DynVec(vec![2.0, 3.0])]);
+        assert_eq!(attr.tuple_size, 2);
+        assert_eq!(attr.data, RawAttributeData::Float(vec![1.0, 0.0, 2.0, 3.0]));
+        assert_eq!(crate::generate_to_attr(Vec::<DynVec>::new()).tuple_size, 1);
+    }
+}
@@ -0,0 +1,486 @@
+//! A compact binary alternative to the JSON transport.
+//!
+//! The layout mirrors the JSON transport. Every payload starts with a header:
+//!
+//! | bytes | content                                                  |
+//! |-------|----------------------------------------------------------|
+//! | 4     | magic `HNGB`                                             |
+//! | 1     | byte order of everything that follows: `0` little, `1` big |
+//!
+//! Followed by the number of geometries (`u32`) and the geometries themselves. Each geometry
+//! consists of the points, vertices, prims and detail sections, in that order. A section is an
+//! attribute count (`u32`) followed by the attributes:
+//!
+//! - name: length (`u64`) followed by UTF-8 bytes
+//! - tuple size (`u64`)
+//! - data type tag (`u8`, see [`AttributeType`] in declaration order, starting at `0`)
+//! - data: element count (`u64`) followed by the elements. Floats are `f32` (`f64` for doubles),
+//!   ints are `i32` (`i64` for 64-bit ints), indices are `u64` and strings are length-prefixed
+//!   like names. Array elements are themselves count-prefixed lists. Indexed strings are the list
+//!   of values followed by the list of indices.
+//!
+//! Attribute metadata ([`crate::AttrMeta`]) is not transferred.
+//!
+//! Writers default to little-endian. Readers accept both byte orders and swap as needed.
+
+use crate::{
+    AttributeType, Error, IntoNodeOutput, NodeOutput, PrimVertices, RawAttribute, RawAttributeData,
+    RawGeometry, RawGeometryOutput, Result,
+};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"HNGB";
+
+/// The most elements a list reserves up front, before they are actually read.
+const MAX_PREALLOCATED: usize = 4096;
+
+/// Byte order of a binary payload.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endianness {
+    fn tag(self) -> u8 {
+        match self {
+            Endianness::Little => 0,
+            Endianness::Big => 1,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            0 => Ok(Endianness::Little),
+            1 => Ok(Endianness::Big),
+            other => Err(Error::InvalidBinary(format!("unknown byte order: {other}"))),
+        }
+    }
+}
+
+/// Reads the geometries of all inputs from a binary payload. Stops after the last geometry, so
+/// `reader` doesn't have to end there.
+pub fn load_raw_binary(reader: impl Read) -> Result<Vec<RawGeometry>> {
+    let mut reader = CountingReader { reader, count: 0 };
+    read_geometries(&mut reader).map_err(|err| match err {
+        Error::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+            Error::TruncatedInput {
+                line: 0,
+                column: reader.count,
+            }
+        }
+        other => other,
+    })
+}
+
+fn read_geometries(mut reader: impl Read) -> Result<Vec<RawGeometry>> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::InvalidBinary("missing header".to_string()));
+    }
+
+    let mut tag = [0; 1];
+    reader.read_exact(&mut tag)?;
+    let mut reader = BinaryReader {
+        reader,
+        endianness: Endianness::from_tag(tag[0])?,
+    };
+
+    let count = reader.u32()?;
+    (0..count)
+        .map(|_| {
+            Ok(RawGeometry {
+                points: reader.section()?,
+                vertices: reader.section()?,
+                prims: reader.section()?,
+                detail: reader.section()?,
+            })
+        })
+        .collect()
+}
+
+/// Counts the bytes read, for the position of [`Error::TruncatedInput`].
+struct CountingReader<R> {
+    reader: R,
+    count: usize,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.reader.read(buf)?;
+        self.count += len;
+        Ok(len)
+    }
+}
+
+/// Writes the geometries as a binary payload with the given byte order.
+pub fn write_raw_binary(
+    writer: impl Write,
+    geometries: &[RawGeometryOutput],
+    endianness: Endianness,
+) -> Result<()> {
+    let mut writer = BinaryWriter { writer, endianness };
+    writer.writer.write_all(MAGIC)?;
+    writer.writer.write_all(&[endianness.tag()])?;
+
+    writer.u32(geometries.len() as u32)?;
+    for geo in geometries {
+        for section in [&geo.points, &geo.vertices, &geo.prims, &geo.detail] {
+            writer.u32(section.len() as u32)?;
+            for (name, attr) in section {
+                writer.attribute(name, attr)?;
+            }
+        }
+    }
+
+    writer.writer.flush()?;
+    Ok(())
+}
+
+/// Binary counterpart to [`crate::generate_to_stdout`], using little-endian byte order. Multiple
+/// outputs are written as multiple geometries.
+pub fn generate_binary_to_stdout<G: IntoNodeOutput>(output: G) -> Result<()> {
+    let geometries = match output.into_node_output()? {
+        NodeOutput::Single(geo) => vec![geo],
+        NodeOutput::Multiple(geos) => geos,
+    };
+    write_raw_binary(std::io::stdout().lock(), &geometries, Endianness::default())
+}
+
+/// Generates a `read` function for a primitive number type that respects the byte order.
+macro_rules! read_number {
+    ($name:ident, $type:ty) => {
+        fn $name(&mut self) -> Result<$type> {
+            let mut bytes = [0; size_of::<$type>()];
+            self.reader.read_exact(&mut bytes)?;
+            Ok(match self.endianness {
+                Endianness::Little => <$type>::from_le_bytes(bytes),
+                Endianness::Big => <$type>::from_be_bytes(bytes),
+            })
+        }
+    };
+}
+
+struct BinaryReader<R> {
+    reader: R,
+    endianness: Endianness,
+}
+
+impl<R: Read> BinaryReader<R> {
+    read_number!(u32, u32);
+    read_number!(u64, u64);
+    read_number!(i32, i32);
+    read_number!(i64, i64);
+    read_number!(f32, f32);
+    read_number!(f64, f64);
+
+    fn len(&mut self) -> Result<usize> {
+        Ok(self.u64()? as usize)
+    }
+
+    fn index(&mut self) -> Result<usize> {
+        Ok(self.u64()? as usize)
+    }
+
+    /// The lengths come from the payload, so the bytes are only allocated as they are read.
+    fn string(&mut self) -> Result<String> {
+        let len = self.u64()?;
+        let mut bytes = Vec::new();
+        (&mut self.reader).take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        String::from_utf8(bytes).map_err(|e| Error::InvalidBinary(e.to_string()))
+    }
+
+    /// Like [`BinaryReader::string`], the list only grows as its elements are read. Every element
+    /// takes up at least one byte, so a wrong length runs into the end of the input.
+    fn list<T>(&mut self, mut read: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let len = self.len()?;
+        let mut values = Vec::with_capacity(len.min(MAX_PREALLOCATED));
+        for _ in 0..len {
+            values.push(read(self)?);
+        }
+        Ok(values)
+    }
+
+    /// Collected instead of read with [`Self::list`], since [`PrimVertices`] may not be a `Vec`.
+    fn prim_vertices(&mut self) -> Result<PrimVertices> {
+        let len = self.len()?;
+        (0..len).map(|_| self.index()).collect()
+    }
+
+    fn section(&mut self) -> Result<HashMap<String, RawAttribute>> {
+        let count = self.u32()?;
+        (0..count)
+            .map(|_| {
+                let name = self.string()?;
+                let tuple_size = self.len()?;
+                let data = self.data()?;
+                Ok((
+                    name,
+                    RawAttribute {
+                        tuple_size,
+                        data,
+                        meta: None,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    fn data(&mut self) -> Result<RawAttributeData> {
+        let mut tag = [0; 1];
+        self.reader.read_exact(&mut tag)?;
+
+        Ok(match tag[0] {
+            0 => RawAttributeData::Float(self.list(Self::f32)?),
+            1 => RawAttributeData::FloatArray(self.list(|r| r.list(Self::f32))?),
+            2 => RawAttributeData::Int(self.list(Self::i32)?),
+            3 => RawAttributeData::IntArray(self.list(|r| r.list(Self::i32))?),
+            4 => RawAttributeData::String(self.list(Self::string)?),
+            5 => RawAttributeData::StringArray(self.list(|r| r.list(Self::string))?),
+            6 => RawAttributeData::Index(self.list(Self::index)?),
+            7 => RawAttributeData::PrimVertex(self.list(Self::prim_vertices)?),
+            8 => RawAttributeData::Int64(self.list(Self::i64)?),
+            9 => RawAttributeData::Float64(self.list(Self::f64)?),
+            10 => RawAttributeData::IndexedString {
+                values: self.list(Self::string)?,
+                indices: self.list(Self::index)?,
+            },
+            other => return Err(Error::InvalidBinary(format!("unknown data type: {other}"))),
+        })
+    }
+}
+
+/// Generates a `write` function for a primitive number type that respects the byte order.
+macro_rules! write_number {
+    ($name:ident, $type:ty) => {
+        fn $name(&mut self, v: $type) -> Result<()> {
+            let bytes = match self.endianness {
+                Endianness::Little => v.to_le_bytes(),
+                Endianness::Big => v.to_be_bytes(),
+            };
+            self.writer.write_all(&bytes)?;
+            Ok(())
+        }
+    };
+}
+
+struct BinaryWriter<W> {
+    writer: W,
+    endianness: Endianness,
+}
+
+impl<W: Write> BinaryWriter<W> {
+    write_number!(u32, u32);
+    write_number!(u64, u64);
+    write_number!(i32, i32);
+    write_number!(i64, i64);
+    write_number!(f32, f32);
+    write_number!(f64, f64);
+
+    fn len(&mut self, len: usize) -> Result<()> {
+        self.u64(len as u64)
+    }
+
+    fn index(&mut self, v: &usize) -> Result<()> {
+        self.u64(*v as u64)
+    }
+
+    fn string(&mut self, v: &str) -> Result<()> {
+        self.len(v.len())?;
+        self.writer.write_all(v.as_bytes())?;
+        Ok(())
+    }
+
+    fn list<T>(
+        &mut self,
+        values: &[T],
+        mut write: impl FnMut(&mut Self, &T) -> Result<()>,
+    ) -> Result<()> {
+        self.len(values.len())?;
+        values.iter().try_for_each(|v| write(self, v))
+    }
+
+    fn attribute(&mut self, name: &str, attr: &RawAttribute) -> Result<()> {
+        self.string(name)?;
+        self.len(attr.tuple_size)?;
+
+        let tag = match attr.data.kind() {
+            AttributeType::Float => 0,
+            AttributeType::FloatArray => 1,
+            AttributeType::Int => 2,
+            AttributeType::IntArray => 3,
+            AttributeType::String => 4,
+            AttributeType::StringArray => 5,
+            AttributeType::Index => 6,
+            AttributeType::PrimVertex => 7,
+            AttributeType::Int64 => 8,
+            AttributeType::Float64 => 9,
+            AttributeType::IndexedString => 10,
+        };
+        self.writer.write_all(&[tag])?;
+
+        match &attr.data {
+            RawAttributeData::Float(v) => self.list(v, |w, v| w.f32(*v)),
+            RawAttributeData::FloatArray(v) => self.list(v, |w, v| w.list(v, |w, v| w.f32(*v))),
+            RawAttributeData::Int(v) => self.list(v, |w, v| w.i32(*v)),
+            RawAttributeData::IntArray(v) => self.list(v, |w, v| w.list(v, |w, v| w.i32(*v))),
+            RawAttributeData::String(v) => self.list(v, |w, v| w.string(v)),
+            RawAttributeData::StringArray(v) => self.list(v, |w, v| w.list(v, |w, v| w.string(v))),
+            RawAttributeData::Index(v) => self.list(v, Self::index),
+            RawAttributeData::PrimVertex(v) => self.list(v, |w, v| w.list(v, Self::index)),
+            RawAttributeData::Int64(v) => self.list(v, |w, v| w.i64(*v)),
+            RawAttributeData::Float64(v) => self.list(v, |w, v| w.f64(*v)),
+            RawAttributeData::IndexedString { values, indices } => {
+                self.list(values, |w, v| w.string(v))?;
+                self.list(indices, Self::index)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{FromRawGeometry, Geometry, IntoRawGeometry};
+    use glam::Vec3;
+    use houdini_node_macro::{InAttrs, OutAttrs};
+
+    #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Vec3,
+        id: i32,
+        name: String,
+    }
+
+    fn geometry() -> Geometry<Point> {
+        Geometry {
+            points: vec![
+                Point {
+                    position: Vec3::new(1.0, 2.0, 3.0),
+                    id: -7,
+                    name: "a".to_string(),
+                },
+                Point {
+                    position: Vec3::new(-0.5, 0.25, 1e6),
+                    id: 42,
+                    name: "b".to_string(),
+                },
+            ],
+            vertices: vec![],
+            prims: vec![],
+            detail: (),
+            extra: Default::default(),
+        }
+    }
+
+    fn round_trip(endianness: Endianness) -> Vec<u8> {
+        let raw = geometry().into_raw().unwrap();
+        let mut bytes = Vec::new();
+        write_raw_binary(&mut bytes, &[raw], endianness).unwrap();
+
+        let raw = load_raw_binary(bytes.as_slice()).unwrap();
+        assert_eq!(raw.len(), 1);
+        let geo_new = Geometry::<Point>::from_raw(raw.into_iter().next().unwrap(), 0).unwrap();
+        assert_eq!(geometry(), geo_new);
+
+        bytes
+    }
+
+    #[test]
+    fn little_endian_by_default() {
+        let bytes = round_trip(Endianness::default());
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(bytes[4], 0);
+        // Geometry count
+        assert_eq!(&bytes[5..9], &1u32.to_le_bytes());
+    }
+
+    #[test]
+    fn big_endian_payload() {
+        let bytes = round_trip(Endianness::Big);
+        assert_eq!(bytes[4], 1);
+        assert_eq!(&bytes[5..9], &1u32.to_be_bytes());
+    }
+
+    #[test]
+    fn big_endian_fixture() {
+        let mut bytes = Vec::new();
+        bytes.extend(b"HNGB\x01");
+        bytes.extend(1u32.to_be_bytes()); // geometries
+        bytes.extend(1u32.to_be_bytes()); // point attributes
+        bytes.extend(2u64.to_be_bytes()); // name
+        bytes.extend(b"id");
+        bytes.extend(1u64.to_be_bytes()); // tuple size
+        bytes.push(2); // int
+        bytes.extend(2u64.to_be_bytes()); // elements
+        bytes.extend(1i32.to_be_bytes());
+        bytes.extend((-2i32).to_be_bytes());
+        bytes.extend([0u32.to_be_bytes(); 3].concat()); // vertex, prim and detail attributes
+
+        let raw = load_raw_binary(bytes.as_slice()).unwrap();
+        assert_eq!(raw.len(), 1);
+        let id = &raw[0].points["id"];
+        assert_eq!(id.tuple_size, 1);
+        assert_eq!(id.data, RawAttributeData::Int(vec![1, -2]));
+        assert!(raw[0].vertices.is_empty() && raw[0].detail.is_empty());
+    }
+
+    #[test]
+    fn oversized_lengths() {
+        let header = [
+            b"HNGB\x00".as_slice(),
+            &1u32.to_le_bytes(),
+            &1u32.to_le_bytes(),
+        ]
+        .concat();
+
+        // A name that claims to be longer than the input.
+        let bytes = [header.as_slice(), &u64::MAX.to_le_bytes(), b"id"].concat();
+        assert!(matches!(
+            load_raw_binary(bytes.as_slice()),
+            Err(Error::TruncatedInput {
+                line: 0,
+                column: 23
+            })
+        ));
+
+        // A list that claims more elements than the input has.
+        let bytes = [
+            header.as_slice(),
+            &2u64.to_le_bytes(),
+            b"id",
+            &1u64.to_le_bytes(),
+            &[2],
+            &u64::MAX.to_le_bytes(),
+            &1i32.to_le_bytes(),
+        ]
+        .concat();
+        let err = load_raw_binary(bytes.as_slice()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TruncatedInput {
+                line: 0,
+                column: 44
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Input ended unexpectedly at byte 44; did the upstream process die?"
+        );
+    }
+
+    #[test]
+    fn invalid_header() {
+        let err = load_raw_binary(&b"JSON\0"[..]).unwrap_err();
+        assert!(matches!(err, Error::InvalidBinary(_)));
+    }
+}
@@ -0,0 +1,150 @@
+//! Byte blobs stored as base64 strings. Requires the `blob` feature.
+
+use crate::{
+    ErrContext, Error, FromAttributeData, IntoAttributeData, RawAttribute, load_from_attr,
+};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Arbitrary bytes, stored as a standard (padded) base64 string attribute. Meant for small
+/// per-entity payloads like serialized sub-objects.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Blob(pub Vec<u8>);
+
+impl FromAttributeData for Blob {
+    type DataType = String;
+
+    /// Decodes invalid strings as empty blobs. The loader goes through `from_attr_data_raw`,
+    /// which rejects them instead.
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(|s| Blob(decode(&s).unwrap_or_default()))
+    }
+
+    fn from_attr_data_raw(
+        attr: Option<RawAttribute>,
+        num_elements: usize,
+        attr_name: &'static str,
+        err_context: ErrContext,
+    ) -> crate::Result<impl Iterator<Item = Self>> {
+        load_from_attr::<String>(attr, num_elements, attr_name, err_context)?
+            .map(|value| match decode(&value) {
+                Some(bytes) => Ok(Blob(bytes)),
+                None => Err(Error::InvalidAttributeString {
+                    attr: attr_name,
+                    value,
+                }),
+            })
+            .collect::<crate::Result<Vec<_>>>()
+            .map(Vec::into_iter)
+    }
+}
+
+impl IntoAttributeData for Blob {
+    type DataType = String;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(|blob| encode(&blob.0))
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, b[0], b[1], b[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode(s: &str) -> Option<Vec<u8>> {
+    fn value(c: u8) -> Option<u32> {
+        ALPHABET.iter().position(|&a| a == c).map(|v| v as u32)
+    }
+
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(s.len() / 4 * 3);
+    for (i, chunk) in s.chunks(4).enumerate() {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && i != s.len() / 4 - 1) {
+            return None;
+        }
+        let mut n = 0;
+        for &c in &chunk[..4 - padding] {
+            n = n << 6 | value(c)?;
+        }
+        n <<= 6 * padding;
+        out.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttributeType, EntityKind, RawAttributeData, generate_to_attr};
+
+    const CONTEXT: ErrContext = ErrContext {
+        input_index: 0,
+        entity: EntityKind::Point,
+    };
+
+    #[test]
+    fn base64() {
+        for (bytes, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+        ] {
+            assert_eq!(encode(bytes), encoded);
+            assert_eq!(decode(encoded).unwrap(), bytes);
+        }
+        assert_eq!(decode("Zm9"), None);
+        assert_eq!(decode("Zg==Zg=="), None);
+        assert_eq!(decode("Z!=="), None);
+    }
+
+    #[test]
+    fn blob_round_trip() {
+        let blobs = vec![
+            Blob(vec![0, 0, 0]),
+            Blob(vec![]),
+            Blob((0..=255).collect()),
+            Blob(vec![255, 0]),
+        ];
+        let attr = generate_to_attr(blobs.clone());
+        assert_eq!(attr.data.kind(), AttributeType::String);
+
+        let read: Vec<Blob> = load_from_attr(Some(attr), 4, "blob", CONTEXT)
+            .unwrap()
+            .collect();
+        assert_eq!(read, blobs);
+
+        let attr = RawAttribute {
+            tuple_size: 1,
+            data: RawAttributeData::String(vec!["not base64".to_string()]),
+            meta: None,
+        };
+        let err = load_from_attr::<Blob>(Some(attr), 1, "blob", CONTEXT)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::InvalidAttributeString { attr: "blob", .. }
+        ));
+    }
+}
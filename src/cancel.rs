@@ -0,0 +1,166 @@
+//! Cooperative cancellation of long-running nodes.
+//!
+//! Node functions poll [`should_cancel`] (or [`check_cancelled`]) in their long loops and bail out
+//! early. The flag is process-wide, so it can be set from any thread. The generated `main` sets it
+//! when the node is asked to stop:
+//!
+//! - when stdin is closed, see [`cancel_on_eof`]. The bridge keeps stdin open after writing the
+//!   input and closes it when the cook is interrupted, so every format reads only up to the end of
+//!   its input.
+//! - on `SIGINT` or `SIGTERM` (Unix only), see [`cancel_on_signal`]. A second signal terminates
+//!   the process as usual, for nodes that don't poll the flag.
+
+use crate::{Error, Result};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the node has been asked to stop.
+pub fn should_cancel() -> bool {
+    CANCELLED.load(Ordering::Relaxed)
+}
+
+/// Like [`should_cancel`], but returns [`Error::Cancelled`] for use with `?`.
+pub fn check_cancelled() -> Result<()> {
+    if should_cancel() {
+        Err(Error::Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// Asks the node to stop.
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::Relaxed);
+}
+
+/// Clears the flag again, for tests and processes that run several nodes in a row.
+pub fn reset() {
+    CANCELLED.store(false, Ordering::Relaxed);
+}
+
+/// Calls [`cancel`] from a background thread once `timeout` has passed.
+pub fn cancel_after(timeout: Duration) {
+    std::thread::spawn(move || {
+        std::thread::sleep(timeout);
+        cancel();
+    });
+}
+
+/// Calls [`cancel`] from a background thread once `reader` is closed. Anything read before is
+/// discarded.
+pub fn cancel_on_eof(mut reader: impl Read + Send + 'static) {
+    std::thread::spawn(move || {
+        let mut buf = [0; 256];
+        // Read errors mean the other side is gone as well.
+        while let Ok(1..) = reader.read(&mut buf) {}
+        cancel();
+    });
+}
+
+/// Calls [`cancel_on_eof`] for stdin. Must only be called once the input has been read.
+pub fn cancel_on_stdin_close() {
+    cancel_on_eof(std::io::stdin());
+}
+
+/// Calls [`cancel`] on the first `SIGINT` or `SIGTERM`. Does nothing on other platforms.
+pub fn cancel_on_signal() {
+    #[cfg(unix)]
+    signal::install();
+}
+
+#[cfg(unix)]
+mod signal {
+    use std::ffi::c_int;
+
+    pub(super) const SIGINT: c_int = 2;
+    pub(super) const SIGTERM: c_int = 15;
+    const SIG_DFL: usize = 0;
+
+    unsafe extern "C" {
+        fn signal(signum: c_int, handler: usize) -> usize;
+    }
+
+    pub(super) fn install() {
+        for signum in [SIGINT, SIGTERM] {
+            // SAFETY: The handler only does async-signal-safe work: an atomic store and resetting
+            // the handler.
+            unsafe { signal(signum, handle as extern "C" fn(c_int) as usize) };
+        }
+    }
+
+    extern "C" fn handle(signum: c_int) {
+        super::cancel();
+        // SAFETY: See `install`.
+        unsafe { signal(signum, SIG_DFL) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    /// The flag is process-wide, so the tests must not run at the same time.
+    static FLAG: Mutex<()> = Mutex::new(());
+
+    /// Stands in for a node function with a long loop.
+    fn long_running_node() -> Result<()> {
+        let start = Instant::now();
+        loop {
+            check_cancelled()?;
+            assert!(start.elapsed() < Duration::from_secs(5), "not cancelled");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn cancellation_stops_loop() {
+        let _flag = FLAG.lock().unwrap();
+        reset();
+        cancel_after(Duration::from_millis(20));
+
+        let err = long_running_node().unwrap_err();
+        assert!(matches!(err, Error::Cancelled));
+        assert!(should_cancel());
+
+        reset();
+        assert!(!should_cancel());
+        assert!(check_cancelled().is_ok());
+    }
+
+    #[test]
+    fn closed_input_cancels() {
+        let _flag = FLAG.lock().unwrap();
+        reset();
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        cancel_on_eof(reader);
+
+        std::io::Write::write_all(&mut writer, b"ignored").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!should_cancel());
+
+        drop(writer);
+        assert!(matches!(long_running_node(), Err(Error::Cancelled)));
+        reset();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn signal_cancels() {
+        unsafe extern "C" {
+            fn raise(signum: std::ffi::c_int) -> std::ffi::c_int;
+        }
+
+        let _flag = FLAG.lock().unwrap();
+        reset();
+        cancel_on_signal();
+        // SAFETY: The handler was just installed, so this doesn't terminate the test process.
+        assert_eq!(unsafe { raise(signal::SIGTERM) }, 0);
+        assert!(matches!(long_running_node(), Err(Error::Cancelled)));
+        reset();
+    }
+}
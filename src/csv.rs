@@ -0,0 +1,164 @@
+//! Flat table dumps of typed geometry, for quick inspection.
+
+use crate::{Geometry, OutAttrs, RawAttribute, RawAttributeData, Result, out_attrs};
+use std::io::Write;
+
+impl<Pt: OutAttrs + Clone, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Writes the points as CSV, one row per point. Attributes are expanded into one column per
+    /// component, named `P.x`, `P.y`, `P.z` and so on (`name.0`, `name.1`, ... beyond four
+    /// components). Strings and arrays are quoted.
+    pub fn to_csv(&self, writer: impl Write) -> Result<()> {
+        let mut writer = writer;
+        let attrs = Pt::into_attr_ref(&self.points)?;
+        let columns = out_attrs::<Pt>();
+
+        // The tuple size of some types is only known at runtime, so it's taken from the data.
+        let header: Vec<_> = columns
+            .iter()
+            .flat_map(|(name, _)| column_names(name, attrs[name].tuple_size))
+            .collect();
+        writeln!(writer, "{}", header.join(","))?;
+
+        for i in 0..self.points.len() {
+            let row: Vec<_> = columns
+                .iter()
+                .flat_map(|(name, _)| cells(&attrs[name], i))
+                .collect();
+            writeln!(writer, "{}", row.join(","))?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn column_names(name: &str, tuple_size: usize) -> Vec<String> {
+    match tuple_size {
+        1 => vec![name.to_string()],
+        2..=4 => ["x", "y", "z", "w"][..tuple_size]
+            .iter()
+            .map(|c| format!("{name}.{c}"))
+            .collect(),
+        _ => (0..tuple_size).map(|c| format!("{name}.{c}")).collect(),
+    }
+}
+
+/// The cells of the element at `index`, one per component.
+fn cells(attr: &RawAttribute, index: usize) -> Vec<String> {
+    fn tuple<T: ToString>(v: &[T], n: usize, index: usize) -> Vec<String> {
+        v[index * n..(index + 1) * n]
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn list<T: ToString>(v: &[T]) -> Vec<String> {
+        vec![quote(
+            &v.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+        )]
+    }
+
+    let n = attr.tuple_size;
+    match &attr.data {
+        RawAttributeData::Float(v) => tuple(v, n, index),
+        RawAttributeData::Int(v) => tuple(v, n, index),
+        RawAttributeData::Int64(v) => tuple(v, n, index),
+        RawAttributeData::Float64(v) => tuple(v, n, index),
+        RawAttributeData::Index(v) => tuple(v, n, index),
+        RawAttributeData::String(v) => v[index * n..(index + 1) * n]
+            .iter()
+            .map(|s| quote(s))
+            .collect(),
+        RawAttributeData::IndexedString { values, indices } => indices[index * n..(index + 1) * n]
+            .iter()
+            .map(|&i| quote(values.get(i).map_or("", String::as_str)))
+            .collect(),
+        RawAttributeData::FloatArray(v) => list(&v[index]),
+        RawAttributeData::IntArray(v) => list(&v[index]),
+        RawAttributeData::StringArray(v) => list(&v[index]),
+        RawAttributeData::PrimVertex(v) => list(&v[index]),
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::DynVec;
+    use glam::Vec3;
+    use houdini_node_macro::OutAttrs;
+
+    #[derive(Debug, Clone, OutAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Vec3,
+        id: i32,
+        name: String,
+    }
+
+    #[test]
+    fn point_table() {
+        let geo = Geometry::<Point> {
+            points: vec![
+                Point {
+                    position: Vec3::new(1.0, 2.5, -3.0),
+                    id: 7,
+                    name: "a".to_string(),
+                },
+                Point {
+                    position: Vec3::ZERO,
+                    id: 8,
+                    name: "say \"hi\", b".to_string(),
+                },
+            ],
+            vertices: vec![],
+            prims: vec![],
+            detail: (),
+            extra: Default::default(),
+        };
+
+        let mut out = Vec::new();
+        geo.to_csv(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines[0], "P.x,P.y,P.z,id,name");
+        assert_eq!(lines[1], "1,2.5,-3,7,\"a\"");
+        assert_eq!(lines[2], "0,0,0,8,\"say \"\"hi\"\", b\"");
+
+        #[derive(Debug, Clone, OutAttrs)]
+        struct Sample {
+            id: i32,
+            feat: DynVec,
+        }
+
+        let geo: Geometry<Sample> = vec![
+            Sample {
+                id: 1,
+                feat: DynVec(vec![1.0, 2.0, 3.0]),
+            },
+            Sample {
+                id: 2,
+                feat: DynVec(vec![4.0]),
+            },
+        ]
+        .into();
+
+        let mut out = Vec::new();
+        geo.to_csv(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert_eq!(lines[0], "id,feat.x,feat.y,feat.z");
+        assert_eq!(lines[1], "1,1,2,3");
+        assert_eq!(lines[2], "2,4,0,0");
+    }
+}
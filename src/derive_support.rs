@@ -0,0 +1,36 @@
+//! Helpers for the code generated by the derive macros, not meant to be used directly.
+
+/// The values of one field of borrowed entities, for the derived `OutAttrs::into_attr_ref`.
+///
+/// Calling `(&&field_values).values()` with [`Borrowed`] and [`Cloned`] in scope borrows the
+/// values if the field type is `Clone`, so that only the values are copied into the output. Other
+/// field types are moved out of clones of the entities instead, so that deriving `OutAttrs` works
+/// for any field type.
+pub struct FieldValues<'a, E, F> {
+    pub entities: &'a [E],
+    pub by_ref: fn(&E) -> &F,
+    pub by_value: fn(E) -> F,
+}
+
+pub trait Borrowed<'a, F> {
+    fn values(&self) -> Vec<&'a F>;
+}
+
+impl<'a, E, F: Clone> Borrowed<'a, F> for &FieldValues<'a, E, F> {
+    fn values(&self) -> Vec<&'a F> {
+        self.entities.iter().map(self.by_ref).collect()
+    }
+}
+
+pub trait Cloned<F> {
+    fn values(&self) -> Vec<F>;
+}
+
+impl<E: Clone, F> Cloned<F> for FieldValues<'_, E, F> {
+    fn values(&self) -> Vec<F> {
+        self.entities
+            .iter()
+            .map(|entity| (self.by_value)(entity.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,299 @@
+//! Attribute-level comparison of node output, for regression tests.
+
+use crate::{AttributeType, EntityKind, RawAttribute, RawAttributeData, RawGeometryOutput};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// A single difference found by [`RawGeometryOutput::diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// The attribute only exists in the other geometry.
+    Added { entity: EntityKind, attr: String },
+    /// The attribute only exists in this geometry.
+    Removed { entity: EntityKind, attr: String },
+    /// The data type or tuple size differs, so the values were not compared.
+    TypeChanged {
+        entity: EntityKind,
+        attr: String,
+        from: (AttributeType, usize),
+        to: (AttributeType, usize),
+    },
+    /// The number of elements differs, only the common elements were compared.
+    LengthChanged {
+        entity: EntityKind,
+        attr: String,
+        from: usize,
+        to: usize,
+    },
+    /// The value of the element at `index` differs.
+    ValueChanged {
+        entity: EntityKind,
+        attr: String,
+        index: usize,
+    },
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difference::Added { entity, attr } => write!(f, "{entity} attribute {attr} added"),
+            Difference::Removed { entity, attr } => write!(f, "{entity} attribute {attr} removed"),
+            Difference::TypeChanged {
+                entity,
+                attr,
+                from,
+                to,
+            } => write!(
+                f,
+                "{entity} attribute {attr} changed from {}[{}] to {}[{}]",
+                from.0, from.1, to.0, to.1
+            ),
+            Difference::LengthChanged {
+                entity,
+                attr,
+                from,
+                to,
+            } => write!(
+                f,
+                "{entity} attribute {attr} changed from {from} to {to} elements"
+            ),
+            Difference::ValueChanged {
+                entity,
+                attr,
+                index,
+            } => write!(f, "{entity} attribute {attr} changed at element {index}"),
+        }
+    }
+}
+
+impl RawGeometryOutput {
+    /// Compares all attributes with `other`. Floats may differ by up to `tolerance`, everything
+    /// else has to match exactly. NaN is equal to NaN, so that unchanged output compares equal.
+    pub fn diff(&self, other: &RawGeometryOutput, tolerance: f32) -> Vec<Difference> {
+        let sections = [
+            (EntityKind::Point, &self.points, &other.points),
+            (EntityKind::Vertex, &self.vertices, &other.vertices),
+            (EntityKind::Prim, &self.prims, &other.prims),
+            (EntityKind::Detail, &self.detail, &other.detail),
+        ];
+        let mut differences = Vec::new();
+        for (entity, ours, theirs) in sections {
+            diff_section(entity, ours, theirs, tolerance, &mut differences);
+        }
+        differences
+    }
+}
+
+fn diff_section(
+    entity: EntityKind,
+    ours: &BTreeMap<Cow<'static, str>, RawAttribute>,
+    theirs: &BTreeMap<Cow<'static, str>, RawAttribute>,
+    tolerance: f32,
+    differences: &mut Vec<Difference>,
+) {
+    for (name, attr) in ours {
+        let attr_name = name.to_string();
+        let Some(other) = theirs.get(name) else {
+            differences.push(Difference::Removed {
+                entity,
+                attr: attr_name,
+            });
+            continue;
+        };
+
+        let (from, to) = (
+            (attr.data.kind(), attr.tuple_size),
+            (other.data.kind(), other.tuple_size),
+        );
+        if from != to {
+            differences.push(Difference::TypeChanged {
+                entity,
+                attr: attr_name,
+                from,
+                to,
+            });
+            continue;
+        }
+
+        let (len, other_len) = (attr.num_elements(), other.num_elements());
+        if len != other_len {
+            differences.push(Difference::LengthChanged {
+                entity,
+                attr: attr_name.clone(),
+                from: len,
+                to: other_len,
+            });
+        }
+        for index in changed_elements(attr, other, tolerance) {
+            differences.push(Difference::ValueChanged {
+                entity,
+                attr: attr_name.clone(),
+                index,
+            });
+        }
+    }
+
+    for name in theirs.keys().filter(|name| !ours.contains_key(*name)) {
+        differences.push(Difference::Added {
+            entity,
+            attr: name.to_string(),
+        });
+    }
+}
+
+/// Indices of the common elements that differ. Expects both attributes to have the same type and
+/// tuple size.
+fn changed_elements(ours: &RawAttribute, theirs: &RawAttribute, tolerance: f32) -> Vec<usize> {
+    fn changed<T>(a: &[T], b: &[T], n: usize, eq: impl Fn(&T, &T) -> bool) -> Vec<usize> {
+        let mut indices: Vec<usize> = a
+            .iter()
+            .zip(b)
+            .enumerate()
+            .filter(|(_, (a, b))| !eq(a, b))
+            .map(|(i, _)| i / n)
+            .collect();
+        indices.dedup();
+        indices
+    }
+
+    let float_eq = |a: &f32, b: &f32| (a.is_nan() && b.is_nan()) || (a - b).abs() <= tolerance;
+    let float64_eq =
+        |a: &f64, b: &f64| (a.is_nan() && b.is_nan()) || (a - b).abs() <= f64::from(tolerance);
+    let floats_eq = |a: &Vec<f32>, b: &Vec<f32>| {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| float_eq(a, b))
+    };
+
+    // Only scalar data is interleaved, every array counts as a single element.
+    let n = ours.tuple_size.max(1);
+    match (&ours.data, &theirs.data) {
+        (RawAttributeData::Float(a), RawAttributeData::Float(b)) => changed(a, b, n, float_eq),
+        (RawAttributeData::Float64(a), RawAttributeData::Float64(b)) => {
+            changed(a, b, n, float64_eq)
+        }
+        (RawAttributeData::Int(a), RawAttributeData::Int(b)) => changed(a, b, n, PartialEq::eq),
+        (RawAttributeData::Int64(a), RawAttributeData::Int64(b)) => changed(a, b, n, PartialEq::eq),
+        (RawAttributeData::String(a), RawAttributeData::String(b)) => {
+            changed(a, b, n, PartialEq::eq)
+        }
+        (RawAttributeData::Index(a), RawAttributeData::Index(b)) => changed(a, b, n, PartialEq::eq),
+        (
+            RawAttributeData::IndexedString {
+                values: a,
+                indices: a_indices,
+            },
+            RawAttributeData::IndexedString {
+                values: b,
+                indices: b_indices,
+            },
+        ) => {
+            // The values are compared, not the indices, which depend on the order of the values.
+            let expand = |values: &[String], indices: &[usize]| -> Vec<Option<String>> {
+                indices.iter().map(|&i| values.get(i).cloned()).collect()
+            };
+            changed(
+                &expand(a, a_indices),
+                &expand(b, b_indices),
+                n,
+                PartialEq::eq,
+            )
+        }
+        (RawAttributeData::FloatArray(a), RawAttributeData::FloatArray(b)) => {
+            changed(a, b, 1, floats_eq)
+        }
+        (RawAttributeData::IntArray(a), RawAttributeData::IntArray(b)) => {
+            changed(a, b, 1, PartialEq::eq)
+        }
+        (RawAttributeData::StringArray(a), RawAttributeData::StringArray(b)) => {
+            changed(a, b, 1, PartialEq::eq)
+        }
+        (RawAttributeData::PrimVertex(a), RawAttributeData::PrimVertex(b)) => {
+            changed(a, b, 1, PartialEq::eq)
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{Geometry, OutAttrs};
+    use glam::Vec3;
+
+    #[derive(Clone, OutAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Vec3,
+        id: i32,
+    }
+
+    fn output(points: &[Point]) -> RawGeometryOutput {
+        let geo: Geometry<Point> = points.to_vec().into();
+        geo.to_raw().unwrap()
+    }
+
+    #[test]
+    fn changed_float() {
+        let points: Vec<_> = (0..3)
+            .map(|i| Point {
+                position: Vec3::splat(i as f32),
+                id: i,
+            })
+            .collect();
+        let expected = output(&points);
+        assert_eq!(expected, output(&points));
+        assert!(expected.diff(&output(&points), 0.0).is_empty());
+
+        let mut changed = points.clone();
+        changed[0].position.x += 1e-4;
+        changed[2].position.y += 0.5;
+        let differences = expected.diff(&output(&changed), 1e-3);
+        assert_eq!(
+            differences,
+            vec![Difference::ValueChanged {
+                entity: EntityKind::Point,
+                attr: "P".to_string(),
+                index: 2,
+            }]
+        );
+        assert_eq!(
+            differences[0].to_string(),
+            "point attribute P changed at element 2"
+        );
+    }
+
+    #[test]
+    fn structural_changes() {
+        let points = vec![Point {
+            position: Vec3::ZERO,
+            id: 0,
+        }];
+        let expected = output(&points);
+        let mut actual = output(&[points[0].clone(), points[0].clone()]);
+        actual.points.remove("id");
+        actual.detail.insert(
+            "name".into(),
+            RawAttribute {
+                tuple_size: 1,
+                data: RawAttributeData::String(vec!["a".to_string()]),
+                meta: None,
+            },
+        );
+
+        let differences: Vec<String> = expected
+            .diff(&actual, 0.0)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            differences,
+            vec![
+                "point attribute P changed from 1 to 2 elements",
+                "point attribute id removed",
+                "detail attribute name added",
+            ]
+        );
+    }
+}
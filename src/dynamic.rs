@@ -0,0 +1,149 @@
+//! Attributes whose names are only known at runtime, as an alternative to the per-element derive.
+
+use crate::{
+    EntityKind, Error, Geometry, IntoAttributeData, IntoRawGeometry, RawAttribute,
+    RawGeometryOutput, Result, generate_to_attr,
+};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+/// A set of attributes named at runtime, e.g. `HashMap<String, Vec<f32>>`, with one attribute per
+/// key and one value per element.
+pub trait DynamicAttrs {
+    fn into_dynamic_attrs(self) -> Vec<(String, RawAttribute)>;
+}
+
+/// Already converted attributes, e.g. from [`crate::generate_attr_group`].
+impl DynamicAttrs for Vec<(String, RawAttribute)> {
+    fn into_dynamic_attrs(self) -> Vec<(String, RawAttribute)> {
+        self
+    }
+}
+
+impl<T: IntoAttributeData> DynamicAttrs for HashMap<String, Vec<T>> {
+    fn into_dynamic_attrs(self) -> Vec<(String, RawAttribute)> {
+        self.into_iter()
+            .map(|(name, values)| (name, generate_to_attr(values)))
+            .collect()
+    }
+}
+
+impl<T: IntoAttributeData> DynamicAttrs for BTreeMap<String, Vec<T>> {
+    fn into_dynamic_attrs(self) -> Vec<(String, RawAttribute)> {
+        self.into_iter()
+            .map(|(name, values)| (name, generate_to_attr(values)))
+            .collect()
+    }
+}
+
+impl RawGeometryOutput {
+    /// Adds the attributes to the given entity. Every attribute needs `count` elements and must
+    /// not use the name of an existing attribute. Fails without modifying `self`.
+    pub fn insert_dynamic(
+        &mut self,
+        entity: EntityKind,
+        attrs: impl DynamicAttrs,
+        count: usize,
+    ) -> Result<()> {
+        let attrs = attrs.into_dynamic_attrs();
+        let section = match entity {
+            EntityKind::Point => &mut self.points,
+            EntityKind::Vertex => &mut self.vertices,
+            EntityKind::Prim => &mut self.prims,
+            EntityKind::Detail => &mut self.detail,
+        };
+
+        for (name, attr) in &attrs {
+            if section.contains_key(name.as_str()) {
+                return Err(Error::AttrNameCollision(name.clone().into()));
+            }
+            if attr.num_elements() != count {
+                return Err(Error::InvalidAttributeLength {
+                    expected: count,
+                    actual: attr.num_elements(),
+                });
+            }
+        }
+
+        section.extend(
+            attrs
+                .into_iter()
+                .map(|(name, attr)| (Cow::Owned(name), attr)),
+        );
+        Ok(())
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt>
+where
+    Self: IntoRawGeometry,
+{
+    /// Like [`IntoRawGeometry::into_raw`], with additional point attributes, see
+    /// [`RawGeometryOutput::insert_dynamic`].
+    pub fn into_raw_with_point_attrs(self, attrs: impl DynamicAttrs) -> Result<RawGeometryOutput> {
+        let count = self.points.len();
+        let mut output = self.into_raw()?;
+        output.insert_dynamic(EntityKind::Point, attrs, count)?;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{OutAttrs, RawAttributeData};
+    use glam::Vec3;
+
+    #[derive(OutAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Vec3,
+    }
+
+    fn geometry() -> Geometry<Point> {
+        vec![
+            Point {
+                position: Vec3::ZERO,
+            },
+            Point { position: Vec3::X },
+        ]
+        .into()
+    }
+
+    #[test]
+    fn dynamic_point_attrs() {
+        let attrs = HashMap::from([
+            ("mask_a".to_string(), vec![0.0f32, 1.0]),
+            ("mask_b".to_string(), vec![0.5f32, 0.25]),
+        ]);
+        let output = geometry().into_raw_with_point_attrs(attrs).unwrap();
+
+        assert_eq!(
+            output.points.keys().collect::<Vec<_>>(),
+            vec!["P", "mask_a", "mask_b"]
+        );
+        assert_eq!(
+            output.points["mask_b"].data,
+            RawAttributeData::Float(vec![0.5, 0.25])
+        );
+    }
+
+    #[test]
+    fn invalid_dynamic_attrs() {
+        let collision = HashMap::from([("P".to_string(), vec![0.0f32, 1.0])]);
+        let err = geometry().into_raw_with_point_attrs(collision).unwrap_err();
+        assert!(matches!(err, Error::AttrNameCollision(name) if name == "P"));
+
+        let short = HashMap::from([("mask".to_string(), vec![0.0f32])]);
+        let err = geometry().into_raw_with_point_attrs(short).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidAttributeLength {
+                expected: 2,
+                actual: 1
+            }
+        ));
+    }
+}
@@ -0,0 +1,139 @@
+//! Attribute data for the `euclid` types. Requires the `euclid` feature.
+//!
+//! The units only exist at compile time, so the attributes are the same as for the `glam` types:
+//! [`Point3D`] and [`Vector3D`] are stored like [`glam::Vec3`] and [`Transform3D`] like
+//! [`glam::Mat4`].
+
+use crate::{FromAttributeData, IntoAttributeData};
+use euclid::{Point3D, Transform3D, Vector3D};
+
+impl<U> FromAttributeData for Point3D<f32, U> {
+    type DataType = [f32; 3];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(Self::from)
+    }
+}
+
+impl<U> IntoAttributeData for Point3D<f32, U> {
+    type DataType = [f32; 3];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(|p| p.to_array())
+    }
+}
+
+// *****************************************
+
+impl<U> FromAttributeData for Vector3D<f32, U> {
+    type DataType = [f32; 3];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(Self::from)
+    }
+}
+
+impl<U> IntoAttributeData for Vector3D<f32, U> {
+    type DataType = [f32; 3];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(|v| v.to_array())
+    }
+}
+
+// *****************************************
+
+// `euclid` transforms row vectors, so its row-major layout is the column-major layout of the
+// equivalent `Mat4`, with the translation in the last four values.
+impl<Src, Dst> FromAttributeData for Transform3D<f32, Src, Dst> {
+    type DataType = [f32; 16];
+    fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self> {
+        data.map(Self::from_array)
+    }
+}
+
+impl<Src, Dst> IntoAttributeData for Transform3D<f32, Src, Dst> {
+    type DataType = [f32; 16];
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        data.map(|m| m.to_array())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{
+        EntityKind, ErrContext, FromRawGeometry, Geometry, IntoRawGeometry, RawAttributeData,
+        generate_to_attr, load_from_attr,
+    };
+    use glam::{Mat4, Vec3};
+    use houdini_node_macro::{InAttrs, OutAttrs};
+
+    const CONTEXT: ErrContext = ErrContext {
+        input_index: 0,
+        entity: EntityKind::Point,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct World;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Local;
+
+    #[derive(Debug, Clone, PartialEq, InAttrs, OutAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Point3D<f32, World>,
+        #[attr(name = "v")]
+        velocity: Vector3D<f32, World>,
+        transform: Transform3D<f32, Local, World>,
+    }
+
+    #[test]
+    fn same_as_glam() {
+        let points = vec![Point3D::<f32, World>::new(1.0, 2.0, 3.0)];
+        assert_eq!(
+            generate_to_attr(points),
+            generate_to_attr(vec![Vec3::new(1.0, 2.0, 3.0)])
+        );
+
+        let transforms = vec![Transform3D::<f32, Local, World>::translation(1.0, 2.0, 3.0)];
+        let attr = generate_to_attr(transforms.clone());
+        assert_eq!(
+            attr,
+            generate_to_attr(vec![Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0))])
+        );
+
+        let read: Vec<Transform3D<f32, Local, World>> =
+            load_from_attr(Some(attr), 1, "transform", CONTEXT)
+                .unwrap()
+                .collect();
+        assert_eq!(read, transforms);
+    }
+
+    #[test]
+    fn entity_round_trip() {
+        let geo = Geometry::<Point> {
+            points: vec![
+                Point {
+                    position: Point3D::new(0.0, 1.0, 2.0),
+                    velocity: Vector3D::new(0.0, 0.0, -1.0),
+                    transform: Transform3D::scale(2.0, 2.0, 2.0),
+                },
+                Point {
+                    position: Point3D::origin(),
+                    velocity: Vector3D::zero(),
+                    transform: Transform3D::identity(),
+                },
+            ],
+            vertices: Vec::new(),
+            prims: Vec::new(),
+            detail: (),
+            extra: Default::default(),
+        };
+
+        let raw = geo.clone().into_raw().unwrap();
+        assert_eq!(raw.points["transform"].tuple_size, 16);
+        assert!(matches!(raw.points["v"].data, RawAttributeData::Float(_)));
+        let raw = serde_json::from_str(&serde_json::to_string(&raw).unwrap()).unwrap();
+        assert_eq!(Geometry::<Point>::from_raw(raw, 0).unwrap(), geo);
+    }
+}
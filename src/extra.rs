@@ -0,0 +1,841 @@
+//! Untyped data that is carried alongside the typed entities of a [`Geometry`].
+
+use crate::{
+    AttributeType, EntityKind, Error, Geometry, InAttrs, OutAttrs, PointCache, PrimVertices,
+    RawAttribute, RawAttributeData, RawGeometry, RawGeometryOutput, Result, out_attrs,
+};
+use glam::{Mat3, Mat4, Vec3};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+/// Controls which untyped data is retained when loading a [`Geometry`]. Everything is off by
+/// default.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct LoadOptions {
+    /// Retain the names and types of all incoming attributes, see [`AttrInfo`].
+    pub attr_info: bool,
+    /// Retain the topology pseudo-attributes, even if the entity types don't have fields for
+    /// them, see [`Geometry::raw_prim_vertices`].
+    pub topology: bool,
+    /// Point attributes to retain untyped in [`GeometryExtra::point_attrs`], where they can be
+    /// modified with [`Geometry::map_point_attr_f32`] and friends. They are written to the output
+    /// unless the point count changes. Must not be read by the point type as well.
+    pub point_attrs: &'static [&'static str],
+}
+
+/// Untyped side data of a [`Geometry`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeometryExtra {
+    /// Only present if requested via [`LoadOptions::attr_info`].
+    pub attr_info: Option<AttrInfo>,
+    /// Point attributes added via [`Geometry::set_point_attr`] or retained via
+    /// [`LoadOptions::point_attrs`]. Written next to the typed ones.
+    pub point_attrs: BTreeMap<&'static str, RawAttribute>,
+    /// The metadata of the incoming attributes, restored on output attributes of the same name.
+    pub attr_meta: AttrMetaMap,
+    /// Only present if requested via [`LoadOptions::topology`].
+    pub topology: Option<RawTopology>,
+    /// See [`Geometry::point_index`].
+    pub point_cache: PointCache,
+}
+
+/// Removes the point attributes requested by [`LoadOptions::point_attrs`] from the input.
+pub(crate) fn take_point_attrs<Pt: InAttrs>(
+    points: &mut HashMap<String, RawAttribute>,
+    options: &LoadOptions,
+    input_index: usize,
+) -> Result<BTreeMap<&'static str, RawAttribute>> {
+    options
+        .point_attrs
+        .iter()
+        .map(|&name| {
+            if Pt::IN_ATTRS.iter().any(|(field, _)| *field == name) {
+                return Err(Error::AttrNameCollision(name.into()));
+            }
+            let attr = points.remove(name).ok_or(Error::MissingAttr {
+                input_index,
+                entity: EntityKind::Point,
+                attr: name,
+            })?;
+            Ok((name, attr))
+        })
+        .collect()
+}
+
+/// The incoming topology pseudo-attributes. Empty if the input has no prims.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawTopology {
+    /// The vertex indices of every prim.
+    pub prim_vertices: Vec<PrimVertices>,
+    /// The point index of every vertex.
+    pub ptnums: Vec<usize>,
+}
+
+impl RawTopology {
+    pub(crate) fn from_raw(raw: &RawGeometry) -> Result<Self> {
+        let prim_vertices = match raw.prims.get("vertices") {
+            Some(attr) => attr.data.clone().prim_vertex()?,
+            None => Vec::new(),
+        };
+        let ptnums = match raw.vertices.get("ptnum") {
+            Some(attr) => attr.data.clone().index()?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            prim_vertices,
+            ptnums,
+        })
+    }
+}
+
+/// Houdini attribute metadata that doesn't affect the values themselves.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttrMeta {
+    /// How the values are transformed, like `point`, `vector`, `normal` or `color`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub typeinfo: Option<String>,
+    /// The storage precision in bits, like `16` for half floats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<u32>,
+    /// Whether the strings were sent as `IndexedString` data. Output strings of the same
+    /// attribute are indexed again. Not sent as metadata, as it's part of the data itself.
+    #[serde(skip)]
+    pub indexed: bool,
+}
+
+impl AttrMeta {
+    /// The semantic of the attribute, from the `typeinfo`.
+    pub fn semantic(&self) -> Option<AttrSemantic> {
+        AttrSemantic::from_typeinfo(self.typeinfo.as_deref()?)
+    }
+
+    /// Whether there's any metadata that is sent along with the attribute.
+    fn is_transported(&self) -> bool {
+        self.typeinfo.is_some() || self.precision.is_some()
+    }
+}
+
+/// What the values of a vector attribute represent, which decides how they are transformed, see
+/// [`RawGeometryOutput::transform`]. Stored as the `typeinfo` metadata of Houdini.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AttrSemantic {
+    /// Transformed by the full matrix.
+    Position,
+    /// Transformed by the inverse transpose, keeping the length.
+    Normal,
+    /// Transformed without the translation.
+    Vector,
+    /// Not transformed.
+    Color,
+}
+
+impl AttrSemantic {
+    pub fn typeinfo(self) -> &'static str {
+        match self {
+            AttrSemantic::Position => "point",
+            AttrSemantic::Normal => "normal",
+            AttrSemantic::Vector => "vector",
+            AttrSemantic::Color => "color",
+        }
+    }
+
+    /// `None` for the other types of Houdini, like `quaternion` or `texturecoord`.
+    pub fn from_typeinfo(typeinfo: &str) -> Option<Self> {
+        match typeinfo {
+            "point" | "hpoint" => Some(AttrSemantic::Position),
+            "normal" => Some(AttrSemantic::Normal),
+            "vector" => Some(AttrSemantic::Vector),
+            "color" => Some(AttrSemantic::Color),
+            _ => None,
+        }
+    }
+}
+
+impl RawAttribute {
+    /// Sets the `typeinfo` metadata, see `#[attr(semantic = "...")]`.
+    pub fn with_semantic(mut self, semantic: AttrSemantic) -> Self {
+        self.meta.get_or_insert_default().typeinfo = Some(semantic.typeinfo().to_string());
+        self
+    }
+
+    pub fn semantic(&self) -> Option<AttrSemantic> {
+        self.meta.as_ref()?.semantic()
+    }
+}
+
+impl RawGeometryOutput {
+    /// Applies `matrix` to all 3-component float attributes according to their
+    /// [`AttrSemantic`]. `P` is a position unless it says otherwise. Attributes without a
+    /// semantic are left as they are.
+    pub fn transform(&mut self, matrix: Mat4) {
+        let normal_matrix = Mat3::from_mat4(matrix).inverse().transpose();
+        let sections = [
+            &mut self.points,
+            &mut self.vertices,
+            &mut self.prims,
+            &mut self.detail,
+        ];
+        for attrs in sections {
+            for (name, attr) in attrs.iter_mut() {
+                let semantic = attr
+                    .semantic()
+                    .or_else(|| (name == "P").then_some(AttrSemantic::Position));
+                let (Some(semantic), 3, RawAttributeData::Float(values)) =
+                    (semantic, attr.tuple_size, &mut attr.data)
+                else {
+                    continue;
+                };
+
+                let transform = |v: Vec3| match semantic {
+                    AttrSemantic::Position => matrix.transform_point3(v),
+                    AttrSemantic::Vector => matrix.transform_vector3(v),
+                    AttrSemantic::Normal => (normal_matrix * v).normalize_or_zero() * v.length(),
+                    AttrSemantic::Color => v,
+                };
+                for value in values.chunks_exact_mut(3) {
+                    let v = transform(Vec3::from_slice(value));
+                    value.copy_from_slice(&v.to_array());
+                }
+            }
+        }
+    }
+}
+
+/// The [`AttrMeta`] of every attribute that has some, per entity.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttrMetaMap {
+    pub points: HashMap<String, AttrMeta>,
+    pub vertices: HashMap<String, AttrMeta>,
+    pub prims: HashMap<String, AttrMeta>,
+    pub detail: HashMap<String, AttrMeta>,
+}
+
+impl AttrMetaMap {
+    pub(crate) fn from_raw(raw: &RawGeometry) -> Self {
+        fn collect(attrs: &HashMap<String, RawAttribute>) -> HashMap<String, AttrMeta> {
+            attrs
+                .iter()
+                .filter_map(|(name, attr)| {
+                    let indexed = attr.data.kind() == AttributeType::IndexedString;
+                    let mut meta = match (&attr.meta, indexed) {
+                        (Some(meta), _) => meta.clone(),
+                        (None, true) => AttrMeta::default(),
+                        (None, false) => return None,
+                    };
+                    meta.indexed = indexed;
+                    Some((name.clone(), meta))
+                })
+                .collect()
+        }
+
+        Self {
+            points: collect(&raw.points),
+            vertices: collect(&raw.vertices),
+            prims: collect(&raw.prims),
+            detail: collect(&raw.detail),
+        }
+    }
+
+    /// Sets the metadata of the output attributes, unless they already have their own, and
+    /// indexes strings that were indexed on the input.
+    pub(crate) fn apply(&self, output: &mut RawGeometryOutput) {
+        fn apply(
+            meta: &HashMap<String, AttrMeta>,
+            attrs: &mut BTreeMap<Cow<'static, str>, RawAttribute>,
+        ) {
+            for (name, attr) in attrs {
+                AttrMetaMap::apply_attr(meta.get(name.as_ref()), attr);
+            }
+        }
+
+        apply(&self.points, &mut output.points);
+        apply(&self.vertices, &mut output.vertices);
+        apply(&self.prims, &mut output.prims);
+        apply(&self.detail, &mut output.detail);
+    }
+
+    /// Like [`AttrMetaMap::apply`], for a single attribute with its input metadata.
+    pub(crate) fn apply_attr(meta: Option<&AttrMeta>, attr: &mut RawAttribute) {
+        let Some(meta) = meta else {
+            return;
+        };
+        if meta.indexed {
+            attr.data = std::mem::replace(&mut attr.data, RawAttributeData::String(vec![]))
+                .into_indexed_strings();
+        }
+        if attr.meta.is_none() && meta.is_transported() {
+            attr.meta = Some(AttrMeta {
+                indexed: false,
+                ..meta.clone()
+            });
+        }
+    }
+}
+
+/// The type and tuple size of an attribute.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AttrDesc {
+    pub kind: AttributeType,
+    pub tuple_size: usize,
+}
+
+/// The attributes that were present on the input, per entity, including those that were not read
+/// into the typed entities.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttrInfo {
+    pub points: HashMap<String, AttrDesc>,
+    pub vertices: HashMap<String, AttrDesc>,
+    pub prims: HashMap<String, AttrDesc>,
+    pub detail: HashMap<String, AttrDesc>,
+}
+
+impl AttrInfo {
+    pub(crate) fn from_raw(raw: &RawGeometry) -> Self {
+        fn describe(attrs: &HashMap<String, RawAttribute>) -> HashMap<String, AttrDesc> {
+            attrs
+                .iter()
+                .map(|(name, attr)| {
+                    let desc = AttrDesc {
+                        kind: attr.data.kind(),
+                        tuple_size: attr.tuple_size,
+                    };
+                    (name.clone(), desc)
+                })
+                .collect()
+        }
+
+        Self {
+            points: describe(&raw.points),
+            vertices: describe(&raw.vertices),
+            prims: describe(&raw.prims),
+            detail: describe(&raw.detail),
+        }
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Whether the input had a point attribute with this name. Always `false` unless the geometry
+    /// was loaded with [`LoadOptions::attr_info`].
+    pub fn has_point_attr(&self, name: &str) -> bool {
+        self.attr_info()
+            .is_some_and(|info| info.points.contains_key(name))
+    }
+
+    /// Like [`Geometry::has_point_attr`], for vertex attributes.
+    pub fn has_vertex_attr(&self, name: &str) -> bool {
+        self.attr_info()
+            .is_some_and(|info| info.vertices.contains_key(name))
+    }
+
+    /// Like [`Geometry::has_point_attr`], for prim attributes.
+    pub fn has_prim_attr(&self, name: &str) -> bool {
+        self.attr_info()
+            .is_some_and(|info| info.prims.contains_key(name))
+    }
+
+    /// Like [`Geometry::has_point_attr`], for detail attributes.
+    pub fn has_detail_attr(&self, name: &str) -> bool {
+        self.attr_info()
+            .is_some_and(|info| info.detail.contains_key(name))
+    }
+
+    /// The vertex indices of every prim, as they were on the input. Unlike
+    /// [`Geometry::prim_vertices`], this doesn't need a prim type with a `vertices` field, but
+    /// requires loading with [`LoadOptions::topology`]. Not updated when the prims are modified.
+    pub fn raw_prim_vertices(&self) -> Option<&[PrimVertices]> {
+        Some(&self.extra.topology.as_ref()?.prim_vertices)
+    }
+
+    /// Like [`Geometry::raw_prim_vertices`], for the point index of every vertex.
+    pub fn raw_vertex_ptnums(&self) -> Option<&[usize]> {
+        Some(&self.extra.topology.as_ref()?.ptnums)
+    }
+
+    fn attr_info(&self) -> Option<&AttrInfo> {
+        self.extra.attr_info.as_ref()
+    }
+
+    /// Applies `f` to every value of the untyped float point attribute `name`, see
+    /// [`GeometryExtra::point_attrs`].
+    pub fn map_point_attr_f32(&mut self, name: &str, f: impl Fn(f32) -> f32) -> Result<()> {
+        match &mut self.untyped_point_attr(name, 1)?.data {
+            RawAttributeData::Float(v) => v.iter_mut().for_each(|v| *v = f(*v)),
+            other => return other.err(AttributeType::Float),
+        }
+        Ok(())
+    }
+
+    /// Like [`Geometry::map_point_attr_f32`], for int attributes.
+    pub fn map_point_attr_i32(&mut self, name: &str, f: impl Fn(i32) -> i32) -> Result<()> {
+        match &mut self.untyped_point_attr(name, 1)?.data {
+            RawAttributeData::Int(v) => v.iter_mut().for_each(|v| *v = f(*v)),
+            other => return other.err(AttributeType::Int),
+        }
+        Ok(())
+    }
+
+    /// Like [`Geometry::map_point_attr_f32`], for float attributes with a tuple size of 3.
+    pub fn map_point_attr_vec3(&mut self, name: &str, f: impl Fn(Vec3) -> Vec3) -> Result<()> {
+        match &mut self.untyped_point_attr(name, 3)?.data {
+            RawAttributeData::Float(v) => v
+                .chunks_exact_mut(3)
+                .for_each(|v| v.copy_from_slice(&f(Vec3::from_slice(v)).to_array())),
+            other => return other.err(AttributeType::Float),
+        }
+        Ok(())
+    }
+
+    fn untyped_point_attr(&mut self, name: &str, tuple_size: usize) -> Result<&mut RawAttribute> {
+        let attr = self
+            .extra
+            .point_attrs
+            .get_mut(name)
+            .ok_or_else(|| Error::UnknownAttr {
+                entity: EntityKind::Point,
+                attr: name.to_string(),
+            })?;
+        if attr.tuple_size != tuple_size {
+            return Err(Error::InvalidAttributeLength {
+                expected: tuple_size,
+                actual: attr.tuple_size,
+            });
+        }
+        Ok(attr)
+    }
+}
+
+impl<Pt: OutAttrs, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Attaches a computed point attribute to the output without adding a field to `Pt`. Needs
+    /// one element per point and must not use the name of one of the fields of `Pt`. Replaces a
+    /// previously set attribute of the same name.
+    pub fn set_point_attr(&mut self, name: &'static str, data: RawAttribute) -> Result<()> {
+        if out_attrs::<Pt>().iter().any(|(field, _)| *field == name) {
+            return Err(Error::AttrNameCollision(name.into()));
+        }
+        if data.num_elements() != self.points.len() {
+            return Err(Error::InvalidAttributeLength {
+                expected: self.points.len(),
+                actual: data.num_elements(),
+            });
+        }
+
+        self.extra.point_attrs.insert(name, data);
+        Ok(())
+    }
+
+    /// Per-component statistics of a numeric point attribute, either a field of `Pt` or one set
+    /// with [`Geometry::set_point_attr`].
+    pub fn point_attr_stats(&self, name: &str) -> Result<AttrStats>
+    where
+        Pt: Clone,
+    {
+        let attr = match self.extra.point_attrs.get(name) {
+            Some(attr) => attr.clone(),
+            None => Pt::single_attr_ref(&self.points, name)?.ok_or_else(|| Error::UnknownAttr {
+                entity: EntityKind::Point,
+                attr: name.to_string(),
+            })?,
+        };
+        AttrStats::new(attr)
+    }
+}
+
+/// Minimum, maximum and mean of every component of an attribute, see
+/// [`Geometry::point_attr_stats`]. All values are NaN if there are no elements. The mean is
+/// accumulated in double precision.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttrStats {
+    pub min: Vec<f32>,
+    pub max: Vec<f32>,
+    pub mean: Vec<f32>,
+}
+
+impl AttrStats {
+    /// Works for float and int data of any tuple size.
+    pub fn new(attr: RawAttribute) -> Result<AttrStats> {
+        let values: Vec<f64> = match attr.data {
+            RawAttributeData::Int(v) => v.into_iter().map(f64::from).collect(),
+            RawAttributeData::Int64(v) => v.into_iter().map(|v| v as f64).collect(),
+            RawAttributeData::Float(_) | RawAttributeData::Float64(_) => attr.data.float64()?,
+            other => {
+                return Err(Error::InvalidAttributeType {
+                    expected: AttributeType::Float,
+                    actual: other.kind(),
+                    location: None,
+                });
+            }
+        };
+
+        let tuple_size = attr.tuple_size.max(1);
+        let count = values.len() / tuple_size;
+        let components = (0..tuple_size).map(|c| values.iter().skip(c).step_by(tuple_size));
+        let stats = components.map(|component| {
+            if count == 0 {
+                return (f32::NAN, f32::NAN, f32::NAN);
+            }
+            let (min, max, sum) = component.fold(
+                (f64::INFINITY, f64::NEG_INFINITY, 0.0),
+                |(min, max, sum), &v| (min.min(v), max.max(v), sum + v),
+            );
+            (min as f32, max as f32, (sum / count as f64) as f32)
+        });
+
+        let (mut min, mut max, mut mean) = (vec![], vec![], vec![]);
+        for (lo, hi, avg) in stats {
+            min.push(lo);
+            max.push(hi);
+            mean.push(avg);
+        }
+        Ok(AttrStats { min, max, mean })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{FromRawGeometry, IntoRawGeometry};
+    use glam::Vec3;
+    use houdini_node_macro::{InAttrs, OutAttrs};
+
+    #[derive(PartialEq, Debug, Clone, InAttrs, OutAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Vec3,
+    }
+
+    fn raw() -> RawGeometry {
+        RawGeometry {
+            points: HashMap::from([
+                (
+                    "P".to_string(),
+                    RawAttribute {
+                        tuple_size: 3,
+                        data: RawAttributeData::Float(vec![0.0; 3]),
+                        meta: None,
+                    },
+                ),
+                (
+                    "Cd".to_string(),
+                    RawAttribute {
+                        tuple_size: 3,
+                        data: RawAttributeData::Float(vec![1.0; 3]),
+                        meta: None,
+                    },
+                ),
+            ]),
+            vertices: HashMap::new(),
+            prims: HashMap::new(),
+            detail: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn attribute_presence() {
+        let options = LoadOptions {
+            attr_info: true,
+            ..Default::default()
+        };
+        let geo = Geometry::<Point>::from_raw_with_options(raw(), 0, options).unwrap();
+
+        assert!(geo.has_point_attr("P"));
+        assert!(geo.has_point_attr("Cd"));
+        assert!(!geo.has_point_attr("N"));
+        assert!(!geo.has_prim_attr("Cd"));
+        assert_eq!(
+            geo.extra.attr_info.unwrap().points["Cd"],
+            AttrDesc {
+                kind: AttributeType::Float,
+                tuple_size: 3
+            }
+        );
+    }
+
+    #[test]
+    fn attribute_info_is_opt_in() {
+        let geo = Geometry::<Point>::from_raw(raw(), 0).unwrap();
+        assert!(!geo.has_point_attr("Cd"));
+        assert!(geo.extra.attr_info.is_none());
+    }
+
+    #[test]
+    fn computed_point_attribute() {
+        let mut geo = Geometry::<Point>::from_raw(raw(), 0).unwrap();
+        let density = RawAttribute {
+            tuple_size: 1,
+            data: RawAttributeData::Float(vec![0.5]),
+            meta: None,
+        };
+
+        let err = geo.set_point_attr("P", density.clone()).unwrap_err();
+        assert!(matches!(err, Error::AttrNameCollision(name) if name == "P"));
+
+        let too_long = RawAttribute {
+            tuple_size: 1,
+            data: RawAttributeData::Float(vec![0.5, 0.5]),
+            meta: None,
+        };
+        let err = geo.set_point_attr("density", too_long).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidAttributeLength {
+                expected: 1,
+                actual: 2
+            }
+        ));
+
+        geo.set_point_attr("density", density.clone()).unwrap();
+        let out = geo.into_raw().unwrap();
+        assert_eq!(out.points["density"], density);
+        assert!(out.points.contains_key("P"));
+    }
+
+    #[test]
+    fn point_attribute_stats() {
+        let geo: Geometry<Point> = [Vec3::new(0.0, 1.0, -2.0), Vec3::new(2.0, 3.0, 4.0)]
+            .into_iter()
+            .map(|position| Point { position })
+            .collect::<Vec<_>>()
+            .into();
+        let stats = geo.point_attr_stats("P").unwrap();
+        assert_eq!(stats.min, vec![0.0, 1.0, -2.0]);
+        assert_eq!(stats.max, vec![2.0, 3.0, 4.0]);
+        assert_eq!(stats.mean, vec![1.0, 2.0, 1.0]);
+
+        let mut geo = geo;
+        geo.set_point_attr(
+            "id",
+            RawAttribute {
+                tuple_size: 1,
+                data: RawAttributeData::Int(vec![3, 8]),
+                meta: None,
+            },
+        )
+        .unwrap();
+        let stats = geo.point_attr_stats("id").unwrap();
+        assert_eq!(
+            (stats.min, stats.max, stats.mean),
+            (vec![3.0], vec![8.0], vec![5.5])
+        );
+
+        let err = geo.point_attr_stats("Cd").unwrap_err();
+        assert_eq!(err.to_string(), "Unknown point attribute: Cd");
+
+        let empty: Geometry<Point> = Vec::new().into();
+        let empty = empty.point_attr_stats("P").unwrap();
+        assert!(empty.mean.iter().all(|v| v.is_nan()));
+        assert_eq!(empty.mean.len(), 3);
+    }
+
+    #[test]
+    fn map_untyped_point_attributes() {
+        let raw = || {
+            RawGeometry::fixture()
+                .point_attr("P", vec![Vec3::ZERO, Vec3::X])
+                .point_attr("pscale", vec![0.5f32, 2.0])
+                .point_attr("id", vec![1, 2])
+                .point_attr("v", vec![Vec3::Y, Vec3::Z])
+                .build()
+        };
+        let options = LoadOptions {
+            point_attrs: &["pscale", "id", "v"],
+            ..Default::default()
+        };
+        let mut geo = Geometry::<Point>::from_raw_with_options(raw(), 0, options).unwrap();
+
+        geo.map_point_attr_f32("pscale", |v| v * 2.0).unwrap();
+        geo.map_point_attr_i32("id", |v| v + 10).unwrap();
+        geo.map_point_attr_vec3("v", |v| -v).unwrap();
+        let out = geo.to_raw().unwrap();
+        assert_eq!(
+            out.points["pscale"].data,
+            RawAttributeData::Float(vec![1.0, 4.0])
+        );
+        assert_eq!(out.points["id"].data, RawAttributeData::Int(vec![11, 12]));
+        assert_eq!(
+            out.points["v"].data,
+            RawAttributeData::Float(vec![0.0, -1.0, 0.0, 0.0, 0.0, -1.0])
+        );
+
+        let err = geo.map_point_attr_f32("id", |v| v).unwrap_err();
+        assert!(matches!(err, Error::InvalidAttributeType { .. }));
+        let err = geo.map_point_attr_f32("v", |v| v).unwrap_err();
+        assert!(matches!(err, Error::InvalidAttributeLength { .. }));
+        let err = geo.map_point_attr_f32("Cd", |v| v).unwrap_err();
+        assert!(matches!(err, Error::UnknownAttr { .. }));
+
+        // Without retaining, the attribute is not available.
+        let mut geo = Geometry::<Point>::from_raw(raw(), 0).unwrap();
+        assert!(geo.map_point_attr_f32("pscale", |v| v).is_err());
+
+        let options = LoadOptions {
+            point_attrs: &["P"],
+            ..Default::default()
+        };
+        let err = Geometry::<Point>::from_raw_with_options(raw(), 0, options).unwrap_err();
+        assert!(matches!(err, Error::AttrNameCollision(name) if name == "P"));
+    }
+
+    #[test]
+    fn attribute_meta_round_trip() {
+        #[derive(OutAttrs, InAttrs)]
+        struct NormalPoint {
+            #[attr(name = "P")]
+            position: Vec3,
+            #[attr(name = "N")]
+            normal: Vec3,
+        }
+
+        let json = r#"[{
+            "points": {
+                "P": {"tuple_size": 3, "data": {"float": [0, 0, 0]}},
+                "N": {"tuple_size": 3, "data": {"float": [0, 1, 0]}, "meta": {"typeinfo": "normal"}}
+            },
+            "vertices": {}, "prims": {}, "detail": {}
+        }]"#;
+        let geo = crate::load_from_reader::<Geometry<NormalPoint>>(json.as_bytes(), 0).unwrap();
+        let out = geo.into_raw().unwrap();
+
+        let normal_meta = AttrMeta {
+            typeinfo: Some("normal".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(out.points["N"].meta, Some(normal_meta));
+        assert_eq!(out.points["P"].meta, None);
+
+        let json = serde_json::to_string(&out.points["N"]).unwrap();
+        assert!(json.ends_with(r#""meta":{"typeinfo":"normal"}}"#));
+        assert!(
+            !serde_json::to_string(&out.points["P"])
+                .unwrap()
+                .contains("meta")
+        );
+    }
+
+    #[test]
+    fn indexed_strings() {
+        #[derive(OutAttrs, InAttrs)]
+        struct MaterialPoint {
+            #[attr(name = "P")]
+            position: Vec3,
+            material: String,
+            label: String,
+        }
+
+        let json = r#"[{
+            "points": {
+                "P": {"tuple_size": 3, "data": {"float": [0, 0, 0, 1, 0, 0, 2, 0, 0]}},
+                "material": {"tuple_size": 1, "data": {"indexed_string": {
+                    "values": ["stone", "wood"],
+                    "indices": [1, 0, 1]
+                }}},
+                "label": {"tuple_size": 1, "data": {"string": ["a", "a", "b"]}}
+            },
+            "vertices": {}, "prims": {}, "detail": {}
+        }]"#;
+        let mut geo =
+            crate::load_from_reader::<Geometry<MaterialPoint>>(json.as_bytes(), 0).unwrap();
+        let materials: Vec<_> = geo.points.iter().map(|p| p.material.as_str()).collect();
+        assert_eq!(materials, ["wood", "stone", "wood"]);
+
+        geo.points[1].material = "wood".to_string();
+        geo.points[2].material = "glass".to_string();
+        let out = geo.into_raw().unwrap();
+        assert_eq!(
+            out.points["material"].data,
+            RawAttributeData::IndexedString {
+                values: vec!["wood".to_string(), "glass".to_string()],
+                indices: vec![0, 0, 1],
+            }
+        );
+        assert_eq!(out.points["material"].meta, None);
+        assert_eq!(
+            out.points["label"].data,
+            RawAttributeData::String(vec!["a".into(), "a".into(), "b".into()])
+        );
+
+        let invalid = RawAttributeData::IndexedString {
+            values: vec!["a".to_string()],
+            indices: vec![0, 1],
+        };
+        let err = invalid.string().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidStringIndex { index: 1, count: 1 }
+        ));
+    }
+
+    #[test]
+    fn raw_topology() {
+        let raw = || {
+            RawGeometry::fixture()
+                .point_attr("P", vec![Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::ONE])
+                .polygons(vec![vec![0, 1, 2], vec![1, 3, 2]])
+                .build()
+        };
+
+        let geo = Geometry::<Point>::from_raw(raw(), 0).unwrap();
+        assert_eq!(geo.raw_prim_vertices(), None);
+
+        let options = LoadOptions {
+            topology: true,
+            ..Default::default()
+        };
+        let geo = Geometry::<Point>::from_raw_with_options(raw(), 0, options).unwrap();
+        assert!(geo.prims.is_empty());
+        assert_eq!(
+            geo.raw_prim_vertices(),
+            Some(&[[0, 1, 2][..].into(), [3, 4, 5][..].into()][..])
+        );
+        assert_eq!(geo.raw_vertex_ptnums(), Some(&[0, 1, 2, 1, 3, 2][..]));
+    }
+
+    #[test]
+    fn transform_by_semantic() {
+        #[derive(OutAttrs)]
+        struct ShadedPoint {
+            #[attr(name = "P")]
+            position: Vec3,
+            #[attr(name = "N", semantic = "normal")]
+            normal: Vec3,
+            #[attr(name = "v", semantic = "vector")]
+            velocity: Vec3,
+            #[attr(name = "Cd", semantic = "color")]
+            color: Vec3,
+        }
+
+        let diagonal = Vec3::new(1.0, 1.0, 0.0);
+        let geo: Geometry<ShadedPoint> = vec![ShadedPoint {
+            position: diagonal,
+            normal: diagonal.normalize(),
+            velocity: diagonal,
+            color: diagonal,
+        }]
+        .into();
+        let mut out = geo.into_raw().unwrap();
+        assert_eq!(out.points["N"].semantic(), Some(AttrSemantic::Normal));
+        assert_eq!(out.points["P"].semantic(), None);
+
+        // Stretches along x and moves along z.
+        let matrix = Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 1.0),
+            glam::Quat::IDENTITY,
+            Vec3::Z,
+        );
+        out.transform(matrix);
+
+        let value = |name: &str| {
+            let RawAttributeData::Float(values) = &out.points[name].data else {
+                panic!("expected float data");
+            };
+            Vec3::from_slice(values)
+        };
+        assert_eq!(value("P"), Vec3::new(2.0, 1.0, 1.0));
+        assert_eq!(value("v"), Vec3::new(2.0, 1.0, 0.0));
+        // Normals stay perpendicular to the stretched surface.
+        assert!(value("N").abs_diff_eq(Vec3::new(0.5, 1.0, 0.0).normalize(), 1e-6));
+        assert_eq!(value("Cd"), diagonal);
+    }
+}
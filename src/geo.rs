@@ -0,0 +1,275 @@
+//! Export to the JSON geometry format of Houdini (`.geo`), which can be loaded by a File SOP
+//! without the Python bridge.
+
+use crate::{Error, RawAttribute, RawAttributeData, RawGeometryOutput, Result};
+use serde_json::{Value, json};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+impl RawGeometryOutput {
+    /// Writes the geometry as a Houdini `.geo` file. Every prim becomes a closed polygon, with
+    /// the vertices numbered in prim order, like the Python bridge creates them. Array attributes
+    /// are not supported.
+    pub fn to_houdini_geo(&self, writer: impl Write) -> Result<()> {
+        let prim_points = match self.prims.get("points") {
+            Some(attr) => attr.data.clone().prim_vertex()?,
+            None => Vec::new(),
+        };
+        let point_count = self
+            .points
+            .values()
+            .map(RawAttribute::num_elements)
+            .max()
+            .unwrap_or(0);
+        let vertex_count = prim_points.iter().map(|points| points.len()).sum::<usize>();
+
+        let mut next_vertex = 0;
+        let primitives: Vec<Value> = prim_points
+            .iter()
+            .map(|points| {
+                let vertices: Vec<_> = (next_vertex..next_vertex + points.len()).collect();
+                next_vertex += points.len();
+                json!([["type", "Poly"], ["vertex", vertices, "closed", true]])
+            })
+            .collect();
+
+        let geo = json!([
+            "fileversion", "20.0",
+            "hasindex", false,
+            "pointcount", point_count,
+            "vertexcount", vertex_count,
+            "primitivecount", prim_points.len(),
+            "info", { "software": "houdini-node" },
+            "topology", [
+                "pointref", ["indices", prim_points.concat()],
+            ],
+            "attributes", [
+                "vertexattributes", attributes(&self.vertices, &["ptnum"])?,
+                "pointattributes", attributes(&self.points, &[])?,
+                "primitiveattributes", attributes(&self.prims, &["points"])?,
+                "globalattributes", attributes(&self.detail, &[])?,
+            ],
+            "primitives", primitives,
+        ]);
+
+        let mut writer = writer;
+        serde_json::to_writer(&mut writer, &geo)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The attributes of one entity, skipping the topology pseudo-attributes in `skip`.
+fn attributes(
+    attrs: &BTreeMap<Cow<'static, str>, RawAttribute>,
+    skip: &[&str],
+) -> Result<Vec<Value>> {
+    attrs
+        .iter()
+        .filter(|(name, _)| !skip.contains(&name.as_ref()))
+        .map(|(name, attr)| attribute(name, attr))
+        .collect()
+}
+
+/// An attribute as a pair of definition and values.
+fn attribute(name: &str, attr: &RawAttribute) -> Result<Value> {
+    let size = attr.tuple_size;
+    // Single components are stored as one array, tuples as a list of tuples.
+    let packed = |storage: &str, values: Value| match size {
+        1 => json!(["size", 1, "storage", storage, "arrays", [values]]),
+        _ => json!([
+            "size",
+            size,
+            "storage",
+            storage,
+            "tuples",
+            tuples(values, size)
+        ]),
+    };
+    let numeric = |storage: &str, values: Value| {
+        let values = packed(storage, values);
+        json!([
+            ["scope", "public", "type", "numeric", "name", name],
+            ["size", size, "storage", storage, "values", values],
+        ])
+    };
+    let string = |strings: &[String], indices: &[usize]| {
+        json!([
+            ["scope", "public", "type", "string", "name", name],
+            [
+                "size",
+                size,
+                "storage",
+                "int32",
+                "strings",
+                strings,
+                "indices",
+                packed("int32", json!(indices)),
+            ],
+        ])
+    };
+
+    Ok(match &attr.data {
+        RawAttributeData::Float(v) => numeric("fpreal32", json!(v)),
+        RawAttributeData::Float64(v) => numeric("fpreal64", json!(v)),
+        RawAttributeData::Int(v) => numeric("int32", json!(v)),
+        RawAttributeData::Int64(v) => numeric("int64", json!(v)),
+        RawAttributeData::Index(v) => numeric("int64", json!(v)),
+        RawAttributeData::IndexedString { values, indices } => string(values, indices),
+        RawAttributeData::String(_) => {
+            let RawAttributeData::IndexedString { values, indices } =
+                attr.data.clone().into_indexed_strings()
+            else {
+                unreachable!("string data is always indexed")
+            };
+            string(&values, &indices)
+        }
+        other => {
+            return Err(Error::UnsupportedGeoAttribute {
+                attr: name.to_string(),
+                kind: other.kind(),
+            });
+        }
+    })
+}
+
+/// Splits interleaved values into tuples of `size` components.
+fn tuples(values: Value, size: usize) -> Value {
+    match values {
+        Value::Array(values) => values
+            .chunks(size)
+            .map(|tuple| Value::Array(tuple.to_vec()))
+            .collect(),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{Geometry, IntoRawGeometry, OutAttrs};
+    use glam::Vec3;
+
+    #[derive(OutAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Vec3,
+    }
+
+    #[derive(OutAttrs)]
+    struct Vertex {
+        ptnum: usize,
+    }
+
+    #[derive(OutAttrs)]
+    struct Prim {
+        vertices: Vec<usize>,
+        name: String,
+    }
+
+    /// A triangle and a quad sharing an edge.
+    fn mesh() -> RawGeometryOutput {
+        let geo = Geometry {
+            points: [Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::ONE, Vec3::Z]
+                .into_iter()
+                .map(|position| Point { position })
+                .collect(),
+            vertices: [0, 1, 2, 1, 3, 4, 2]
+                .into_iter()
+                .map(|ptnum| Vertex { ptnum })
+                .collect(),
+            prims: vec![
+                Prim {
+                    vertices: vec![0, 1, 2],
+                    name: "tri".to_string(),
+                },
+                Prim {
+                    vertices: vec![3, 4, 5, 6],
+                    name: "quad".to_string(),
+                },
+            ],
+            detail: (),
+            extra: Default::default(),
+        };
+        geo.into_raw().unwrap()
+    }
+
+    /// Turns the alternating keys and values of a `.geo` section into a map.
+    fn section(value: &Value) -> BTreeMap<String, Value> {
+        value
+            .as_array()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| (pair[0].as_str().unwrap().to_string(), pair[1].clone()))
+            .collect()
+    }
+
+    #[test]
+    fn geo_structure() {
+        let mut bytes = Vec::new();
+        mesh().to_houdini_geo(&mut bytes).unwrap();
+        let geo: Value = serde_json::from_slice(&bytes).unwrap();
+
+        let keys: Vec<_> = geo
+            .as_array()
+            .unwrap()
+            .iter()
+            .step_by(2)
+            .map(|key| key.as_str().unwrap())
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "fileversion",
+                "hasindex",
+                "pointcount",
+                "vertexcount",
+                "primitivecount",
+                "info",
+                "topology",
+                "attributes",
+                "primitives",
+            ]
+        );
+
+        let geo = section(&geo);
+        assert_eq!(geo["pointcount"], 5);
+        assert_eq!(geo["vertexcount"], 7);
+        assert_eq!(geo["primitivecount"], 2);
+        assert_eq!(
+            section(&geo["topology"])["pointref"],
+            json!(["indices", [0, 1, 2, 1, 3, 4, 2]])
+        );
+        assert_eq!(
+            geo["primitives"][1],
+            json!([["type", "Poly"], ["vertex", [3, 4, 5, 6], "closed", true]])
+        );
+
+        let attributes = section(&geo["attributes"]);
+        assert_eq!(attributes["vertexattributes"], json!([]));
+        let position = &attributes["pointattributes"][0];
+        assert_eq!(position[0][5], "P");
+        assert_eq!(position[1][5][5][1], json!([1.0, 0.0, 0.0]));
+        let name = &attributes["primitiveattributes"][0];
+        assert_eq!(name[0][3], "string");
+        assert_eq!(name[1][5], json!(["tri", "quad"]));
+    }
+
+    #[test]
+    fn array_attributes_unsupported() {
+        let mut output = mesh();
+        output.detail.insert(
+            "weights".into(),
+            RawAttribute {
+                tuple_size: 1,
+                data: RawAttributeData::FloatArray(vec![vec![1.0]]),
+                meta: None,
+            },
+        );
+        let err = output.to_houdini_geo(Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedGeoAttribute { attr, .. } if attr == "weights"));
+    }
+}
@@ -0,0 +1,186 @@
+//! Tolerant reading of numeric attribute data whose values drifted from its type:
+//!
+//! - floats in `int` data are read as float data instead of failing to parse.
+//! - integer data is accepted by [`RawAttributeData::float`], [`RawAttributeData::float64`] and
+//!   [`RawAttributeData::float_array`], so float fields can be read from it.
+//!
+//! Both report a warning to Houdini.
+
+use crate::{AttributeType, PrimVertices, RawAttributeData, non_finite, report};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use serde_json::Number;
+
+/// Mirrors [`RawAttributeData`], with integer data that may contain non-integer values. It is
+/// parsed in the same single pass, only the integer data is checked afterwards.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Data {
+    Float(#[serde(deserialize_with = "non_finite::floats::deserialize")] Vec<f32>),
+    FloatArray(#[serde(deserialize_with = "non_finite::float_arrays::deserialize")] Vec<Vec<f32>>),
+    Int(Vec<Number>),
+    IntArray(Vec<Vec<Number>>),
+    String(Vec<String>),
+    StringArray(Vec<Vec<String>>),
+    Index(Vec<Number>),
+    PrimVertex(Vec<PrimVertices>),
+    Int64(Vec<Number>),
+    Float64(#[serde(deserialize_with = "non_finite::doubles::deserialize")] Vec<f64>),
+    IndexedString {
+        values: Vec<String>,
+        indices: Vec<usize>,
+    },
+}
+
+/// `serde(deserialize_with)` for the `data` of an attribute. Data that reads strictly is left
+/// untouched, integer data with non-integer values is read as floats.
+pub(crate) fn data<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<RawAttributeData, D::Error> {
+    let (kind, data) = match Data::deserialize(deserializer)? {
+        Data::Int(v) => match ints(&v) {
+            Some(v) => return Ok(RawAttributeData::Int(v)),
+            None => (AttributeType::Int, RawAttributeData::Float(floats(&v)?)),
+        },
+        Data::Index(v) => match ints(&v) {
+            Some(v) => return Ok(RawAttributeData::Index(v)),
+            None => (AttributeType::Index, RawAttributeData::Float(floats(&v)?)),
+        },
+        Data::Int64(v) => match ints(&v) {
+            Some(v) => return Ok(RawAttributeData::Int64(v)),
+            None => (AttributeType::Int64, RawAttributeData::Float64(floats(&v)?)),
+        },
+        Data::IntArray(v) => match v.iter().map(|a| ints(a)).collect() {
+            Some(v) => return Ok(RawAttributeData::IntArray(v)),
+            None => (
+                AttributeType::IntArray,
+                RawAttributeData::FloatArray(
+                    v.iter().map(|a| floats(a)).collect::<Result<_, _>>()?,
+                ),
+            ),
+        },
+        Data::Float(v) => return Ok(RawAttributeData::Float(v)),
+        Data::FloatArray(v) => return Ok(RawAttributeData::FloatArray(v)),
+        Data::String(v) => return Ok(RawAttributeData::String(v)),
+        Data::StringArray(v) => return Ok(RawAttributeData::StringArray(v)),
+        Data::PrimVertex(v) => return Ok(RawAttributeData::PrimVertex(v)),
+        Data::Float64(v) => return Ok(RawAttributeData::Float64(v)),
+        Data::IndexedString { values, indices } => {
+            return Ok(RawAttributeData::IndexedString { values, indices });
+        }
+    };
+    report::warn(format!(
+        "reading `{kind}` attribute data with non-integer values as floats"
+    ));
+    Ok(data)
+}
+
+/// `None` if any of the values isn't an integer in the range of `T`.
+fn ints<T: TryFrom<i64>>(values: &[Number]) -> Option<Vec<T>> {
+    values
+        .iter()
+        .map(|v| T::try_from(v.as_i64()?).ok())
+        .collect()
+}
+
+fn floats<T: FromF64, E: Error>(values: &[Number]) -> Result<Vec<T>, E> {
+    values
+        .iter()
+        .map(|v| {
+            v.as_f64()
+                .map(T::from_f64)
+                .ok_or_else(|| E::custom(format!("number {v} out of range")))
+        })
+        .collect()
+}
+
+trait FromF64 {
+    fn from_f64(v: f64) -> Self;
+}
+
+impl FromF64 for f32 {
+    fn from_f64(v: f64) -> Self {
+        v as f32
+    }
+}
+
+impl FromF64 for f64 {
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+}
+
+/// Integer data read by one of the float accessors of [`RawAttributeData`].
+pub(crate) fn ints_as_floats<T, F>(
+    kind: AttributeType,
+    data: Vec<T>,
+    f: impl Fn(T) -> F,
+) -> Vec<F> {
+    report::warn(format!("reading `{kind}` attribute data as floats"));
+    data.into_iter().map(f).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use crate::{EntityKind, ErrContext, InAttrs, RawAttribute, RawAttributeData};
+    use std::collections::HashMap;
+
+    #[derive(InAttrs)]
+    struct Point {
+        pscale: f32,
+    }
+
+    fn read_pscale(attr: RawAttribute) -> Vec<f32> {
+        let context = ErrContext {
+            input_index: 0,
+            entity: EntityKind::Point,
+        };
+        Point::from_attr(HashMap::from([("pscale".to_string(), attr)]), context)
+            .unwrap()
+            .map(|p| p.pscale)
+            .collect()
+    }
+
+    #[test]
+    fn floats_in_int_data() {
+        let attr: RawAttribute =
+            serde_json::from_str(r#"{"tuple_size": 1, "data": {"int": [1, 2.5, 3]}}"#).unwrap();
+        assert_eq!(attr.data, RawAttributeData::Float(vec![1.0, 2.5, 3.0]));
+        assert_eq!(read_pscale(attr), vec![1.0, 2.5, 3.0]);
+
+        let attr: RawAttribute =
+            serde_json::from_str(r#"{"tuple_size": 1, "data": {"int_array": [[1], [2.5]]}}"#)
+                .unwrap();
+        assert_eq!(
+            attr.data,
+            RawAttributeData::FloatArray(vec![vec![1.0], vec![2.5]])
+        );
+
+        let err =
+            serde_json::from_str::<RawAttribute>(r#"{"tuple_size": 1, "data": {"string": [1.5]}}"#);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn ints_into_float_fields() {
+        // Strictly valid data is left as is.
+        let attr: RawAttribute =
+            serde_json::from_str(r#"{"tuple_size": 1, "data": {"int": [1, 2]}}"#).unwrap();
+        assert_eq!(attr.data, RawAttributeData::Int(vec![1, 2]));
+        assert_eq!(read_pscale(attr), vec![1.0, 2.0]);
+
+        assert_eq!(
+            RawAttributeData::Int64(vec![3]).float64().unwrap(),
+            vec![3.0]
+        );
+        assert_eq!(
+            RawAttributeData::IntArray(vec![vec![4]])
+                .float_array()
+                .unwrap(),
+            vec![vec![4.0]]
+        );
+        assert!(RawAttributeData::String(vec![]).float().is_err());
+    }
+}
@@ -2,51 +2,604 @@
 
 mod attribute_data_basic;
 mod attribute_types;
+pub mod binary;
+#[cfg(feature = "blob")]
+mod blob;
+pub mod cancel;
+mod csv;
+#[doc(hidden)]
+pub mod derive_support;
+mod diff;
+mod dynamic;
+#[cfg(feature = "euclid")]
+mod euclid_types;
+mod extra;
+mod geo;
+#[cfg(feature = "lenient")]
+mod lenient;
+mod non_finite;
+mod projection;
+mod ramp;
+pub mod report;
+#[cfg(feature = "simd")]
+mod simd;
+mod snapshot;
+mod spatial;
+mod stream;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod topology;
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Display, Formatter};
 use std::iter;
 
 use crate::Error::MissingAttr;
-pub use houdini_node_macro::{InAttrs, OutAttrs, houdini_node_main};
+pub use attribute_types::{
+    DynVec, GlamVec, NormalizedQuat, PointRef, RowMajorMat3, RowMajorMat4, StringEncoded, Uv,
+};
+#[cfg(feature = "blob")]
+pub use blob::Blob;
+pub use diff::Difference;
+pub use dynamic::DynamicAttrs;
+pub use extra::{
+    AttrDesc, AttrInfo, AttrMeta, AttrMetaMap, AttrSemantic, AttrStats, GeometryExtra, LoadOptions,
+    RawTopology,
+};
+pub use glam;
+pub use houdini_node_macro::{
+    InAttrs, OutAttrs, PointPosition, PrimTopology, VertexTopology, houdini_node_main,
+};
 /// Re-export itertools as it is used in the derive macros.
 pub use itertools;
-
-/// The geometry that gets (de)serialized between Houdini and this script.
-#[derive(Debug, Deserialize)]
+pub use projection::{Projection, load_raw_projected};
+pub use ramp::{AttrGroup, Ramp, RampBasis, RampKey, generate_attr_group, load_attr_group};
+pub use snapshot::SharedGeometry;
+pub use spatial::{PointCache, PointIndex, PointPosition, PointPositionMut};
+pub use topology::{PrimTopology, Promotion, SubsetPolicy, TopologyChange, VertexTopology};
+
+/// The geometry that gets (de)serialized between Houdini and this script. A missing or `null`
+/// entity section is read as an empty one.
+#[derive(Debug, PartialEq, Deserialize)]
 pub struct RawGeometry {
+    #[serde(default, deserialize_with = "null_as_empty")]
     pub points: HashMap<String, RawAttribute>,
+    #[serde(default, deserialize_with = "null_as_empty")]
     pub vertices: HashMap<String, RawAttribute>,
+    #[serde(default, deserialize_with = "null_as_empty")]
     pub prims: HashMap<String, RawAttribute>,
+    #[serde(default, deserialize_with = "null_as_empty")]
     pub detail: HashMap<String, RawAttribute>,
 }
 
-#[derive(Debug, Serialize)]
+fn null_as_empty<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<HashMap<String, RawAttribute>, D::Error> {
+    Ok(Option::deserialize(deserializer)?.unwrap_or_default())
+}
+
+impl RawGeometry {
+    /// Removes the point attribute, returning whether it was present.
+    pub fn drop_point_attribute(&mut self, name: &str) -> bool {
+        self.points.remove(name).is_some()
+    }
+
+    /// Like [`RawGeometry::drop_point_attribute`], for vertex attributes.
+    pub fn drop_vertex_attribute(&mut self, name: &str) -> bool {
+        self.vertices.remove(name).is_some()
+    }
+
+    /// Like [`RawGeometry::drop_point_attribute`], for prim attributes.
+    pub fn drop_prim_attribute(&mut self, name: &str) -> bool {
+        self.prims.remove(name).is_some()
+    }
+
+    /// Like [`RawGeometry::drop_point_attribute`], for detail attributes.
+    pub fn drop_detail_attribute(&mut self, name: &str) -> bool {
+        self.detail.remove(name).is_some()
+    }
+
+    /// Loads the points as `Pt`, leaving the other entities untyped. The returned geometry has the
+    /// point attributes that `Pt` doesn't read, and all vertex, prim and detail attributes. Element
+    /// numbers are resolved like in [`FromRawGeometry::from_raw`].
+    pub fn into_typed_parts<Pt: InAttrs>(
+        mut self,
+        input_index: usize,
+    ) -> Result<(Vec<Pt>, RawGeometry)> {
+        self.resolve_element_numbers()?;
+        self.check_prim_vertices()?;
+
+        let mut typed: HashMap<_, _> = match in_attrs::<Pt>() {
+            Some(attrs) => attrs
+                .iter()
+                .filter_map(|(name, _)| self.points.remove_entry(*name))
+                .collect(),
+            None => std::mem::take(&mut self.points),
+        };
+        // Without any of its attributes, `Pt` takes the point count from the others, see
+        // `element_count`.
+        if typed.is_empty()
+            && let Some((name, attr)) = self
+                .points
+                .get_key_value("P")
+                .or_else(|| self.points.iter().next())
+        {
+            typed.insert(name.clone(), attr.clone());
+        }
+        let points = Pt::from_attr(
+            typed,
+            ErrContext {
+                input_index,
+                entity: EntityKind::Point,
+            },
+        )?
+        .collect();
+        Ok((points, self))
+    }
+
+    /// Checks that the prim `vertices` only refer to existing vertices. Skipped if there are no
+    /// vertex attributes to take the count from. Called by [`FromRawGeometry::from_raw`].
+    pub fn check_prim_vertices(&self) -> Result<()> {
+        let Some(vertex_count) = self
+            .vertices
+            .get("ptnum")
+            .or_else(|| self.vertices.values().next())
+            .map(RawAttribute::num_elements)
+        else {
+            return Ok(());
+        };
+        let Some(RawAttributeData::PrimVertex(prims)) = self.prims.get("vertices").map(|a| &a.data)
+        else {
+            return Ok(());
+        };
+
+        for (prim_index, vertices) in prims.iter().enumerate() {
+            if let Some(&vertex) = vertices.iter().find(|&&v| v >= vertex_count) {
+                return Err(Error::InvalidInPrimVertex { prim_index, vertex });
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrites the topology to dense indices if the points or vertices come with an
+    /// [`ELEMNUM_ATTR`] attribute, i.e. their element numbers aren't `0..n`. Vertex `ptnum`s are
+    /// looked up in the point numbers and prim `vertices` in the vertex numbers. The element number
+    /// attributes are removed. Called by [`FromRawGeometry::from_raw`].
+    pub fn resolve_element_numbers(&mut self) -> Result<()> {
+        if let Some(numbers) = self.points.remove(ELEMNUM_ATTR) {
+            let lookup = element_lookup(numbers)?;
+            if let Some(ptnum) = self.vertices.get_mut("ptnum") {
+                let RawAttributeData::Index(ptnums) = &mut ptnum.data else {
+                    return ptnum.data.err(AttributeType::Index);
+                };
+                for ptnum in ptnums {
+                    *ptnum = resolve_element(&lookup, EntityKind::Point, *ptnum)?;
+                }
+            }
+        }
+        if let Some(numbers) = self.vertices.remove(ELEMNUM_ATTR) {
+            let lookup = element_lookup(numbers)?;
+            if let Some(vertices) = self.prims.get_mut("vertices") {
+                for vertex in vertices.data.prim_vertex_iter_mut()?.flatten() {
+                    *vertex = resolve_element(&lookup, EntityKind::Vertex, *vertex)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Name of the optional point and vertex attribute holding the element number of every element,
+/// for inputs whose numbers aren't contiguous. See [`RawGeometry::resolve_element_numbers`].
+pub const ELEMNUM_ATTR: &str = "__elemnum";
+
+/// Maps element numbers to their index in the attribute arrays.
+fn element_lookup(numbers: RawAttribute) -> Result<HashMap<usize, usize>> {
+    let numbers: Vec<usize> = match numbers.data {
+        RawAttributeData::Index(numbers) => numbers,
+        RawAttributeData::Int(numbers) => numbers
+            .into_iter()
+            .map(|number| {
+                usize::try_from(number).map_err(|_| Error::IntOutOfRange {
+                    attr: ELEMNUM_ATTR,
+                    value: i64::from(number),
+                    target: "usize",
+                })
+            })
+            .collect::<Result<_>>()?,
+        other => return other.err(AttributeType::Index),
+    };
+    Ok(numbers
+        .into_iter()
+        .enumerate()
+        .map(|(index, number)| (number, index))
+        .collect())
+}
+
+fn resolve_element(
+    lookup: &HashMap<usize, usize>,
+    entity: EntityKind,
+    number: usize,
+) -> Result<usize> {
+    lookup
+        .get(&number)
+        .copied()
+        .ok_or(Error::UnknownElementNumber { entity, number })
+}
+
+/// Human-readable summary, one line per entity, e.g.
+/// `points: 1024 [Cd: float3, P: float3, name: string]`.
+impl Display for RawGeometry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let entities = [
+            ("points", &self.points),
+            ("vertices", &self.vertices),
+            ("prims", &self.prims),
+            ("detail", &self.detail),
+        ];
+
+        for (i, (entity, attrs)) in entities.into_iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            let count = attrs
+                .values()
+                .map(RawAttribute::num_elements)
+                .max()
+                .unwrap_or(0);
+            write!(f, "{entity}: {count} [")?;
+
+            let mut names: Vec<_> = attrs.keys().collect();
+            names.sort();
+            for (j, name) in names.into_iter().enumerate() {
+                if j > 0 {
+                    write!(f, ", ")?;
+                }
+                let attr = &attrs[name];
+                write!(f, "{name}: {}", attr.data.kind())?;
+                if attr.tuple_size > 1 {
+                    write!(f, "{}", attr.tuple_size)?;
+                }
+            }
+            write!(f, "]")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Attributes are kept sorted by name so that the serialized output is deterministic. The names
+/// are only owned if they were renamed, see [`OutputOptions::renames`].
+#[derive(Debug, Default, PartialEq, Serialize)]
 pub struct RawGeometryOutput {
-    pub points: HashMap<&'static str, RawAttribute>,
-    pub vertices: HashMap<&'static str, RawAttribute>,
-    pub prims: HashMap<&'static str, RawAttribute>,
-    pub detail: HashMap<&'static str, RawAttribute>,
+    pub points: BTreeMap<Cow<'static, str>, RawAttribute>,
+    pub vertices: BTreeMap<Cow<'static, str>, RawAttribute>,
+    pub prims: BTreeMap<Cow<'static, str>, RawAttribute>,
+    pub detail: BTreeMap<Cow<'static, str>, RawAttribute>,
+}
+
+impl RawGeometryOutput {
+    /// Moves all attributes of `other` into `self`. Fails without modifying `self` if an
+    /// attribute exists in both.
+    pub fn merge(&mut self, other: RawGeometryOutput) -> Result<()> {
+        let sections = [
+            (&self.points, &other.points),
+            (&self.vertices, &other.vertices),
+            (&self.prims, &other.prims),
+            (&self.detail, &other.detail),
+        ];
+        for (ours, theirs) in sections {
+            if let Some(name) = theirs.keys().find(|name| ours.contains_key(*name)) {
+                return Err(Error::AttrNameCollision(name.clone()));
+            }
+        }
+
+        self.points.extend(other.points);
+        self.vertices.extend(other.vertices);
+        self.prims.extend(other.prims);
+        self.detail.extend(other.detail);
+        Ok(())
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawAttributeJson")]
 pub struct RawAttribute {
+    /// Defaults to 1 when missing from the JSON, except for array attributes.
     pub tuple_size: usize,
     pub data: RawAttributeData,
+    /// Not part of the binary format, and left out of the JSON when `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<AttrMeta>,
+}
+
+/// Some exporters leave out the `tuple_size` of scalar attributes.
+#[derive(Deserialize)]
+struct RawAttributeJson {
+    tuple_size: Option<usize>,
+    #[cfg_attr(feature = "lenient", serde(deserialize_with = "lenient::data"))]
+    data: RawAttributeData,
+    #[serde(default)]
+    meta: Option<AttrMeta>,
+}
+
+impl TryFrom<RawAttributeJson> for RawAttribute {
+    type Error = String;
+
+    fn try_from(json: RawAttributeJson) -> std::result::Result<Self, Self::Error> {
+        let is_array = matches!(
+            json.data.kind(),
+            AttributeType::FloatArray | AttributeType::IntArray | AttributeType::StringArray
+        );
+        let tuple_size = match json.tuple_size {
+            Some(tuple_size) => tuple_size,
+            None if is_array => {
+                return Err(format!(
+                    "missing field `tuple_size` for {} attribute",
+                    json.data.kind()
+                ));
+            }
+            None => 1,
+        };
+        Ok(RawAttribute {
+            tuple_size,
+            data: json.data,
+            meta: json.meta,
+        })
+    }
+}
+
+impl RawAttribute {
+    /// The number of entities this attribute has values for.
+    pub fn num_elements(&self) -> usize {
+        match self.data.kind() {
+            AttributeType::Float
+            | AttributeType::Int
+            | AttributeType::Int64
+            | AttributeType::Float64
+            | AttributeType::String
+            | AttributeType::IndexedString => self.data.len() / self.tuple_size.max(1),
+            _ => self.data.len(),
+        }
+    }
+
+    /// Extracts a single component as a new attribute with a tuple size of `1`. Only works for
+    /// non-array data.
+    pub fn component(&self, index: usize) -> Result<RawAttribute> {
+        fn extract<T: Clone>(data: &[T], tuple_size: usize, index: usize) -> Vec<T> {
+            data.iter()
+                .skip(index)
+                .step_by(tuple_size)
+                .cloned()
+                .collect()
+        }
+
+        if index >= self.tuple_size {
+            return Err(Error::InvalidAttributeLength {
+                expected: index + 1,
+                actual: self.tuple_size,
+            });
+        }
+
+        let n = self.tuple_size;
+        let data = match &self.data {
+            RawAttributeData::Float(v) => RawAttributeData::Float(extract(v, n, index)),
+            RawAttributeData::Int(v) => RawAttributeData::Int(extract(v, n, index)),
+            RawAttributeData::Int64(v) => RawAttributeData::Int64(extract(v, n, index)),
+            RawAttributeData::Float64(v) => RawAttributeData::Float64(extract(v, n, index)),
+            RawAttributeData::String(v) => RawAttributeData::String(extract(v, n, index)),
+            RawAttributeData::Index(v) => RawAttributeData::Index(extract(v, n, index)),
+            RawAttributeData::IndexedString { values, indices } => {
+                RawAttributeData::IndexedString {
+                    values: values.clone(),
+                    indices: extract(indices, n, index),
+                }
+            }
+            other => return Err(Error::UnsupportedTupleResize(other.kind())),
+        };
+        Ok(RawAttribute {
+            tuple_size: 1,
+            data,
+            meta: None,
+        })
+    }
+
+    /// Interleaves single-component attributes into one attribute, the inverse of
+    /// [`RawAttribute::component`]. Fails with [`Error::InvalidAttributeLength`] if there are no
+    /// components, or if they don't all have a tuple size of `1` and the same length. The
+    /// components must have the same scalar non-array type.
+    pub fn from_components(components: Vec<RawAttribute>) -> Result<RawAttribute> {
+        fn interleave<T>(
+            data: Vec<RawAttributeData>,
+            values: impl Fn(RawAttributeData) -> Result<Vec<T>>,
+        ) -> Result<Vec<T>> {
+            let components = data.into_iter().map(values).collect::<Result<Vec<_>>>()?;
+            let len = components.first().map_or(0, Vec::len);
+            if let Some(c) = components.iter().find(|c| c.len() != len) {
+                return Err(Error::InvalidAttributeLength {
+                    expected: len,
+                    actual: c.len(),
+                });
+            }
+            let mut iters: Vec<_> = components.into_iter().map(Vec::into_iter).collect();
+            Ok((0..len)
+                .flat_map(|_| {
+                    iters
+                        .iter_mut()
+                        .map(|it| it.next().unwrap())
+                        .collect::<Vec<_>>()
+                })
+                .collect())
+        }
+
+        if let Some(c) = components.iter().find(|c| c.tuple_size != 1) {
+            return Err(Error::InvalidAttributeLength {
+                expected: 1,
+                actual: c.tuple_size,
+            });
+        }
+        let tuple_size = components.len();
+        let data: Vec<_> = components.into_iter().map(|c| c.data).collect();
+        let Some(kind) = data.first().map(RawAttributeData::kind) else {
+            return Err(Error::InvalidAttributeLength {
+                expected: 1,
+                actual: 0,
+            });
+        };
+        if let Some(other) = data.iter().find(|d| d.kind() != kind) {
+            return other.err(kind);
+        }
+        let data = match kind {
+            AttributeType::Float => RawAttributeData::Float(interleave(data, |d| d.float())?),
+            AttributeType::Int => RawAttributeData::Int(interleave(data, |d| d.int())?),
+            AttributeType::Int64 => RawAttributeData::Int64(interleave(data, |d| d.int64())?),
+            AttributeType::Float64 => RawAttributeData::Float64(interleave(data, |d| d.float64())?),
+            AttributeType::String => RawAttributeData::String(interleave(data, |d| d.string())?),
+            AttributeType::Index => RawAttributeData::Index(interleave(data, |d| d.index())?),
+            other => return Err(Error::UnsupportedTupleResize(other)),
+        };
+        Ok(RawAttribute {
+            tuple_size,
+            data,
+            meta: None,
+        })
+    }
+
+    /// Coerces the data to another type in place:
+    ///
+    /// - float to int rounds to the nearest integer, with halfway cases away from zero. Values
+    ///   outside of the `i32` range saturate and NaN becomes `0`.
+    /// - int to float is exact up to 2^24 and rounds to the nearest representable float beyond.
+    /// - int to string uses the decimal representation.
+    /// - strings and indexed strings convert into each other without changing the values.
+    ///
+    /// The same applies to the array variants. Converting to the current type does nothing, any
+    /// other conversion fails without modifying the attribute.
+    pub fn convert_to(&mut self, target: AttributeType) -> Result<()> {
+        fn float_to_int(v: &f32) -> i32 {
+            v.round() as i32
+        }
+        fn int_to_float(v: &i32) -> f32 {
+            *v as f32
+        }
+        fn map<T, U>(v: &[T], f: impl Fn(&T) -> U) -> Vec<U> {
+            v.iter().map(f).collect()
+        }
+
+        let data = match (&self.data, target) {
+            (data, target) if data.kind() == target => return Ok(()),
+            (RawAttributeData::Float(v), AttributeType::Int) => {
+                RawAttributeData::Int(map(v, float_to_int))
+            }
+            (RawAttributeData::Int(v), AttributeType::Float) => {
+                RawAttributeData::Float(map(v, int_to_float))
+            }
+            (RawAttributeData::Int(v), AttributeType::String) => {
+                RawAttributeData::String(map(v, i32::to_string))
+            }
+            (RawAttributeData::IndexedString { .. }, AttributeType::String) => {
+                RawAttributeData::String(self.data.clone().string()?)
+            }
+            (RawAttributeData::String(v), AttributeType::IndexedString) => {
+                RawAttributeData::String(v.clone()).into_indexed_strings()
+            }
+            (RawAttributeData::FloatArray(v), AttributeType::IntArray) => {
+                RawAttributeData::IntArray(map(v, |v| map(v, float_to_int)))
+            }
+            (RawAttributeData::IntArray(v), AttributeType::FloatArray) => {
+                RawAttributeData::FloatArray(map(v, |v| map(v, int_to_float)))
+            }
+            (RawAttributeData::IntArray(v), AttributeType::StringArray) => {
+                RawAttributeData::StringArray(map(v, |v| map(v, i32::to_string)))
+            }
+            (data, target) => {
+                return Err(Error::UnsupportedConversion {
+                    from: data.kind(),
+                    to: target,
+                });
+            }
+        };
+        self.data = data;
+        Ok(())
+    }
+
+    /// Changes the tuple size by dropping surplus components or padding missing ones. Padded
+    /// components are `0`, except for the fourth one which is `1` so that positions become
+    /// homogeneous coordinates. Only works for non-array data.
+    pub fn resize_tuples(&mut self, tuple_size: usize) -> Result<()> {
+        fn resize<T: Clone>(data: &mut Vec<T>, from: usize, to: usize, pad: impl Fn(usize) -> T) {
+            if from == 0 {
+                data.clear();
+                return;
+            }
+            *data = data
+                .chunks(from)
+                .flat_map(|tuple| (0..to).map(|i| tuple.get(i).cloned().unwrap_or_else(|| pad(i))))
+                .collect();
+        }
+
+        let from = self.tuple_size;
+        if from == tuple_size {
+            return Ok(());
+        }
+
+        match &mut self.data {
+            RawAttributeData::Float(v) => {
+                resize(v, from, tuple_size, |i| if i == 3 { 1.0 } else { 0.0 })
+            }
+            RawAttributeData::Int(v) => resize(v, from, tuple_size, |i| if i == 3 { 1 } else { 0 }),
+            RawAttributeData::Int64(v) => {
+                resize(v, from, tuple_size, |i| if i == 3 { 1 } else { 0 })
+            }
+            RawAttributeData::Float64(v) => {
+                resize(v, from, tuple_size, |i| if i == 3 { 1.0 } else { 0.0 })
+            }
+            RawAttributeData::String(v) => resize(v, from, tuple_size, |_| String::new()),
+            RawAttributeData::Index(v) => resize(v, from, tuple_size, |_| 0),
+            _ => return Err(Error::UnsupportedTupleResize(self.data.kind())),
+        }
+        self.tuple_size = tuple_size;
+        Ok(())
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The vertex indices of a single prim in the raw data, see [`RawAttributeData::PrimVertex`].
+/// Typed prims keep them in a `Vec<usize>`, see [`PrimTopology`].
+#[cfg(not(feature = "smallvec"))]
+pub type PrimVertices = Vec<usize>;
+
+/// The vertex indices of a single prim in the raw data, see [`RawAttributeData::PrimVertex`].
+/// Triangles and quads are stored inline, so they don't need an allocation each. Typed prims keep
+/// them in a `Vec<usize>`, see [`PrimTopology`].
+#[cfg(feature = "smallvec")]
+pub type PrimVertices = smallvec::SmallVec<[usize; 4]>;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum RawAttributeData {
-    Float(Vec<f32>),
-    FloatArray(Vec<Vec<f32>>),
+    Float(#[serde(with = "non_finite::floats")] Vec<f32>),
+    FloatArray(#[serde(with = "non_finite::float_arrays")] Vec<Vec<f32>>),
     Int(Vec<i32>),
     IntArray(Vec<Vec<i32>>),
     String(Vec<String>),
     StringArray(Vec<Vec<String>>),
     Index(Vec<usize>),
-    PrimVertex(Vec<Vec<usize>>),
+    PrimVertex(Vec<PrimVertices>),
+    /// 64-bit integers, for values that don't fit into `Int`. Never sent by the Houdini side.
+    Int64(Vec<i64>),
+    /// Double precision floats, for values that need more precision than `Float`. Never sent by
+    /// the Houdini side.
+    Float64(#[serde(with = "non_finite::doubles")] Vec<f64>),
+    /// Strings where every distinct value is stored once and referenced by index, like Houdini
+    /// stores them internally. Read like `String` data, see
+    /// [`RawAttributeData::into_indexed_strings`].
+    IndexedString {
+        values: Vec<String>,
+        indices: Vec<usize>,
+    },
 }
 
 impl RawAttributeData {
@@ -60,6 +613,9 @@ impl RawAttributeData {
             RawAttributeData::StringArray(v) => v.len(),
             RawAttributeData::Index(v) => v.len(),
             RawAttributeData::PrimVertex(v) => v.len(),
+            RawAttributeData::Int64(v) => v.len(),
+            RawAttributeData::Float64(v) => v.len(),
+            RawAttributeData::IndexedString { indices, .. } => indices.len(),
         }
     }
 
@@ -77,27 +633,70 @@ impl RawAttributeData {
             RawAttributeData::StringArray(_) => AttributeType::StringArray,
             RawAttributeData::Index(_) => AttributeType::Index,
             RawAttributeData::PrimVertex(_) => AttributeType::PrimVertex,
+            RawAttributeData::Int64(_) => AttributeType::Int64,
+            RawAttributeData::Float64(_) => AttributeType::Float64,
+            RawAttributeData::IndexedString { .. } => AttributeType::IndexedString,
         }
     }
 
-    /// Helper function
-    fn err<T>(&self, expected: AttributeType) -> Result<T> {
+    /// Helper function. The location is filled in by [`FromAttributeData::from_attr_data_raw`],
+    /// as the data itself doesn't know where it came from.
+    pub(crate) fn err<T>(&self, expected: AttributeType) -> Result<T> {
         Err(Error::InvalidAttributeType {
             expected,
             actual: self.kind(),
+            location: None,
         })
     }
 
+    /// With the `lenient` feature, also accepts `Int` and `Int64` data, with a warning.
     pub fn float(self) -> Result<Vec<f32>> {
         match self {
             RawAttributeData::Float(v) => Ok(v),
+            #[cfg(feature = "lenient")]
+            RawAttributeData::Int(v) => {
+                Ok(lenient::ints_as_floats(AttributeType::Int, v, |v| v as f32))
+            }
+            #[cfg(feature = "lenient")]
+            RawAttributeData::Int64(v) => {
+                Ok(lenient::ints_as_floats(AttributeType::Int64, v, |v| {
+                    v as f32
+                }))
+            }
             other => other.err(AttributeType::Float),
         }
     }
 
+    /// Also accepts `Float` data, which is widened. With the `lenient` feature, also accepts
+    /// `Int` and `Int64` data, with a warning.
+    pub fn float64(self) -> Result<Vec<f64>> {
+        match self {
+            RawAttributeData::Float64(v) => Ok(v),
+            RawAttributeData::Float(v) => Ok(v.into_iter().map(f64::from).collect()),
+            #[cfg(feature = "lenient")]
+            RawAttributeData::Int(v) => {
+                Ok(lenient::ints_as_floats(AttributeType::Int, v, f64::from))
+            }
+            #[cfg(feature = "lenient")]
+            RawAttributeData::Int64(v) => {
+                Ok(lenient::ints_as_floats(AttributeType::Int64, v, |v| {
+                    v as f64
+                }))
+            }
+            other => other.err(AttributeType::Float64),
+        }
+    }
+
+    /// With the `lenient` feature, also accepts `IntArray` data, with a warning.
     pub fn float_array(self) -> Result<Vec<Vec<f32>>> {
         match self {
             RawAttributeData::FloatArray(v) => Ok(v),
+            #[cfg(feature = "lenient")]
+            RawAttributeData::IntArray(v) => {
+                Ok(lenient::ints_as_floats(AttributeType::IntArray, v, |v| {
+                    v.into_iter().map(|v| v as f32).collect()
+                }))
+            }
             other => other.err(AttributeType::FloatArray),
         }
     }
@@ -109,6 +708,15 @@ impl RawAttributeData {
         }
     }
 
+    /// Also accepts `Int` data, which is widened.
+    pub fn int64(self) -> Result<Vec<i64>> {
+        match self {
+            RawAttributeData::Int64(v) => Ok(v),
+            RawAttributeData::Int(v) => Ok(v.into_iter().map(i64::from).collect()),
+            other => other.err(AttributeType::Int64),
+        }
+    }
+
     pub fn int_array(self) -> Result<Vec<Vec<i32>>> {
         match self {
             RawAttributeData::IntArray(v) => Ok(v),
@@ -116,9 +724,19 @@ impl RawAttributeData {
         }
     }
 
+    /// Also accepts `IndexedString` data, which is expanded.
     pub fn string(self) -> Result<Vec<String>> {
         match self {
             RawAttributeData::String(v) => Ok(v),
+            RawAttributeData::IndexedString { values, indices } => indices
+                .into_iter()
+                .map(|index| {
+                    values.get(index).cloned().ok_or(Error::InvalidStringIndex {
+                        index,
+                        count: values.len(),
+                    })
+                })
+                .collect(),
             other => other.err(AttributeType::String),
         }
     }
@@ -137,14 +755,49 @@ impl RawAttributeData {
         }
     }
 
-    pub fn prim_vertex(self) -> Result<Vec<Vec<usize>>> {
+    pub fn prim_vertex(self) -> Result<Vec<PrimVertices>> {
         match self {
             RawAttributeData::PrimVertex(v) => Ok(v),
             other => other.err(AttributeType::PrimVertex),
         }
     }
 
-    pub fn prim_vertex_iter_mut(&mut self) -> Result<impl Iterator<Item = &mut Vec<usize>>> {
+    /// Rounds float data in place, other data is left as is.
+    pub fn round_floats(&mut self, precision: FloatPrecision) {
+        match self {
+            RawAttributeData::Float(v) => v
+                .iter_mut()
+                .for_each(|v| *v = precision.round(f64::from(*v)) as f32),
+            RawAttributeData::Float64(v) => v.iter_mut().for_each(|v| *v = precision.round(*v)),
+            RawAttributeData::FloatArray(v) => v
+                .iter_mut()
+                .flatten()
+                .for_each(|v| *v = precision.round(f64::from(*v)) as f32),
+            _ => {}
+        }
+    }
+
+    /// Stores every distinct value of `String` data only once, as `IndexedString` data. Indices
+    /// follow the order of first occurrence. Other data is returned unchanged.
+    pub fn into_indexed_strings(self) -> RawAttributeData {
+        let RawAttributeData::String(strings) = self else {
+            return self;
+        };
+        let mut lookup = HashMap::new();
+        let mut values = Vec::new();
+        let indices = strings
+            .into_iter()
+            .map(|string| {
+                *lookup.entry(string).or_insert_with_key(|string| {
+                    values.push(string.clone());
+                    values.len() - 1
+                })
+            })
+            .collect();
+        RawAttributeData::IndexedString { values, indices }
+    }
+
+    pub fn prim_vertex_iter_mut(&mut self) -> Result<impl Iterator<Item = &mut PrimVertices>> {
         match self {
             RawAttributeData::PrimVertex(v) => Ok(v.iter_mut()),
             other => other.err(AttributeType::PrimVertex),
@@ -152,7 +805,7 @@ impl RawAttributeData {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum AttributeType {
     Float,
     FloatArray,
@@ -162,6 +815,9 @@ pub enum AttributeType {
     StringArray,
     Index,
     PrimVertex,
+    Int64,
+    Float64,
+    IndexedString,
 }
 
 impl Display for AttributeType {
@@ -175,8 +831,91 @@ impl Display for AttributeType {
             AttributeType::StringArray => write!(f, "string_array"),
             AttributeType::Index => write!(f, "index"),
             AttributeType::PrimVertex => write!(f, "prim_vertex"),
+            AttributeType::Int64 => write!(f, "int64"),
+            AttributeType::Float64 => write!(f, "float64"),
+            AttributeType::IndexedString => write!(f, "indexed_string"),
+        }
+    }
+}
+
+/// The kind of the values of an [`AttributeType`], regardless of their precision and whether they
+/// are stored in arrays.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ElementKind {
+    Float,
+    Int,
+    String,
+    Index,
+}
+
+impl AttributeType {
+    pub fn element_kind(&self) -> ElementKind {
+        match self {
+            AttributeType::Float | AttributeType::FloatArray | AttributeType::Float64 => {
+                ElementKind::Float
+            }
+            AttributeType::Int | AttributeType::IntArray | AttributeType::Int64 => ElementKind::Int,
+            AttributeType::String | AttributeType::StringArray | AttributeType::IndexedString => {
+                ElementKind::String
+            }
+            AttributeType::Index | AttributeType::PrimVertex => ElementKind::Index,
+        }
+    }
+
+    /// Whether every element is a list of values, which Houdini calls array attributes. Includes
+    /// the prim vertex lists.
+    pub fn is_array(&self) -> bool {
+        matches!(
+            self,
+            AttributeType::FloatArray
+                | AttributeType::IntArray
+                | AttributeType::StringArray
+                | AttributeType::PrimVertex
+        )
+    }
+
+    /// The size of a single value in bytes, as in the binary transport. `None` for strings.
+    pub fn value_size(&self) -> Option<usize> {
+        match self {
+            AttributeType::Float
+            | AttributeType::FloatArray
+            | AttributeType::Int
+            | AttributeType::IntArray => Some(4),
+            AttributeType::Float64
+            | AttributeType::Int64
+            | AttributeType::Index
+            | AttributeType::PrimVertex => Some(8),
+            AttributeType::String | AttributeType::StringArray | AttributeType::IndexedString => {
+                None
+            }
         }
     }
+
+    /// The field type that reads an attribute of this type and tuple size without conversion, as
+    /// a hint for code generation. `None` if there is no such type.
+    pub fn expected_rust_type(&self, tuple_size: usize) -> Option<&'static str> {
+        Some(match (self, tuple_size) {
+            (AttributeType::Float, 1) => "f32",
+            (AttributeType::Float, 2) => "glam::Vec2",
+            (AttributeType::Float, 3) => "glam::Vec3",
+            (AttributeType::Float, 4) => "glam::Vec4",
+            (AttributeType::Float, 9) => "glam::Mat3",
+            (AttributeType::Float, 16) => "glam::Mat4",
+            (AttributeType::Float64, 1) => "f64",
+            (AttributeType::Float64, 16) => "glam::DMat4",
+            (AttributeType::Int, 1) => "i32",
+            (AttributeType::Int, 3) => "glam::IVec3",
+            (AttributeType::Int64, 1) => "i64",
+            (AttributeType::Int64, 3) => "glam::I64Vec3",
+            (AttributeType::String | AttributeType::IndexedString, 1) => "String",
+            (AttributeType::Index, 1) => "usize",
+            (AttributeType::FloatArray, 1) => "Vec<f32>",
+            (AttributeType::IntArray, 1) => "Vec<i32>",
+            (AttributeType::StringArray, 1) => "Vec<String>",
+            (AttributeType::PrimVertex, 1) => "Vec<usize>",
+            _ => return None,
+        })
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -185,16 +924,26 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
+    /// For binary input, which has no lines, `line` is 0 and `column` is the byte offset.
+    #[error(
+        "Input ended unexpectedly at {}; did the upstream process die?",
+        input_position(*line, *column)
+    )]
+    TruncatedInput { line: usize, column: usize },
     #[error("No geometry found")]
     NoGeometry,
     #[error("No detail attribute found")]
     NoDetail,
     #[error("Invalid attribute length (expected: {expected}, actual: {actual})")]
     InvalidAttributeLength { expected: usize, actual: usize },
-    #[error("Invalid attribute type (expected: {expected}, actual: {actual})")]
+    #[error(
+        "Invalid attribute type (expected: {expected}, actual: {actual}){}",
+        .location.map(|l| format!(" for {l}")).unwrap_or_default()
+    )]
     InvalidAttributeType {
         expected: AttributeType,
         actual: AttributeType,
+        location: Option<AttrLocation>,
     },
     #[error("Missing geometry at input: {0} ")]
     GeometryMissing(usize),
@@ -206,32 +955,288 @@ pub enum Error {
         entity: EntityKind,
         attr: &'static str,
     },
+    #[error(
+        "Input {input_index} {entity} attribute `{attr}` has {actual} elements instead of {expected}"
+    )]
+    InconsistentInAttr {
+        input_index: usize,
+        entity: EntityKind,
+        attr: &'static str,
+        expected: usize,
+        actual: usize,
+    },
     #[error("Output primitives missing `vertices` pseudo-attribute")]
     MissingOutPrimVertices,
     #[error("Output vertices missing `ptnum` pseudo-attribute")]
     MissingOutVertexPtnums,
+    #[error(
+        "Output prims have vertices, but the geometry has no vertices. Add vertices with a `ptnum` \
+        field, or set `OutputOptions::synthesize_ptnum` to use the prim vertices as point indices"
+    )]
+    MissingOutVertices,
+    #[error(
+        "Cannot synthesize `ptnum`: {vertices} vertices don't correspond 1:1 to {points} points"
+    )]
+    PtnumSynthesisMismatch { points: usize, vertices: usize },
     #[error("Output `ptnum` pseudo-attribute has wrong type.")]
     InvalidOutVertexPtnum,
+    #[error("Output {entity} attribute `{attr}` has {actual} elements instead of {expected}")]
+    InconsistentOutAttr {
+        entity: EntityKind,
+        attr: String,
+        expected: usize,
+        actual: usize,
+    },
     #[error("Output prim using non-existing vertex: {0}")]
     InvalidOutPrimVertex(usize),
+    #[error("Input prim {prim_index} using non-existing vertex: {vertex}")]
+    InvalidInPrimVertex { prim_index: usize, vertex: usize },
+    /// The derives reject the pre-defined names at compile time where they can, e.g. a prim field
+    /// named `points` or a field reading the element numbers, see [`ELEMNUM_ATTR`].
     #[error("Attribute is using a pre-defined name: {0}")]
-    AttrNameCollision(&'static str),
+    AttrNameCollision(Cow<'static, str>),
+    #[error("Invalid binary data: {0}")]
+    InvalidBinary(String),
+    #[error("Prim {prim_index} has {actual} vertices, expected {expected}")]
+    InvalidPrimSize {
+        prim_index: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("Cannot write {kind} attribute {attr} to a .geo file")]
+    UnsupportedGeoAttribute { attr: String, kind: AttributeType },
+    #[error("Cannot convert {from} attributes to {to}")]
+    UnsupportedConversion {
+        from: AttributeType,
+        to: AttributeType,
+    },
+    #[error("Invalid value for string-encoded attribute {attr}: {value:?}")]
+    InvalidAttributeString { attr: &'static str, value: String },
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("Value {value} of attribute {attr} is out of range for {target}")]
+    IntOutOfRange {
+        attr: &'static str,
+        value: i64,
+        target: &'static str,
+    },
+    #[error("Value {value} of attribute {attr} is outside of {range}")]
+    ValueOutOfRange {
+        attr: &'static str,
+        value: String,
+        range: String,
+    },
+    #[error("Point reference {index} is out of range for {point_count} points")]
+    InvalidPointRef { index: usize, point_count: usize },
+    #[error("Unknown {entity} attribute: {attr}")]
+    UnknownAttr { entity: EntityKind, attr: String },
+    #[error("No {entity} with element number {number}")]
+    UnknownElementNumber { entity: EntityKind, number: usize },
+    #[error("String index {index} is out of range for {count} values")]
+    InvalidStringIndex { index: usize, count: usize },
+    #[error("Array of attribute {attr} has {len} values, which is not a multiple of {stride}")]
+    InvalidArrayStride {
+        attr: &'static str,
+        len: usize,
+        stride: usize,
+    },
+    #[error("Invalid JSON in attribute {attr}: {message}")]
+    InvalidAttributeJson { attr: &'static str, message: String },
+    #[error("Topology changed: {0}")]
+    TopologyChanged(TopologyChange),
+    #[error("Cannot change the tuple size of {0} attributes")]
+    UnsupportedTupleResize(AttributeType),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 impl Error {
+    /// Reports the error to Houdini, see [`report`].
     pub fn print_json(&self) {
-        eprintln!("{}", self.to_string());
+        report::error(self);
     }
 }
 
+/// The environment variable with a file path that [`load_raw_from_stdin`] copies its input to, for
+/// replaying failed cooks with [`replay_from_path`].
+pub const CAPTURE_ENV_VAR: &str = "HOUDINI_NODE_CAPTURE";
+
+/// Reads the input sent by Houdini. Also writes it to the file in [`CAPTURE_ENV_VAR`], if set.
+///
+/// Stops at the end of the input instead of waiting for stdin to be closed, which the bridge only
+/// does to cancel the node, see [`cancel`].
 pub fn load_raw_from_stdin() -> Result<Vec<RawGeometry>> {
-    serde_json::from_reader(std::io::stdin()).map_err(Into::into)
+    let stdin = std::io::stdin().lock();
+    match std::env::var_os(CAPTURE_ENV_VAR) {
+        Some(path) => load_raw_capturing(stdin, path),
+        None => load_raw_value(stdin),
+    }
+}
+
+/// Like [`load_raw_from_reader`], but also writes the input, as far as it was read, unchanged to
+/// `path`. The file is written even if parsing fails, so that such input is captured as well.
+pub fn load_raw_capturing(
+    reader: impl std::io::Read,
+    path: impl AsRef<std::path::Path>,
+) -> Result<Vec<RawGeometry>> {
+    let mut tee = Tee {
+        reader: std::io::BufReader::new(reader),
+        bytes: Vec::new(),
+    };
+    let geometries = load_raw_value(&mut tee);
+    std::fs::write(path, &tee.bytes)?;
+    geometries
+}
+
+/// Reads the geometries up to the end of the JSON value, ignoring anything after it. Uses
+/// `simd-json` with the `simd` feature.
+fn load_raw_value(reader: impl std::io::BufRead) -> Result<Vec<RawGeometry>> {
+    #[cfg(feature = "simd")]
+    return simd::load_raw_value(reader);
+
+    #[cfg(not(feature = "simd"))]
+    {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        Vec::<RawGeometry>::deserialize(&mut deserializer).map_err(input_error)
+    }
+}
+
+/// Keeps a copy of everything consumed, for [`load_raw_capturing`]. Bytes that were buffered but
+/// not consumed are not part of the input, so they aren't copied.
+struct Tee<R> {
+    reader: R,
+    bytes: Vec<u8>,
+}
+
+impl<R: std::io::BufRead> std::io::Read for Tee<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = std::io::BufRead::fill_buf(self)?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        std::io::BufRead::consume(self, len);
+        Ok(len)
+    }
+}
+
+impl<R: std::io::BufRead> std::io::BufRead for Tee<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        // The bytes are still buffered, so this doesn't read.
+        if amount > 0
+            && let Ok(buf) = self.reader.fill_buf()
+        {
+            self.bytes.extend_from_slice(&buf[..amount]);
+        }
+        self.reader.consume(amount);
+    }
+}
+
+/// Turns parse errors at the end of the input into [`Error::TruncatedInput`], since the input of a
+/// node only ends early when the process writing it stopped.
+pub(crate) fn input_error(err: serde_json::Error) -> Error {
+    match err.is_eof() {
+        true => Error::TruncatedInput {
+            line: err.line(),
+            column: err.column(),
+        },
+        false => err.into(),
+    }
+}
+
+/// The position of [`Error::TruncatedInput`] for its message.
+fn input_position(line: usize, column: usize) -> String {
+    match line {
+        0 => format!("byte {column}"),
+        _ => format!("line {line}, column {column}"),
+    }
+}
+
+/// Loads input captured by [`load_raw_capturing`].
+pub fn replay_from_path(path: impl AsRef<std::path::Path>) -> Result<Vec<RawGeometry>> {
+    let file = std::fs::File::open(path)?;
+    load_raw_from_reader(std::io::BufReader::new(file))
+}
+
+/// Reads successive frames from `reader`, each a list of the geometries of all inputs. Meant for
+/// long-running processes, like solvers, that receive a new frame on the same pipe every step.
+/// Frames may be separated by whitespace.
+pub fn load_raw_stream(
+    reader: impl std::io::Read,
+) -> impl Iterator<Item = Result<Vec<RawGeometry>>> {
+    serde_json::Deserializer::from_reader(reader)
+        .into_iter::<Vec<RawGeometry>>()
+        .map(|frame| frame.map_err(input_error))
+}
+
+/// Reads newline-delimited JSON (NDJSON) with one geometry object per line, see
+/// [`generate_ndjson_to_writer`].
+pub fn load_raw_ndjson(reader: impl std::io::Read) -> Result<Vec<RawGeometry>> {
+    serde_json::Deserializer::from_reader(reader)
+        .into_iter::<RawGeometry>()
+        .map(|geo| geo.map_err(input_error))
+        .collect()
+}
+
+/// Like [`load_raw_ndjson`], but the geometries are preceded by their count on the first line.
+/// Reading stops after the last geometry instead of at the end of `reader`, since the bridge keeps
+/// stdin open while the node runs. This is how `#[houdini_node_main(format = "ndjson")]` reads its
+/// input.
+pub fn load_raw_ndjson_counted(reader: impl std::io::Read) -> Result<Vec<RawGeometry>> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let count = usize::deserialize(&mut deserializer).map_err(input_error)?;
+    (0..count)
+        .map(|_| RawGeometry::deserialize(&mut deserializer).map_err(input_error))
+        .collect()
+}
+
+/// Writes the output as newline-delimited JSON (NDJSON): every geometry as a single compact line.
+pub fn generate_ndjson_to_writer<G: IntoNodeOutput>(
+    mut writer: impl std::io::Write,
+    output: G,
+) -> Result<()> {
+    let geometries = match output.into_node_output()? {
+        NodeOutput::Single(geo) => vec![geo],
+        NodeOutput::Multiple(geos) => geos,
+    };
+    for geo in &geometries {
+        serde_json::to_writer(&mut writer, geo)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the output to stdout as compact JSON, or indented if [`PRETTY_ENV_VAR`] is set.
+pub fn generate_to_stdout<G: IntoNodeOutput>(output: G) -> Result<()> {
+    if std::env::var_os(PRETTY_ENV_VAR).is_some() {
+        return generate_to_stdout_pretty(output);
+    }
+    let output = output.into_node_output()?;
+    println!("{}", serde_json::to_string(&output)?);
+    Ok(())
 }
 
-pub fn generate_to_stdout<G: IntoRawGeometry>(geometry: G) -> Result<()> {
-    println!("{}", generate::<G>(geometry)?);
+/// The environment variable that makes [`generate_to_stdout`] write indented JSON, for reading the
+/// output while developing a node. Compact JSON is smaller and faster to parse.
+pub const PRETTY_ENV_VAR: &str = "HOUDINI_NODE_PRETTY";
+
+/// Like [`generate_to_stdout`], always writing indented JSON.
+pub fn generate_to_stdout_pretty<G: IntoNodeOutput>(output: G) -> Result<()> {
+    generate_pretty_to_writer(std::io::stdout().lock(), output)
+}
+
+/// Writes the output as indented JSON, followed by a newline.
+pub fn generate_pretty_to_writer<G: IntoNodeOutput>(
+    mut writer: impl std::io::Write,
+    output: G,
+) -> Result<()> {
+    let output = output.into_node_output()?;
+    serde_json::to_writer_pretty(&mut writer, &output)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
     Ok(())
 }
 
@@ -242,15 +1247,33 @@ pub fn load_from_raw<G: FromRawGeometry>(
     G::from_raw(raw_geometry, input_index)
 }
 
-#[cfg(test)]
-fn load<G: FromRawGeometry>(reader: impl std::io::Read) -> Result<G> {
-    let raw_geometry: Vec<RawGeometry> = serde_json::from_reader(reader)?;
-    G::from_raw(raw_geometry.into_iter().next().ok_or(Error::NoGeometry)?, 0)
+pub fn load_from_raw_with_options<G: FromRawGeometry>(
+    raw_geometry: RawGeometry,
+    input_index: usize,
+    options: LoadOptions,
+) -> Result<G> {
+    G::from_raw_with_options(raw_geometry, input_index, options)
+}
+
+/// Reads the geometries of all inputs from `reader`, e.g. `std::io::stdin()` in a custom `main`.
+///
+/// Like [`load_raw_from_stdin`], this stops at the end of the JSON value and doesn't wait for the
+/// reader to be closed, as the bridge keeps stdin open while the node runs, see [`cancel`]. The
+/// reader is buffered, so data after the value may be consumed as well.
+pub fn load_raw_from_reader(reader: impl std::io::Read) -> Result<Vec<RawGeometry>> {
+    load_raw_value(std::io::BufReader::new(reader))
 }
 
-fn generate<G: IntoRawGeometry>(geometry: G) -> Result<String> {
-    let raw_geometry = G::into_raw(geometry)?;
-    serde_json::to_string(&raw_geometry).map_err(Into::into)
+/// Parses the geometries of all inputs from `reader` and loads the one at `input_index`.
+pub fn load_from_reader<G: FromRawGeometry>(
+    reader: impl std::io::Read,
+    input_index: usize,
+) -> Result<G> {
+    let raw_geometry = load_raw_from_reader(reader)?
+        .into_iter()
+        .nth(input_index)
+        .ok_or(Error::GeometryMissing(input_index))?;
+    G::from_raw(raw_geometry, input_index)
 }
 
 /// The actual geometry for the script to use in AoS (Array-of-structs) form.
@@ -260,28 +1283,177 @@ pub struct Geometry<Pt, Vt = (), Pr = (), Dt = ()> {
     pub vertices: Vec<Vt>,
     pub prims: Vec<Pr>,
     pub detail: Dt,
+    /// Untyped side data that is not part of the typed entities.
+    pub extra: GeometryExtra,
 }
 
-pub trait FromRawGeometry: Sized {
-    fn from_raw(raw: RawGeometry, input_index: usize) -> Result<Self>;
+/// A geometry with only points.
+impl<Pt> From<Vec<Pt>> for Geometry<Pt> {
+    fn from(points: Vec<Pt>) -> Self {
+        Self {
+            points,
+            vertices: Vec::new(),
+            prims: Vec::new(),
+            detail: (),
+            extra: GeometryExtra::default(),
+        }
+    }
 }
 
-impl<Pt, Vt, Pr, Dt> FromRawGeometry for Geometry<Pt, Vt, Pr, Dt>
-where
-    Pt: InAttrs,
-    Vt: InAttrs,
-    Pr: InAttrs,
-    Dt: InAttrs,
-{
-    fn from_raw(raw: RawGeometry, input_index: usize) -> Result<Self> {
-        let mut details = Dt::from_attr(
-            raw.detail,
-            ErrContext {
-                input_index,
-                entity: EntityKind::Detail,
-            },
-        )?;
-        let detail = details.next();
+impl<Pt, Vt, Pr, Dt: Default> Geometry<Pt, Vt, Pr, Dt> {
+    /// An empty geometry with room for the given number of entities, e.g. the counts of the input
+    /// when the output has the same size.
+    pub fn with_capacity(points: usize, vertices: usize, prims: usize) -> Self {
+        Self {
+            points: Vec::with_capacity(points),
+            vertices: Vec::with_capacity(vertices),
+            prims: Vec::with_capacity(prims),
+            detail: Dt::default(),
+            extra: GeometryExtra::default(),
+        }
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Converts every point with `f`, keeping everything else. The new points keep the capacity
+    /// of the old ones.
+    pub fn map_points<Pt2>(self, f: impl FnMut(Pt) -> Pt2) -> Geometry<Pt2, Vt, Pr, Dt> {
+        let mut extra = self.extra;
+        extra.point_cache.clear();
+        Geometry {
+            points: map_with_capacity(self.points, f),
+            vertices: self.vertices,
+            prims: self.prims,
+            detail: self.detail,
+            extra,
+        }
+    }
+
+    /// Like [`Geometry::map_points`], for the vertices.
+    pub fn map_vertices<Vt2>(self, f: impl FnMut(Vt) -> Vt2) -> Geometry<Pt, Vt2, Pr, Dt> {
+        Geometry {
+            points: self.points,
+            vertices: map_with_capacity(self.vertices, f),
+            prims: self.prims,
+            detail: self.detail,
+            extra: self.extra,
+        }
+    }
+
+    /// Like [`Geometry::map_points`], for the prims.
+    pub fn map_prims<Pr2>(self, f: impl FnMut(Pr) -> Pr2) -> Geometry<Pt, Vt, Pr2, Dt> {
+        Geometry {
+            points: self.points,
+            vertices: self.vertices,
+            prims: map_with_capacity(self.prims, f),
+            detail: self.detail,
+            extra: self.extra,
+        }
+    }
+
+    /// Converts the detail with `f`, keeping the entities. E.g. for computing a summary at the end
+    /// of a node.
+    pub fn map_detail<Dt2>(self, f: impl FnOnce(Dt) -> Dt2) -> Geometry<Pt, Vt, Pr, Dt2> {
+        Geometry {
+            points: self.points,
+            vertices: self.vertices,
+            prims: self.prims,
+            detail: f(self.detail),
+            extra: self.extra,
+        }
+    }
+
+    /// The points, for modification. Unlike changing `points` directly, this clears the cached data
+    /// derived from them, see [`GeometryExtra::point_cache`].
+    pub fn points_mut(&mut self) -> &mut Vec<Pt> {
+        self.extra.point_cache.clear();
+        &mut self.points
+    }
+
+    /// Appends a loose point that isn't referenced by any vertex.
+    ///
+    /// Clears the point attributes added via [`Geometry::set_point_attr`], as they no longer have a
+    /// value for every point. Clears the cached [`Geometry::point_index`].
+    pub fn push_point(&mut self, point: Pt) {
+        self.extend_points(iter::once(point));
+    }
+
+    /// Like [`Geometry::push_point`], for multiple points.
+    pub fn extend_points(&mut self, points: impl IntoIterator<Item = Pt>) {
+        let len = self.points.len();
+        self.points_mut().extend(points);
+        if self.points.len() != len {
+            self.extra.point_attrs.clear();
+        }
+    }
+}
+
+/// `Vec::into_iter().map().collect()` only reuses the allocation if the layouts match, so the
+/// capacity is carried over explicitly.
+fn map_with_capacity<T, U>(values: Vec<T>, f: impl FnMut(T) -> U) -> Vec<U> {
+    let mut mapped = Vec::with_capacity(values.capacity());
+    mapped.extend(values.into_iter().map(f));
+    mapped
+}
+
+pub trait FromRawGeometry: Sized {
+    fn from_raw(raw: RawGeometry, input_index: usize) -> Result<Self>;
+
+    /// Like [`FromRawGeometry::from_raw`], but allows retaining additional untyped data. Ignores
+    /// the options by default.
+    fn from_raw_with_options(
+        raw: RawGeometry,
+        input_index: usize,
+        options: LoadOptions,
+    ) -> Result<Self> {
+        let _ = options;
+        Self::from_raw(raw, input_index)
+    }
+
+    /// The attributes that [`FromRawGeometry::from_raw`] needs. Defaults to all of them.
+    fn projection() -> Projection {
+        Projection::default()
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> FromRawGeometry for Geometry<Pt, Vt, Pr, Dt>
+where
+    Pt: InAttrs,
+    Vt: InAttrs,
+    Pr: InAttrs,
+    Dt: InAttrs,
+{
+    fn from_raw(raw: RawGeometry, input_index: usize) -> Result<Self> {
+        Self::from_raw_with_options(raw, input_index, LoadOptions::default())
+    }
+
+    fn from_raw_with_options(
+        mut raw: RawGeometry,
+        input_index: usize,
+        options: LoadOptions,
+    ) -> Result<Self> {
+        raw.resolve_element_numbers()?;
+        raw.check_prim_vertices()?;
+        let mut extra = GeometryExtra {
+            attr_info: options.attr_info.then(|| AttrInfo::from_raw(&raw)),
+            attr_meta: AttrMetaMap::from_raw(&raw),
+            topology: if options.topology {
+                Some(RawTopology::from_raw(&raw)?)
+            } else {
+                None
+            },
+            ..Default::default()
+        };
+        extra.point_attrs = extra::take_point_attrs::<Pt>(&mut raw.points, &options, input_index)?;
+
+        let mut details = Dt::from_attr(
+            raw.detail,
+            ErrContext {
+                input_index,
+                entity: EntityKind::Detail,
+            },
+        )?;
+        let detail = details.next();
 
         let detail = if let Some(detail) = detail {
             detail
@@ -292,15 +1464,27 @@ where
             v
         };
 
+        let points: Vec<Pt> = Pt::from_attr(
+            raw.points,
+            ErrContext {
+                input_index,
+                entity: EntityKind::Point,
+            },
+        )?
+        .collect();
+        if let Some(attr) = extra
+            .point_attrs
+            .values()
+            .find(|a| a.num_elements() != points.len())
+        {
+            return Err(Error::InvalidAttributeLength {
+                expected: points.len(),
+                actual: attr.num_elements(),
+            });
+        }
+
         Ok(Self {
-            points: Pt::from_attr(
-                raw.points,
-                ErrContext {
-                    input_index,
-                    entity: EntityKind::Point,
-                },
-            )?
-            .collect(),
+            points,
             vertices: Vt::from_attr(
                 raw.vertices,
                 ErrContext {
@@ -318,12 +1502,140 @@ where
             )?
             .collect(),
             detail,
+            extra,
         })
     }
+
+    fn projection() -> Projection {
+        Projection {
+            // Needed to resolve the topology, see `RawGeometry::resolve_element_numbers`.
+            points: Projection::of::<Pt>([ELEMNUM_ATTR]),
+            vertices: Projection::of::<Vt>([ELEMNUM_ATTR]),
+            prims: Projection::of::<Pr>([]),
+            detail: Projection::of::<Dt>([]),
+        }
+    }
+}
+
+/// Controls how a [`Geometry`] is converted for output. Everything is off by default.
+#[derive(Debug, Clone, Default)]
+pub struct OutputOptions {
+    /// If the vertices have no `ptnum` attribute, let vertex `i` refer to point `i` instead of
+    /// failing with [`Error::MissingOutVertexPtnums`]. This requires as many vertices as there are
+    /// points, or no vertices at all, in which case the prims refer to the points directly.
+    /// Always on for geometry without a vertex type (`Vt = ()`), whose prims can only refer to
+    /// points.
+    pub synthesize_ptnum: bool,
+    /// Rounds all float attributes, so they serialize with fewer digits. This is lossy.
+    pub float_precision: Option<FloatPrecision>,
+    /// Renames output attributes after they were generated from the typed entities.
+    pub renames: OutputRenames,
+}
+
+impl OutputOptions {
+    /// The options for a geometry with vertices of type `Vt`. A vertex type without attributes
+    /// can't have a `ptnum`, so the prim vertices are taken as point indices.
+    fn for_vertices<Vt: OutAttrs>(mut self) -> Self {
+        self.synthesize_ptnum |= out_attrs::<Vt>().is_empty();
+        self
+    }
+}
+
+/// Maps attribute names to new names, per entity. Attributes that don't exist are ignored, the
+/// topology pseudo-attributes (`ptnum` and `vertices`) must not be renamed. Renaming an attribute
+/// to the name of another one is an error, unless that one is renamed as well.
+#[derive(Debug, Clone, Default)]
+pub struct OutputRenames {
+    pub points: HashMap<&'static str, String>,
+    pub vertices: HashMap<&'static str, String>,
+    pub prims: HashMap<&'static str, String>,
+    pub detail: HashMap<&'static str, String>,
+}
+
+impl OutputRenames {
+    fn apply(
+        mut attrs: BTreeMap<&'static str, RawAttribute>,
+        renames: &HashMap<&'static str, String>,
+    ) -> Result<BTreeMap<Cow<'static, str>, RawAttribute>> {
+        let names: Vec<_> = attrs.keys().copied().collect();
+        Ok(Self::rename(names, renames)?
+            .into_iter()
+            .filter_map(|(new_name, name)| Some((new_name, attrs.remove(name)?)))
+            .collect())
+    }
+
+    /// Like [`OutputRenames::apply`], for the owned names of a [`RawGeometry`].
+    fn apply_owned(
+        mut attrs: HashMap<String, RawAttribute>,
+        renames: &HashMap<&'static str, String>,
+    ) -> Result<BTreeMap<Cow<'static, str>, RawAttribute>> {
+        let mut names: Vec<_> = attrs.keys().cloned().collect();
+        names.sort_unstable();
+        Ok(Self::rename(names, renames)?
+            .into_iter()
+            .filter_map(|(new_name, name)| Some((new_name, attrs.remove(&name)?)))
+            .collect())
+    }
+
+    /// Maps the output names, in output order, to the names before renaming. `names` must be
+    /// sorted, so that collisions are reported for the same attribute as by [`OutputRenames::apply`].
+    pub(crate) fn rename<N: AsRef<str> + Clone + Into<Cow<'static, str>>>(
+        names: impl IntoIterator<Item = N>,
+        renames: &HashMap<&'static str, String>,
+    ) -> Result<BTreeMap<Cow<'static, str>, N>> {
+        let mut renamed = BTreeMap::new();
+        for name in names {
+            let new_name = match renames.get(name.as_ref()) {
+                Some(new_name) => Cow::Owned(new_name.clone()),
+                None => name.clone().into(),
+            };
+            if renamed.contains_key(&new_name) {
+                return Err(Error::AttrNameCollision(new_name));
+            }
+            renamed.insert(new_name, name);
+        }
+        Ok(renamed)
+    }
+}
+
+/// How far floats are rounded, see [`OutputOptions::float_precision`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FloatPrecision {
+    /// Keeps this many significant digits, for values of any magnitude.
+    SignificantDigits(u32),
+    /// Keeps this many digits after the decimal point, for values with a known range.
+    Decimals(u32),
+}
+
+impl FloatPrecision {
+    fn round(self, v: f64) -> f64 {
+        if !v.is_finite() || v == 0.0 {
+            return v;
+        }
+        let decimals = match self {
+            FloatPrecision::SignificantDigits(digits) => {
+                digits as i32 - 1 - v.abs().log10().floor() as i32
+            }
+            FloatPrecision::Decimals(decimals) => decimals as i32,
+        };
+        // Dividing by an exact power of ten rounds better than multiplying with its inverse.
+        if decimals >= 0 {
+            let factor = 10f64.powi(decimals);
+            (v * factor).round() / factor
+        } else {
+            let factor = 10f64.powi(-decimals);
+            (v / factor).round() * factor
+        }
+    }
 }
 
 pub trait IntoRawGeometry: Sized {
-    fn into_raw(self) -> Result<RawGeometryOutput>;
+    fn into_raw(self) -> Result<RawGeometryOutput> {
+        self.into_raw_with(OutputOptions::default())
+    }
+
+    /// Like [`IntoRawGeometry::into_raw`], with options.
+    fn into_raw_with(self, options: OutputOptions) -> Result<RawGeometryOutput>;
 }
 
 impl<Pt, Vt, Pr, Dt> IntoRawGeometry for Geometry<Pt, Vt, Pr, Dt>
@@ -333,43 +1645,372 @@ where
     Pr: OutAttrs,
     Dt: OutAttrs,
 {
-    fn into_raw(self) -> Result<RawGeometryOutput> {
-        let vertices = Vt::into_attr(self.vertices);
-        let mut prims = Pr::into_attr(self.prims);
-
-        // For houdini, we need to convert the vertex indices to point indices.
-        if !prims.is_empty() {
-            let mut primverts = prims
-                .remove("vertices")
-                .ok_or_else(|| Error::MissingOutPrimVertices)?;
-            let vert2pt = vertices
-                .get("ptnum")
-                .ok_or_else(|| Error::MissingOutVertexPtnums)?;
-            let RawAttributeData::Index(vert2pt) = &vert2pt.data else {
-                return Err(Error::InvalidOutVertexPtnum);
-            };
+    fn into_raw_with(self, options: OutputOptions) -> Result<RawGeometryOutput> {
+        let options = options.for_vertices::<Vt>();
+        let counts = (self.points.len(), self.vertices.len());
+        let mut points = Pt::into_attr(self.points)?;
+        points.extend(self.extra.point_attrs);
+        let mut output = assemble_output(
+            points,
+            Vt::into_attr(self.vertices)?,
+            Pr::into_attr(self.prims)?,
+            Dt::into_attr(vec![self.detail])?,
+            counts,
+            &options,
+        )?;
+        self.extra.attr_meta.apply(&mut output);
+        Ok(output)
+    }
+}
+
+/// Passes an input through unchanged, for nodes that work on the raw attributes. Like for typed
+/// geometry, the prim `vertices` are written as point indices, and the [`OutputOptions`] apply to
+/// all attributes.
+impl IntoRawGeometry for RawGeometry {
+    fn into_raw_with(mut self, options: OutputOptions) -> Result<RawGeometryOutput> {
+        self.resolve_element_numbers()?;
+
+        if let Some(precision) = options.float_precision {
+            for attrs in [
+                &mut self.points,
+                &mut self.vertices,
+                &mut self.prims,
+                &mut self.detail,
+            ] {
+                for attr in attrs.values_mut() {
+                    attr.data.round_floats(precision);
+                }
+            }
+        }
 
+        if let Some(mut primverts) = self.prims.remove("vertices") {
+            if self.prims.contains_key("points") {
+                return Err(Error::AttrNameCollision("points".into()));
+            }
+            let count = |attrs: &HashMap<String, RawAttribute>| {
+                attrs.values().next().map_or(0, RawAttribute::num_elements)
+            };
+            let counts = (count(&self.points), count(&self.vertices));
+            let vert2pt =
+                VertexToPoint::new(self.vertices.get("ptnum"), counts, options.synthesize_ptnum)?;
             for primvert in primverts.data.prim_vertex_iter_mut()? {
                 for v in primvert {
-                    *v = *vert2pt
-                        .get(*v)
-                        .ok_or_else(|| Error::InvalidOutPrimVertex(*v))?;
+                    *v = vert2pt.get(*v)?;
+                }
+            }
+            self.prims.insert("points".to_string(), primverts);
+        }
+
+        let renames = &options.renames;
+        Ok(RawGeometryOutput {
+            points: OutputRenames::apply_owned(self.points, &renames.points)?,
+            vertices: OutputRenames::apply_owned(self.vertices, &renames.vertices)?,
+            prims: OutputRenames::apply_owned(self.prims, &renames.prims)?,
+            detail: OutputRenames::apply_owned(self.detail, &renames.detail)?,
+        })
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt>
+where
+    Pt: OutAttrs + Clone,
+    Vt: OutAttrs + Clone,
+    Pr: OutAttrs + Clone,
+    Dt: OutAttrs + Clone,
+{
+    /// Like [`IntoRawGeometry::into_raw`], but keeps the geometry. With derived entities, only the
+    /// field values are copied, see [`OutAttrs::into_attr_ref`].
+    pub fn to_raw(&self) -> Result<RawGeometryOutput> {
+        self.to_raw_with(OutputOptions::default())
+    }
+
+    /// Runs the same checks as [`IntoRawGeometry::into_raw`], without building the output. Only
+    /// the topology pseudo-attributes are collected.
+    pub fn check_serializable(&self) -> Result<()> {
+        self.check_serializable_with(OutputOptions::default())
+    }
+
+    /// Like [`Geometry::check_serializable`], with options.
+    pub fn check_serializable_with(&self, options: OutputOptions) -> Result<()> {
+        let options = options.for_vertices::<Vt>();
+        let point_count = Some(self.points.len());
+        check_out_lengths(EntityKind::Point, &self.extra.point_attrs, point_count)?;
+
+        let prim_attrs = out_attrs::<Pr>();
+        if !self.prims.is_empty() && !prim_attrs.is_empty() {
+            if prim_attrs.iter().any(|(name, _)| *name == "points") {
+                return Err(Error::AttrNameCollision("points".into()));
+            }
+            let primverts = Pr::single_attr_ref(&self.prims, "vertices")?
+                .ok_or(Error::MissingOutPrimVertices)?;
+            let ptnum = self.out_ptnum()?;
+            let counts = (self.points.len(), self.vertices.len());
+            let vert2pt = VertexToPoint::new(ptnum.as_ref(), counts, options.synthesize_ptnum)?;
+
+            for primvert in primverts.data.prim_vertex()? {
+                for v in primvert {
+                    vert2pt.get(v)?;
                 }
             }
+        }
+
+        let [points, vertices, prims, detail] = self.out_attr_names();
+        let renames = &options.renames;
+        OutputRenames::rename(points, &renames.points)?;
+        OutputRenames::rename(vertices, &renames.vertices)?;
+        OutputRenames::rename(prims, &renames.prims)?;
+        OutputRenames::rename(detail, &renames.detail)?;
+        Ok(())
+    }
+
+    /// The names of the output attributes of the points, vertices, prims and detail before
+    /// renaming, sorted. As in `assemble_output`, entities without elements have none, and the
+    /// prim `vertices` are written as `points`.
+    pub(crate) fn out_attr_names(&self) -> [Vec<&'static str>; 4] {
+        fn names(
+            attrs: &[(&'static str, usize)],
+            extra: impl IntoIterator<Item = &'static str>,
+        ) -> Vec<&'static str> {
+            let mut names: Vec<_> = attrs.iter().map(|(name, _)| *name).chain(extra).collect();
+            names.sort_unstable();
+            names.dedup();
+            names
+        }
+
+        let points = match self.points.len() {
+            0 => Vec::new(),
+            _ => names(&out_attrs::<Pt>(), self.extra.point_attrs.keys().copied()),
+        };
+        let vertices = match self.vertices.len() {
+            0 => Vec::new(),
+            _ => names(&out_attrs::<Vt>(), []),
+        };
+        let prims = match &*out_attrs::<Pr>() {
+            _ if self.prims.is_empty() => Vec::new(),
+            [] => Vec::new(),
+            attrs => names(attrs, ["points"])
+                .into_iter()
+                .filter(|name| *name != "vertices")
+                .collect(),
+        };
+        [points, vertices, prims, names(&out_attrs::<Dt>(), [])]
+    }
+
+    /// The vertex `ptnum` pseudo-attribute. As in `assemble_output`, it is dropped without
+    /// vertices.
+    pub(crate) fn out_ptnum(&self) -> Result<Option<RawAttribute>> {
+        let ptnum = Vt::single_attr_ref(&self.vertices, "ptnum")?;
+        Ok(ptnum.filter(|_| !self.vertices.is_empty()))
+    }
 
-            if prims.contains_key("points") {
-                return Err(Error::AttrNameCollision("points"));
+    /// Like [`Geometry::to_raw`], with options.
+    pub fn to_raw_with(&self, options: OutputOptions) -> Result<RawGeometryOutput> {
+        let options = options.for_vertices::<Vt>();
+        let mut points = Pt::into_attr_ref(&self.points)?;
+        points.extend(self.extra.point_attrs.clone());
+        let mut output = assemble_output(
+            points,
+            Vt::into_attr_ref(&self.vertices)?,
+            Pr::into_attr_ref(&self.prims)?,
+            Dt::into_attr_ref(std::slice::from_ref(&self.detail))?,
+            (self.points.len(), self.vertices.len()),
+            &options,
+        )?;
+        self.extra.attr_meta.apply(&mut output);
+        Ok(output)
+    }
+}
+
+/// `counts` are the number of points and vertices, which can't be derived from the attributes if
+/// the entities don't have any.
+fn assemble_output(
+    mut points: BTreeMap<&'static str, RawAttribute>,
+    mut vertices: BTreeMap<&'static str, RawAttribute>,
+    mut prims: BTreeMap<&'static str, RawAttribute>,
+    mut detail: BTreeMap<&'static str, RawAttribute>,
+    counts: (usize, usize),
+    options: &OutputOptions,
+) -> Result<RawGeometryOutput> {
+    // The shortest representation of a rounded float that round-trips is also short, so it's
+    // enough to round the values themselves.
+    if let Some(precision) = options.float_precision {
+        for attrs in [&mut points, &mut vertices, &mut prims, &mut detail] {
+            for attr in attrs.values_mut() {
+                attr.data.round_floats(precision);
             }
+        }
+    }
 
-            prims.insert("points", primverts);
+    // Entities without elements are written as empty sections.
+    let sections = [
+        (EntityKind::Point, &mut points, Some(counts.0)),
+        (EntityKind::Vertex, &mut vertices, Some(counts.1)),
+        (EntityKind::Prim, &mut prims, None),
+        (EntityKind::Detail, &mut detail, None),
+    ];
+    for (entity, attrs, count) in sections {
+        if check_out_lengths(entity, attrs, count)? == 0 {
+            attrs.clear();
         }
+    }
 
-        Ok(RawGeometryOutput {
-            points: Pt::into_attr(self.points),
-            vertices,
-            prims,
-            detail: Dt::into_attr(vec![self.detail]),
-        })
+    // For houdini, we need to convert the vertex indices to point indices.
+    if !prims.is_empty() {
+        if prims.contains_key("points") {
+            return Err(Error::AttrNameCollision("points".into()));
+        }
+
+        let mut primverts = prims
+            .remove("vertices")
+            .ok_or(Error::MissingOutPrimVertices)?;
+        let vert2pt = VertexToPoint::new(vertices.get("ptnum"), counts, options.synthesize_ptnum)?;
+
+        for primvert in primverts.data.prim_vertex_iter_mut()? {
+            for v in primvert {
+                *v = vert2pt.get(*v)?;
+            }
+        }
+
+        prims.insert("points", primverts);
+    }
+
+    let renames = &options.renames;
+    Ok(RawGeometryOutput {
+        points: OutputRenames::apply(points, &renames.points)?,
+        vertices: OutputRenames::apply(vertices, &renames.vertices)?,
+        prims: OutputRenames::apply(prims, &renames.prims)?,
+        detail: OutputRenames::apply(detail, &renames.detail)?,
+    })
+}
+
+/// Checks that every attribute has `count` elements, or as many as the first one if the count
+/// isn't known. Returns the count, 0 without attributes.
+fn check_out_lengths(
+    entity: EntityKind,
+    attrs: &BTreeMap<&'static str, RawAttribute>,
+    count: Option<usize>,
+) -> Result<usize> {
+    let Some(count) = count.or_else(|| attrs.values().next().map(RawAttribute::num_elements))
+    else {
+        return Ok(0);
+    };
+    match attrs.iter().find(|(_, attr)| attr.num_elements() != count) {
+        Some((name, attr)) => Err(Error::InconsistentOutAttr {
+            entity,
+            attr: name.to_string(),
+            expected: count,
+            actual: attr.num_elements(),
+        }),
+        None => Ok(count),
+    }
+}
+
+/// Maps the vertex indices of the output prims to point indices.
+enum VertexToPoint<'a> {
+    Ptnum(&'a [usize]),
+    /// Vertex `i` refers to point `i`, see [`OutputOptions::synthesize_ptnum`].
+    Synthesized {
+        point_count: usize,
+    },
+}
+
+impl<'a> VertexToPoint<'a> {
+    fn new(
+        ptnum: Option<&'a RawAttribute>,
+        (point_count, vertex_count): (usize, usize),
+        synthesize_ptnum: bool,
+    ) -> Result<Self> {
+        match ptnum {
+            Some(ptnum) => {
+                let RawAttributeData::Index(ptnum) = &ptnum.data else {
+                    return Err(Error::InvalidOutVertexPtnum);
+                };
+                Ok(Self::Ptnum(ptnum))
+            }
+            None if synthesize_ptnum => {
+                if vertex_count != 0 && vertex_count != point_count {
+                    return Err(Error::PtnumSynthesisMismatch {
+                        points: point_count,
+                        vertices: vertex_count,
+                    });
+                }
+                Ok(Self::Synthesized { point_count })
+            }
+            // Most likely the prims refer to points directly, without a vertex entity.
+            None if vertex_count == 0 => Err(Error::MissingOutVertices),
+            None => Err(Error::MissingOutVertexPtnums),
+        }
+    }
+
+    fn get(&self, vertex: usize) -> Result<usize> {
+        match *self {
+            Self::Ptnum(ptnum) => ptnum.get(vertex).copied(),
+            Self::Synthesized { point_count } => (vertex < point_count).then_some(vertex),
+        }
+        .ok_or(Error::InvalidOutPrimVertex(vertex))
+    }
+}
+
+/// Everything a node emits. A single geometry is serialized as an object, multiple geometries as a
+/// list of objects in output order.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum NodeOutput {
+    Single(RawGeometryOutput),
+    Multiple(Vec<RawGeometryOutput>),
+}
+
+/// The return type of a node: either a single geometry, a tuple of geometries, one per output, or
+/// `()` for nodes without output.
+pub trait IntoNodeOutput {
+    fn into_node_output(self) -> Result<NodeOutput>;
+}
+
+impl<G: IntoRawGeometry> IntoNodeOutput for G {
+    fn into_node_output(self) -> Result<NodeOutput> {
+        Ok(NodeOutput::Single(self.into_raw()?))
+    }
+}
+
+/// For nodes that only have side effects, like writing a report file. Emits an empty geometry so
+/// that Houdini still gets parseable output.
+impl IntoNodeOutput for () {
+    fn into_node_output(self) -> Result<NodeOutput> {
+        Ok(NodeOutput::Single(RawGeometryOutput::default()))
+    }
+}
+
+/// Implements [`IntoNodeOutput`] for a tuple of geometries.
+macro_rules! impl_into_node_output {
+    ($($name:ident),+) => {
+        impl<$($name: IntoRawGeometry),+> IntoNodeOutput for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn into_node_output(self) -> Result<NodeOutput> {
+                let ($($name,)+) = self;
+                Ok(NodeOutput::Multiple(vec![$($name.into_raw()?),+]))
+            }
+        }
+    };
+}
+
+impl_into_node_output!(A, B);
+impl_into_node_output!(A, B, C);
+impl_into_node_output!(A, B, C, D);
+
+/// Where an attribute that caused an error was read from.
+#[derive(Debug, Copy, Clone)]
+pub struct AttrLocation {
+    pub context: ErrContext,
+    pub attr: &'static str,
+}
+
+impl Display for AttrLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "input {} {} attribute: {}",
+            self.context.input_index, self.context.entity, self.attr
+        )
     }
 }
 
@@ -379,7 +2020,7 @@ pub struct ErrContext {
     pub entity: EntityKind,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum EntityKind {
     Point,
     Vertex,
@@ -399,18 +2040,78 @@ impl Display for EntityKind {
 }
 
 pub trait OutAttrs: Sized {
-    fn into_attr(entities: Vec<Self>) -> HashMap<&'static str, RawAttribute>;
+    /// Names and tuple sizes of the attributes that are written by [`OutAttrs::into_attr`].
+    /// Defaults to empty, in which case the attributes are taken from what `into_attr` returns
+    /// for no entities.
+    const OUT_ATTRS: &'static [(&'static str, usize)] = &[];
+
+    /// Fails if a value can't be converted, like a `#[attr(json)]` field that doesn't serialize
+    /// to JSON.
+    fn into_attr(entities: Vec<Self>) -> Result<BTreeMap<&'static str, RawAttribute>>;
+
+    /// Like [`OutAttrs::into_attr`], but leaves the entities to the caller. Defaults to cloning
+    /// them. The derive only copies the individual field values into the output instead.
+    fn into_attr_ref(entities: &[Self]) -> Result<BTreeMap<&'static str, RawAttribute>>
+    where
+        Self: Clone,
+    {
+        Self::into_attr(entities.to_vec())
+    }
+
+    /// Like [`OutAttrs::into_attr_ref`], for a single attribute. `None` if there is no attribute
+    /// with that name. Defaults to building all attributes and picking the requested one.
+    fn single_attr_ref(entities: &[Self], name: &str) -> Result<Option<RawAttribute>>
+    where
+        Self: Clone,
+    {
+        Ok(Self::into_attr_ref(entities)?.remove(name))
+    }
 }
 
 impl OutAttrs for () {
-    fn into_attr(_entities: Vec<Self>) -> HashMap<&'static str, RawAttribute> {
-        HashMap::new()
+    fn into_attr(_entities: Vec<Self>) -> Result<BTreeMap<&'static str, RawAttribute>> {
+        Ok(BTreeMap::new())
+    }
+}
+
+/// The attributes written by `T`. See [`OutAttrs::OUT_ATTRS`].
+pub(crate) fn out_attrs<T: OutAttrs>() -> Cow<'static, [(&'static str, usize)]> {
+    match T::OUT_ATTRS {
+        // Without entities, there are no values that could fail to convert.
+        [] => T::into_attr(Vec::new())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, attr)| (name, attr.tuple_size))
+            .collect(),
+        attrs => Cow::Borrowed(attrs),
     }
 }
 
 pub trait IntoAttributeData: Sized {
     type DataType: IntoAttributeDataSource;
     fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType>;
+
+    /// Predefined implementation that calls `into_attr_data`, with the tuple size of the data
+    /// type. Overridden by types whose tuple size is only known at runtime.
+    fn into_attr_raw(data: impl Iterator<Item = Self>) -> RawAttribute {
+        RawAttribute {
+            tuple_size: Self::DataType::LEN,
+            data: Self::DataType::into_attr_data(Self::into_attr_data(data)),
+            meta: None,
+        }
+    }
+}
+
+/// Used by the derived [`OutAttrs::into_attr_ref`].
+impl<T: IntoAttributeData + Clone> IntoAttributeData for &T {
+    type DataType = T::DataType;
+    fn into_attr_data(data: impl Iterator<Item = Self>) -> impl Iterator<Item = Self::DataType> {
+        T::into_attr_data(data.cloned())
+    }
+
+    fn into_attr_raw(data: impl Iterator<Item = Self>) -> RawAttribute {
+        T::into_attr_raw(data.cloned())
+    }
 }
 
 pub trait IntoAttributeDataSource: Sized {
@@ -420,6 +2121,10 @@ pub trait IntoAttributeDataSource: Sized {
 
 /// To be derived from the Geo Entity (Point, Vertex, Prim or Detail)
 pub trait InAttrs: Sized {
+    /// Names and tuple sizes of the attributes that are read by [`InAttrs::from_attr`].
+    /// Defaults to empty, in which case `from_attr` is passed all attributes of its entity.
+    const IN_ATTRS: &'static [(&'static str, usize)] = &[];
+
     fn from_attr(
         attrs: HashMap<String, RawAttribute>,
         err_context: ErrContext,
@@ -444,20 +2149,33 @@ impl InAttrs for () {
     }
 }
 
+/// The attributes read by `T`, `None` if it doesn't list them. See [`InAttrs::IN_ATTRS`].
+pub(crate) fn in_attrs<T: InAttrs>() -> Option<&'static [(&'static str, usize)]> {
+    match T::IN_ATTRS {
+        [] if T::empty().is_none() => None,
+        attrs => Some(attrs),
+    }
+}
+
 /// Translates from the chunked raw data into the final representation.
 /// To be implemented by the various data types.
 pub trait FromAttributeData: Sized {
     type DataType: FromAttributeDataSource;
     fn from_attr_data(data: impl Iterator<Item = Self::DataType>) -> impl Iterator<Item = Self>;
 
-    /// Predefined implementation that chunks the data, then calls `from_attr_data`.
+    /// Predefined implementation that chunks the data, then calls `from_attr_data`. A missing
+    /// attribute is only accepted if there are no elements, as empty entities are written as
+    /// empty sections.
     fn from_attr_data_raw(
         attr: Option<RawAttribute>,
-        _num_elements: usize,
+        num_elements: usize,
         attr_name: &'static str,
         err_context: ErrContext,
     ) -> Result<impl Iterator<Item = Self>> {
         let Some(attr) = attr else {
+            if num_elements == 0 && err_context.entity != EntityKind::Detail {
+                return Ok(itertools::Either::Right(iter::empty()));
+            }
             return Err(MissingAttr {
                 input_index: err_context.input_index,
                 entity: err_context.entity,
@@ -472,9 +2190,23 @@ pub trait FromAttributeData: Sized {
             });
         }
 
-        let data_iter = Self::DataType::from_attr_data(attr)?;
+        let data_iter = Self::DataType::from_attr_data(attr).map_err(|e| match e {
+            Error::InvalidAttributeType {
+                expected,
+                actual,
+                location: None,
+            } => Error::InvalidAttributeType {
+                expected,
+                actual,
+                location: Some(AttrLocation {
+                    context: err_context,
+                    attr: attr_name,
+                }),
+            },
+            e => e,
+        })?;
 
-        Ok(Self::from_attr_data(data_iter))
+        Ok(itertools::Either::Left(Self::from_attr_data(data_iter)))
     }
 }
 
@@ -496,36 +2228,209 @@ pub fn load_from_attr<T: FromAttributeData>(
     T::from_attr_data_raw(attr, num_elements, attr_name, err_context)
 }
 
-pub fn generate_to_attr<T: IntoAttributeData>(data: Vec<T>) -> RawAttribute {
-    let data_iter = data.into_iter();
-    let data = T::DataType::into_attr_data(T::into_attr_data(data_iter));
-    RawAttribute {
-        tuple_size: T::DataType::LEN,
-        data,
-    }
+/// Like [`load_from_attr`], for fields with a `tuple_size` override. The attribute must have
+/// exactly `tuple_size` components, which are then resized to the tuple size of the field type.
+pub fn load_from_attr_resized<T: FromAttributeData>(
+    attr: Option<RawAttribute>,
+    tuple_size: usize,
+    num_elements: usize,
+    attr_name: &'static str,
+    err_context: ErrContext,
+) -> Result<impl Iterator<Item = T>> {
+    let attr = match attr {
+        Some(mut attr) => {
+            if attr.tuple_size != tuple_size {
+                return Err(Error::InvalidAttributeLength {
+                    expected: tuple_size,
+                    actual: attr.tuple_size,
+                });
+            }
+            attr.resize_tuples(T::DataType::LEN)?;
+            Some(attr)
+        }
+        None => None,
+    };
+    T::from_attr_data_raw(attr, num_elements, attr_name, err_context)
 }
 
-#[cfg(test)]
-mod tests {
-    extern crate self as houdini_node;
+/// Like [`load_from_attr`], for fields with a `component` option. Reads a single component of an
+/// attribute that is shared with the fields reading the other components.
+pub fn load_component_from_attr<T: FromAttributeData>(
+    attr: Option<&RawAttribute>,
+    component: usize,
+    num_elements: usize,
+    attr_name: &'static str,
+    err_context: ErrContext,
+) -> Result<impl Iterator<Item = T> + use<T>> {
+    let attr = attr.map(|attr| attr.component(component)).transpose()?;
+    T::from_attr_data_raw(attr, num_elements, attr_name, err_context)
+}
 
-    use super::*;
-    use glam::Vec3;
-    use houdini_node_macro::{InAttrs, OutAttrs};
+/// Like [`generate_to_attr`], but resizes the attribute to `tuple_size` components, see
+/// [`RawAttribute::resize_tuples`]. Fails for array attributes, which the derive rejects where it
+/// can.
+pub fn generate_to_attr_resized<T: IntoAttributeData>(
+    data: Vec<T>,
+    tuple_size: usize,
+) -> Result<RawAttribute> {
+    let mut attr = generate_to_attr(data);
+    attr.resize_tuples(tuple_size)?;
+    Ok(attr)
+}
 
-    #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
-    struct GeoPoint {
-        #[attr(name = "P")]
-        position: Vec3,
-        name: String,
+/// Used by the derive: the number of elements of an entity, which all attributes it reads must
+/// have. Attributes that aren't read are not checked. If none of them are present, e.g. because
+/// all fields are optional or have a `default_fn`, the count is taken from the other attributes,
+/// see [`entity_count`].
+pub fn element_count(
+    attrs: &HashMap<String, RawAttribute>,
+    read: &[(&'static str, usize)],
+    err_context: ErrContext,
+) -> Result<usize> {
+    let mut counts = read
+        .iter()
+        .filter_map(|&(name, _)| Some((name, attrs.get(name)?.num_elements())));
+    let Some((_, count)) = counts.next() else {
+        return Ok(entity_count(attrs, err_context.entity));
+    };
+    match counts.find(|&(_, c)| c != count) {
+        Some((attr, actual)) => Err(Error::InconsistentInAttr {
+            input_index: err_context.input_index,
+            entity: err_context.entity,
+            attr,
+            expected: count,
+            actual,
+        }),
+        None => Ok(count),
     }
+}
 
-    #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
-    struct GeoDetail {
-        some_detail: String,
-    }
+/// The number of elements of an entity, taken from its position or topology attribute (`P` for
+/// points, `ptnum` for vertices, `vertices` for prims), or else from any of its attributes. `0` if
+/// it has no attributes, except for the detail.
+fn entity_count(attrs: &HashMap<String, RawAttribute>, entity: EntityKind) -> usize {
+    let main = match entity {
+        EntityKind::Point => "P",
+        EntityKind::Vertex => "ptnum",
+        EntityKind::Prim => "vertices",
+        // There is always exactly one detail.
+        EntityKind::Detail => return 1,
+    };
+    attrs
+        .get(main)
+        .or_else(|| attrs.values().next())
+        .map_or(0, RawAttribute::num_elements)
+}
 
-    #[test]
+/// Used by the derive for fields marked with `#[attr(clamp = "..", reject_out_of_range)]`.
+pub fn check_range<T: PartialOrd + std::fmt::Debug>(
+    value: T,
+    range: std::ops::RangeInclusive<T>,
+    attr_name: &'static str,
+) -> Result<T> {
+    if range.contains(&value) {
+        Ok(value)
+    } else {
+        Err(Error::ValueOutOfRange {
+            attr: attr_name,
+            value: format!("{value:?}"),
+            range: format!("{range:?}"),
+        })
+    }
+}
+
+/// Like [`load_from_attr`], for fields marked with `#[attr(parse_string)]`. Reads a string
+/// attribute and parses every value, see [`StringEncoded`].
+pub fn load_from_string_attr<T: StringEncoded>(
+    attr: Option<RawAttribute>,
+    num_elements: usize,
+    attr_name: &'static str,
+    err_context: ErrContext,
+) -> Result<impl Iterator<Item = T>> {
+    load_from_attr::<String>(attr, num_elements, attr_name, err_context)?
+        .map(|value| {
+            T::parse_attr_string(&value).ok_or(Error::InvalidAttributeString {
+                attr: attr_name,
+                value,
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Vec::into_iter)
+}
+
+/// Like [`generate_to_attr`], for fields marked with `#[attr(parse_string)]`.
+pub fn generate_to_string_attr<T: StringEncoded, B: std::borrow::Borrow<T>>(
+    data: Vec<B>,
+) -> RawAttribute {
+    generate_to_attr(
+        data.iter()
+            .map(|v| v.borrow().format_attr_string())
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Like [`load_from_attr`], for fields marked with `#[attr(json)]`. Reads a string attribute and
+/// deserializes every value from JSON, like the dictionary attributes of Houdini.
+pub fn load_from_json_attr<T: serde::de::DeserializeOwned>(
+    attr: Option<RawAttribute>,
+    num_elements: usize,
+    attr_name: &'static str,
+    err_context: ErrContext,
+) -> Result<impl Iterator<Item = T>> {
+    load_from_attr::<String>(attr, num_elements, attr_name, err_context)?
+        .map(|value| {
+            serde_json::from_str(&value).map_err(|e| Error::InvalidAttributeJson {
+                attr: attr_name,
+                message: e.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Vec::into_iter)
+}
+
+/// Like [`generate_to_attr`], for fields marked with `#[attr(json)]`. Fails if a value can't be
+/// serialized to JSON, like maps with non-string keys.
+pub fn generate_to_json_attr<T: Serialize, B: std::borrow::Borrow<T>>(
+    data: Vec<B>,
+    attr_name: &'static str,
+) -> Result<RawAttribute> {
+    let values = data
+        .iter()
+        .map(|v| {
+            serde_json::to_string(v.borrow()).map_err(|e| Error::InvalidAttributeJson {
+                attr: attr_name,
+                message: e.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(generate_to_attr(values))
+}
+
+pub fn generate_to_attr<T: IntoAttributeData>(data: Vec<T>) -> RawAttribute {
+    T::into_attr_raw(data.into_iter())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use glam::Vec3;
+    use houdini_node_macro::{InAttrs, OutAttrs};
+
+    #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
+    struct GeoPoint {
+        #[attr(name = "P")]
+        position: Vec3,
+        name: String,
+    }
+
+    #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
+    struct GeoDetail {
+        some_detail: String,
+    }
+
+    #[test]
     fn parsing() {
         let d = r#"
         [
@@ -574,7 +2479,837 @@ mod tests {
         ]
         "#;
 
-        load::<Geometry<GeoPoint>>(d.as_bytes()).unwrap();
+        load_from_reader::<Geometry<GeoPoint>>(d.as_bytes(), 0).unwrap();
+    }
+
+    #[test]
+    fn summary() {
+        let raw = RawGeometry {
+            points: HashMap::from([
+                (
+                    "P".to_string(),
+                    RawAttribute {
+                        tuple_size: 3,
+                        data: RawAttributeData::Float(vec![0.0; 6]),
+                        meta: None,
+                    },
+                ),
+                (
+                    "name".to_string(),
+                    RawAttribute {
+                        tuple_size: 1,
+                        data: RawAttributeData::String(vec!["a".to_string(), "b".to_string()]),
+                        meta: None,
+                    },
+                ),
+            ]),
+            vertices: HashMap::new(),
+            prims: HashMap::new(),
+            detail: HashMap::new(),
+        };
+
+        let summary = raw.to_string();
+        assert!(summary.contains("points: 2 [P: float3, name: string]"));
+        assert!(summary.contains("detail: 0 []"));
+    }
+
+    #[test]
+    fn drop_attribute() {
+        let mut raw = RawGeometry::fixture()
+            .point_attr("P", vec![Vec3::ZERO])
+            .point_attr("Cd", vec![Vec3::ONE])
+            .build();
+
+        assert!(raw.drop_point_attribute("Cd"));
+        assert!(!raw.points.contains_key("Cd"));
+        assert!(raw.points.contains_key("P"));
+        assert!(!raw.drop_point_attribute("Cd"));
+        assert!(!raw.drop_prim_attribute("P"));
+    }
+
+    #[test]
+    fn attribute_introspection() {
+        assert_eq!(GeoPoint::IN_ATTRS, &[("P", 3), ("name", 1)]);
+        assert_eq!(GeoPoint::OUT_ATTRS, &[("P", 3), ("name", 1)]);
+        assert!(<() as OutAttrs>::OUT_ATTRS.is_empty());
+    }
+
+    #[test]
+    fn merge_outputs() {
+        let attr = || RawAttribute {
+            tuple_size: 1,
+            data: RawAttributeData::Float(vec![1.0]),
+            meta: None,
+        };
+        let output = |points: &[&'static str], detail: &[&'static str]| RawGeometryOutput {
+            points: points.iter().map(|&name| (name.into(), attr())).collect(),
+            vertices: BTreeMap::new(),
+            prims: BTreeMap::new(),
+            detail: detail.iter().map(|&name| (name.into(), attr())).collect(),
+        };
+
+        let mut merged = output(&["P"], &[]);
+        merged.merge(output(&["Cd"], &["time"])).unwrap();
+        assert!(merged.points.contains_key("P"));
+        assert!(merged.points.contains_key("Cd"));
+        assert!(merged.detail.contains_key("time"));
+
+        let err = merged.merge(output(&["N", "Cd"], &[])).unwrap_err();
+        assert!(matches!(err, Error::AttrNameCollision(name) if name == "Cd"));
+        assert!(!merged.points.contains_key("N"));
+    }
+
+    #[test]
+    fn homogeneous_positions() {
+        #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
+        struct HomogeneousPoint {
+            #[attr(name = "P", tuple_size = 4)]
+            position: Vec3,
+        }
+
+        assert_eq!(HomogeneousPoint::IN_ATTRS, &[("P", 4)]);
+
+        let attrs = HashMap::from([(
+            "P".to_string(),
+            RawAttribute {
+                tuple_size: 4,
+                data: RawAttributeData::Float(vec![1.0, 2.0, 3.0, 0.5, 4.0, 5.0, 6.0, 0.5]),
+                meta: None,
+            },
+        )]);
+        let err_context = ErrContext {
+            input_index: 0,
+            entity: EntityKind::Point,
+        };
+        let points: Vec<_> = HomogeneousPoint::from_attr(attrs, err_context)
+            .unwrap()
+            .collect();
+        assert_eq!(
+            points.iter().map(|p| p.position).collect::<Vec<_>>(),
+            vec![Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0)]
+        );
+
+        let out = HomogeneousPoint::into_attr(points).unwrap();
+        assert_eq!(out["P"].tuple_size, 4);
+        let RawAttributeData::Float(data) = &out["P"].data else {
+            panic!("expected float data");
+        };
+        assert_eq!(data, &[1.0, 2.0, 3.0, 1.0, 4.0, 5.0, 6.0, 1.0]);
+    }
+
+    #[test]
+    fn streaming_frames() {
+        #[derive(PartialEq, Debug, InAttrs)]
+        struct Point {
+            #[attr(name = "P")]
+            position: Vec3,
+        }
+
+        let frame = |x: f32| {
+            format!(
+                r#"[{{"points":{{"P":{{"tuple_size":3,"data":{{"float":[{x},0,0]}}}}}},"vertices":{{}},"prims":{{}},"detail":{{}}}}]"#
+            )
+        };
+        let input = format!("{}\n{}", frame(1.0), frame(2.0));
+
+        let positions: Vec<_> = load_raw_stream(input.as_bytes())
+            .map(|frame| {
+                let raw = frame.unwrap().into_iter().next().unwrap();
+                Geometry::<Point>::from_raw(raw, 0).unwrap().points[0].position
+            })
+            .collect();
+        assert_eq!(positions, vec![Vec3::X, Vec3::new(2.0, 0.0, 0.0)]);
+    }
+
+    #[test]
+    fn convert_attributes() {
+        let convert = |data: RawAttributeData, target: AttributeType| {
+            let mut attr = RawAttribute {
+                tuple_size: 1,
+                data,
+                meta: None,
+            };
+            attr.convert_to(target).map(|()| attr.data)
+        };
+
+        assert_eq!(
+            convert(
+                RawAttributeData::Float(vec![1.4, 1.5, -2.5, f32::NAN, 1e20]),
+                AttributeType::Int
+            )
+            .unwrap(),
+            RawAttributeData::Int(vec![1, 2, -3, 0, i32::MAX])
+        );
+        assert_eq!(
+            convert(RawAttributeData::Int(vec![-1, 7]), AttributeType::Float).unwrap(),
+            RawAttributeData::Float(vec![-1.0, 7.0])
+        );
+        assert_eq!(
+            convert(RawAttributeData::Int(vec![-1, 7]), AttributeType::String).unwrap(),
+            RawAttributeData::String(vec!["-1".to_string(), "7".to_string()])
+        );
+        assert_eq!(
+            convert(
+                RawAttributeData::FloatArray(vec![vec![0.6]]),
+                AttributeType::IntArray
+            )
+            .unwrap(),
+            RawAttributeData::IntArray(vec![vec![1]])
+        );
+        assert_eq!(
+            convert(
+                RawAttributeData::IntArray(vec![vec![3]]),
+                AttributeType::FloatArray
+            )
+            .unwrap(),
+            RawAttributeData::FloatArray(vec![vec![3.0]])
+        );
+        assert_eq!(
+            convert(
+                RawAttributeData::IntArray(vec![vec![3]]),
+                AttributeType::StringArray
+            )
+            .unwrap(),
+            RawAttributeData::StringArray(vec![vec!["3".to_string()]])
+        );
+        assert_eq!(
+            convert(RawAttributeData::Float(vec![1.0]), AttributeType::Float).unwrap(),
+            RawAttributeData::Float(vec![1.0])
+        );
+
+        let err = convert(
+            RawAttributeData::String(vec!["a".to_string()]),
+            AttributeType::Int,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::UnsupportedConversion {
+                from: AttributeType::String,
+                to: AttributeType::Int
+            }
+        ));
+    }
+
+    #[test]
+    fn non_finite_floats() {
+        let g = Geometry::<GeoPoint> {
+            points: vec![GeoPoint {
+                position: Vec3::new(f32::NAN, f32::INFINITY, f32::NEG_INFINITY),
+                name: "a".to_string(),
+            }],
+            vertices: vec![],
+            prims: vec![],
+            detail: (),
+            extra: GeometryExtra::default(),
+        };
+
+        let s = generate_for_testing(g).unwrap();
+        assert!(s.contains(r#"["NaN","inf","-inf"]"#));
+
+        let geo_new = load_from_reader::<Geometry<GeoPoint>>(s.as_bytes(), 0).unwrap();
+        let position = geo_new.points[0].position;
+        assert!(position.x.is_nan());
+        assert_eq!(position.y, f32::INFINITY);
+        assert_eq!(position.z, f32::NEG_INFINITY);
+
+        let raw: RawAttributeData =
+            serde_json::from_str(r#"{"float_array": [[1, "nan", "Infinity"]]}"#).unwrap();
+        let RawAttributeData::FloatArray(v) = raw else {
+            panic!("expected float array");
+        };
+        assert_eq!(v[0][0], 1.0);
+        assert!(v[0][1].is_nan());
+        assert_eq!(v[0][2], f32::INFINITY);
+    }
+
+    #[test]
+    fn missing_tuple_size() {
+        let attr: RawAttribute = serde_json::from_str(r#"{"data": {"int": [1, 2]}}"#).unwrap();
+        assert_eq!(attr.tuple_size, 1);
+        assert_eq!(attr.num_elements(), 2);
+
+        let err = serde_json::from_str::<RawAttribute>(r#"{"data": {"int_array": [[1, 2]]}}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("missing field `tuple_size`"));
+        assert!(err.to_string().contains("int_array"));
+    }
+
+    #[test]
+    fn single_component() {
+        #[derive(PartialEq, Debug, InAttrs)]
+        struct HeightPoint {
+            #[attr(name = "P", component = 1)]
+            height: f32,
+        }
+
+        assert_eq!(HeightPoint::IN_ATTRS, &[("P", 2)]);
+
+        let raw = RawGeometry::fixture()
+            .point_attr(
+                "P",
+                vec![Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0)],
+            )
+            .build();
+        let geo = Geometry::<HeightPoint>::from_raw(raw, 0).unwrap();
+        assert_eq!(
+            geo.points,
+            vec![HeightPoint { height: 2.0 }, HeightPoint { height: 5.0 }]
+        );
+    }
+
+    #[test]
+    fn from_components() {
+        let x = generate_to_attr(vec![1.0f32, 4.0]);
+        let y = generate_to_attr(vec![2.0f32, 5.0]);
+        let xy = RawAttribute::from_components(vec![x.clone(), y.clone()]).unwrap();
+        assert_eq!(xy.tuple_size, 2);
+        assert_eq!(xy.data, RawAttributeData::Float(vec![1.0, 2.0, 4.0, 5.0]));
+        assert_eq!(xy.component(1).unwrap(), y);
+
+        let short = generate_to_attr(vec![3.0f32]);
+        let err = RawAttribute::from_components(vec![x.clone(), short]).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidAttributeLength {
+                expected: 2,
+                actual: 1
+            }
+        ));
+        let err = RawAttribute::from_components(vec![x.clone(), xy]).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidAttributeLength {
+                expected: 1,
+                actual: 2
+            }
+        ));
+        let err = RawAttribute::from_components(Vec::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidAttributeLength { .. }));
+
+        let ints = generate_to_attr(vec![1, 2]);
+        let err = RawAttribute::from_components(vec![x, ints]).unwrap_err();
+        assert!(matches!(err, Error::InvalidAttributeType { .. }));
+    }
+
+    #[test]
+    fn position_components() {
+        #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
+        struct SplitPoint {
+            #[attr(name = "P", component = 2)]
+            z: f32,
+            #[attr(name = "P", component = 0)]
+            x: f32,
+            name: String,
+            #[attr(name = "P", component = 1)]
+            y: f32,
+        }
+
+        assert_eq!(SplitPoint::IN_ATTRS, &[("P", 3), ("name", 1)]);
+        assert_eq!(SplitPoint::OUT_ATTRS, &[("P", 3), ("name", 1)]);
+
+        let g = Geometry::<GeoPoint> {
+            points: vec![
+                GeoPoint {
+                    position: Vec3::new(1.0, 2.0, 3.0),
+                    name: "a".to_string(),
+                },
+                GeoPoint {
+                    position: Vec3::new(4.0, 5.0, 6.0),
+                    name: "b".to_string(),
+                },
+            ],
+            vertices: vec![],
+            prims: vec![],
+            detail: (),
+            extra: GeometryExtra::default(),
+        };
+
+        let s = generate_for_testing(g.clone()).unwrap();
+        let split = load_from_reader::<Geometry<SplitPoint>>(s.as_bytes(), 0).unwrap();
+        assert_eq!(
+            split.points[1],
+            SplitPoint {
+                x: 4.0,
+                y: 5.0,
+                z: 6.0,
+                name: "b".to_string()
+            }
+        );
+
+        let s = generate_for_testing(split).unwrap();
+        let geo_new = load_from_reader::<Geometry<GeoPoint>>(s.as_bytes(), 0).unwrap();
+        assert_eq!(g, geo_new);
+    }
+
+    #[test]
+    fn string_encoded_vectors() {
+        #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
+        struct LegacyPoint {
+            #[attr(name = "dir", parse_string)]
+            direction: Vec3,
+        }
+
+        assert_eq!(LegacyPoint::IN_ATTRS, &[("dir", 1)]);
+
+        let err_context = ErrContext {
+            input_index: 0,
+            entity: EntityKind::Point,
+        };
+        let attrs = |values: &[&str]| {
+            HashMap::from([(
+                "dir".to_string(),
+                RawAttribute {
+                    tuple_size: 1,
+                    data: RawAttributeData::String(values.iter().map(|v| v.to_string()).collect()),
+                    meta: None,
+                },
+            )])
+        };
+
+        let points: Vec<_> =
+            LegacyPoint::from_attr(attrs(&["1.0,2.0,3.0", " 4, 5.5 ,-6"]), err_context)
+                .unwrap()
+                .collect();
+        assert_eq!(points[0].direction, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(points[1].direction, Vec3::new(4.0, 5.5, -6.0));
+
+        let out = LegacyPoint::into_attr(points.clone()).unwrap();
+        assert_eq!(
+            out["dir"].data,
+            RawAttributeData::String(vec!["1,2,3".to_string(), "4,5.5,-6".to_string()])
+        );
+
+        for malformed in ["1.0,2.0", "1,2,3,4", "1,x,3"] {
+            let err = LegacyPoint::from_attr(attrs(&[malformed]), err_context)
+                .err()
+                .unwrap();
+            assert!(matches!(
+                err,
+                Error::InvalidAttributeString { attr: "dir", .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn string_encoded_bools() {
+        #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
+        struct FlagPoint {
+            #[attr(bool_from_string)]
+            visible: bool,
+        }
+
+        let err_context = ErrContext {
+            input_index: 0,
+            entity: EntityKind::Point,
+        };
+        let attrs = |values: &[&str]| {
+            HashMap::from([(
+                "visible".to_string(),
+                RawAttribute {
+                    tuple_size: 1,
+                    data: RawAttributeData::String(values.iter().map(|v| v.to_string()).collect()),
+                    meta: None,
+                },
+            )])
+        };
+
+        let points: Vec<_> = FlagPoint::from_attr(attrs(&["true", "0", "1", "false"]), err_context)
+            .unwrap()
+            .map(|p| p.visible)
+            .collect();
+        assert_eq!(points, vec![true, false, true, false]);
+
+        let out = FlagPoint::into_attr(vec![FlagPoint { visible: true }]).unwrap();
+        assert_eq!(
+            out["visible"].data,
+            RawAttributeData::String(vec!["true".to_string()])
+        );
+
+        let err = FlagPoint::from_attr(attrs(&["true", "yes"]), err_context)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::InvalidAttributeString { attr: "visible", value } if value == "yes"
+        ));
+    }
+
+    #[test]
+    fn clamp_and_reject() {
+        #[derive(InAttrs)]
+        struct MaskPoint {
+            #[attr(clamp = "0.0..=1.0")]
+            mask: f32,
+            #[attr(clamp = "0..=10", reject_out_of_range)]
+            level: i32,
+        }
+
+        let err_context = ErrContext {
+            input_index: 0,
+            entity: EntityKind::Point,
+        };
+        let attrs = |levels: Vec<i32>| {
+            HashMap::from([
+                (
+                    "mask".to_string(),
+                    generate_to_attr(vec![-0.5f32, 0.25, 2.0]),
+                ),
+                ("level".to_string(), generate_to_attr(levels)),
+            ])
+        };
+
+        let points: Vec<_> = MaskPoint::from_attr(attrs(vec![0, 5, 10]), err_context)
+            .unwrap()
+            .map(|point| (point.mask, point.level))
+            .collect();
+        assert_eq!(points, vec![(0.0, 0), (0.25, 5), (1.0, 10)]);
+
+        let err = MaskPoint::from_attr(attrs(vec![0, 11, 10]), err_context)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Value 11 of attribute level is outside of 0..=10"
+        );
+    }
+
+    #[test]
+    fn typed_points_raw_prims() {
+        let raw = RawGeometry::fixture()
+            .point_attr("P", vec![Vec3::X, Vec3::Y, Vec3::Z])
+            .point_attr(
+                "name",
+                vec!["a", "b", "c"].into_iter().map(String::from).collect(),
+            )
+            .point_attr("weight", vec![0.5f32, 1.0, 1.5])
+            .polygons(vec![vec![0, 1, 2]])
+            .prim_attr("material", vec!["wood".to_string()])
+            .build();
+
+        let (points, rest) = raw.into_typed_parts::<GeoPoint>(0).unwrap();
+        assert_eq!(points[2].position, Vec3::Z);
+        assert_eq!(points[2].name, "c");
+
+        assert_eq!(rest.points.keys().collect::<Vec<_>>(), vec!["weight"]);
+        assert_eq!(
+            rest.prims["vertices"].data,
+            RawAttributeData::PrimVertex(vec![[0, 1, 2][..].into()])
+        );
+        assert_eq!(
+            rest.prims["material"].data,
+            RawAttributeData::String(vec!["wood".to_string()])
+        );
+        assert_eq!(
+            rest.vertices["ptnum"].data,
+            RawAttributeData::Index(vec![0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn default_fn_ids() {
+        fn sequential_id(index: usize) -> i32 {
+            index as i32 + 100
+        }
+
+        #[derive(PartialEq, Debug, InAttrs)]
+        struct IdPoint {
+            #[attr(name = "P")]
+            position: Vec3,
+            #[attr(default_fn = "sequential_id")]
+            id: i32,
+        }
+
+        let err_context = ErrContext {
+            input_index: 0,
+            entity: EntityKind::Point,
+        };
+        let positions = generate_to_attr(vec![Vec3::X, Vec3::Y, Vec3::Z]);
+        let ids: Vec<_> = IdPoint::from_attr(
+            HashMap::from([("P".to_string(), positions.clone())]),
+            err_context,
+        )
+        .unwrap()
+        .map(|point| point.id)
+        .collect();
+        assert_eq!(ids, vec![100, 101, 102]);
+
+        // An existing attribute is read as usual.
+        let attrs = HashMap::from([
+            ("P".to_string(), positions),
+            ("id".to_string(), generate_to_attr(vec![7, 8, 9])),
+        ]);
+        let ids: Vec<_> = IdPoint::from_attr(attrs, err_context)
+            .unwrap()
+            .map(|point| point.id)
+            .collect();
+        assert_eq!(ids, vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn only_default_fn() {
+        fn sequential_id(index: usize) -> i32 {
+            index as i32
+        }
+
+        #[derive(PartialEq, Debug, InAttrs)]
+        struct IdPoint {
+            #[attr(default_fn = "sequential_id")]
+            id: i32,
+        }
+
+        let raw = RawGeometry {
+            points: HashMap::from([(
+                "P".to_string(),
+                generate_to_attr(vec![Vec3::X, Vec3::Y, Vec3::Z]),
+            )]),
+            vertices: HashMap::new(),
+            prims: HashMap::new(),
+            detail: HashMap::new(),
+        };
+        let geo: Geometry<IdPoint> = load_from_raw(raw, 0).unwrap();
+        let ids: Vec<_> = geo.points.iter().map(|point| point.id).collect();
+        assert_eq!(ids, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn json_attributes() {
+        #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+        struct Settings {
+            name: String,
+            weights: Vec<f32>,
+            nested: Option<Box<Settings>>,
+        }
+
+        #[derive(PartialEq, Debug, Clone, OutAttrs, InAttrs)]
+        struct ConfiguredPoint {
+            #[attr(json)]
+            settings: Settings,
+        }
+
+        assert_eq!(ConfiguredPoint::IN_ATTRS, &[("settings", 1)]);
+
+        let points = vec![ConfiguredPoint {
+            settings: Settings {
+                name: "outer".to_string(),
+                weights: vec![0.5, 1.0],
+                nested: Some(Box::new(Settings {
+                    name: "inner".to_string(),
+                    weights: vec![],
+                    nested: None,
+                })),
+            },
+        }];
+        let out = ConfiguredPoint::into_attr(points.clone()).unwrap();
+        let json = out["settings"].data.clone().string().unwrap();
+        assert!(json[0].starts_with(r#"{"name":"outer","weights":[0.5,1.0],"nested":{"#));
+
+        let err_context = ErrContext {
+            input_index: 0,
+            entity: EntityKind::Point,
+        };
+        let attrs = HashMap::from([("settings".to_string(), out["settings"].clone())]);
+        let read: Vec<_> = ConfiguredPoint::from_attr(attrs, err_context)
+            .unwrap()
+            .collect();
+        assert_eq!(read, points);
+
+        let attrs = HashMap::from([(
+            "settings".to_string(),
+            RawAttribute {
+                tuple_size: 1,
+                data: RawAttributeData::String(vec![r#"{"name": 1}"#.to_string()]),
+                meta: None,
+            },
+        )]);
+        let err = ConfiguredPoint::from_attr(attrs, err_context)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::InvalidAttributeJson {
+                attr: "settings",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn unserializable_json_attribute() {
+        #[derive(Clone, OutAttrs)]
+        struct GridPoint {
+            // JSON objects need string keys.
+            #[attr(json)]
+            cells: HashMap<(i32, i32), i32>,
+        }
+
+        let geo = Geometry::from(vec![GridPoint {
+            cells: HashMap::from([((0, 1), 2)]),
+        }]);
+        for err in [geo.to_raw().unwrap_err(), geo.into_raw().unwrap_err()] {
+            assert!(matches!(
+                err,
+                Error::InvalidAttributeJson { attr: "cells", .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_attribute_type_location() {
+        let attrs = HashMap::from([
+            (
+                "P".to_string(),
+                RawAttribute {
+                    tuple_size: 3,
+                    data: RawAttributeData::Float(vec![0.0; 3]),
+                    meta: None,
+                },
+            ),
+            (
+                "name".to_string(),
+                RawAttribute {
+                    tuple_size: 1,
+                    data: RawAttributeData::Int(vec![1]),
+                    meta: None,
+                },
+            ),
+        ]);
+        let err_context = ErrContext {
+            input_index: 2,
+            entity: EntityKind::Point,
+        };
+
+        let err = GeoPoint::from_attr(attrs, err_context).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Invalid attribute type (expected: string, actual: int) for input 2 point attribute: name"
+        );
+    }
+
+    #[test]
+    fn ndjson_round_trip() {
+        let geo = |name: &str| Geometry::<GeoPoint> {
+            points: vec![GeoPoint {
+                position: Vec3::X,
+                name: name.to_string(),
+            }],
+            vertices: vec![],
+            prims: vec![],
+            detail: (),
+            extra: GeometryExtra::default(),
+        };
+
+        let mut out = Vec::new();
+        generate_ndjson_to_writer(&mut out, (geo("a"), geo("b"))).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.ends_with('\n'));
+
+        let raw = load_raw_ndjson(out.as_slice()).unwrap();
+        let geos: Vec<_> = raw
+            .into_iter()
+            .enumerate()
+            .map(|(i, raw)| Geometry::<GeoPoint>::from_raw(raw, i).unwrap())
+            .collect();
+        assert_eq!(geos, vec![geo("a"), geo("b")]);
+    }
+
+    #[test]
+    fn capture_and_replay() {
+        let input = r#"[{
+            "points": {"P": {"tuple_size": 3, "data": {"float": [0, 1, 2]}}},
+            "vertices": {}, "prims": {}, "detail": {}
+        }]"#;
+        let path =
+            std::env::temp_dir().join(format!("houdini-node-capture-{}.json", std::process::id()));
+
+        let loaded = load_raw_capturing(input.as_bytes(), &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), input);
+        let replayed = replay_from_path(&path).unwrap();
+        assert_eq!(replayed, loaded);
+
+        // Reading stops at the end of the input, as stdin stays open while the node runs.
+        let with_trailing = format!("{input} not read");
+        load_raw_capturing(with_trailing.as_bytes(), &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), input);
+
+        // Input that fails to parse is still captured.
+        assert!(load_raw_capturing(&b"[{"[..], &path).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"[{");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reader_stays_open() {
+        /// Stands in for stdin, which the bridge only closes to cancel the node.
+        struct StillOpen;
+
+        impl std::io::Read for StillOpen {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                panic!("read past the end of the input");
+            }
+        }
+
+        let input = r#"[{"points":{},"vertices":{},"prims":{},"detail":{}}]"#;
+        let raw = load_raw_from_reader(std::io::Read::chain(input.as_bytes(), StillOpen)).unwrap();
+        assert_eq!(raw.len(), 1);
+
+        let geo = r#"{"points":{},"vertices":{},"prims":{},"detail":{}}"#;
+        let input = format!("2\n{geo}\n{geo}\n");
+        let raw =
+            load_raw_ndjson_counted(std::io::Read::chain(input.as_bytes(), StillOpen)).unwrap();
+        assert_eq!(raw.len(), 2);
+    }
+
+    #[test]
+    fn ndjson_counted() {
+        let geo = r#"{"points":{},"vertices":{},"prims":{},"detail":{}}"#;
+        let raw = load_raw_ndjson_counted(format!("0\n{geo}\n").as_bytes()).unwrap();
+        assert!(raw.is_empty());
+
+        let err = load_raw_ndjson_counted(format!("2\n{geo}\n").as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::TruncatedInput { line: 3, .. }));
+        let err = load_raw_ndjson_counted(&b""[..]).unwrap_err();
+        assert!(matches!(err, Error::TruncatedInput { line: 1, .. }));
+
+        // Plain NDJSON, without the count.
+        let err = load_raw_ndjson_counted(format!("{geo}\n").as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::Json(_)));
+    }
+
+    #[test]
+    fn truncated_input() {
+        let input = r#"[{
+            "points": {"P": {"tuple_size": 3, "data": {"float": [0, 1, 2]}}},
+            "vertices": {}, "prims": {}, "detail": {}
+        }]"#;
+        let truncated = &input[..input.find("2]").unwrap()];
+
+        let err = load_raw_from_reader(truncated.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::TruncatedInput { line: 2, .. }));
+        let err = load_raw_ndjson(&input.as_bytes()[..10]).unwrap_err();
+        assert!(matches!(err, Error::TruncatedInput { .. }));
+
+        // Input that is complete but invalid is not truncated.
+        let err = load_raw_from_reader(&br#"[{"points": 1}]"#[..]).unwrap_err();
+        assert!(matches!(err, Error::Json(_)));
+    }
+
+    #[test]
+    fn load_selected_input() {
+        #[derive(PartialEq, Debug, InAttrs)]
+        struct Point {
+            #[attr(name = "P")]
+            position: Vec3,
+        }
+
+        let input = r#"[{"points":{"P":{"tuple_size":3,"data":{"float":[1,2,3]}}},"vertices":{},"prims":{},"detail":{}}]"#;
+
+        let geo = load_from_reader::<Geometry<Point>>(input.as_bytes(), 0).unwrap();
+        assert_eq!(geo.points[0].position, Vec3::new(1.0, 2.0, 3.0));
+
+        let err = load_from_reader::<Geometry<Point>>(input.as_bytes(), 1).unwrap_err();
+        assert!(matches!(err, Error::GeometryMissing(1)));
     }
 
     /// Output currently only supports a single geo, but input has multiple.
@@ -595,10 +3330,514 @@ mod tests {
             detail: GeoDetail {
                 some_detail: "hello".to_string(),
             },
+            extra: GeometryExtra::default(),
         };
 
         let s = generate_for_testing(g.clone()).unwrap();
-        let geo_new = load::<Geometry<GeoPoint, (), (), GeoDetail>>(s.as_bytes()).unwrap();
+        let geo_new =
+            load_from_reader::<Geometry<GeoPoint, (), (), GeoDetail>>(s.as_bytes(), 0).unwrap();
         assert_eq!(g, geo_new);
     }
+
+    #[test]
+    fn borrowed_output() {
+        #[derive(PartialEq, Debug, OutAttrs)]
+        struct TrackedPoint {
+            #[attr(name = "P")]
+            position: Vec3,
+            name: String,
+            #[attr(name = "dir", parse_string)]
+            direction: Vec3,
+        }
+
+        // `to_raw` needs `Clone`, but the derived implementation only copies the field values.
+        impl Clone for TrackedPoint {
+            fn clone(&self) -> Self {
+                panic!("the entities must not be cloned")
+            }
+        }
+
+        let g = Geometry::<TrackedPoint> {
+            points: vec![TrackedPoint {
+                position: Vec3::X,
+                name: "a".to_string(),
+                direction: Vec3::Y,
+            }],
+            vertices: vec![],
+            prims: vec![],
+            detail: (),
+            extra: GeometryExtra::default(),
+        };
+
+        let raw = g.to_raw().unwrap();
+        assert_eq!(
+            raw.points["P"].data,
+            RawAttributeData::Float(vec![1.0, 0.0, 0.0])
+        );
+        assert_eq!(
+            raw.points["dir"].data,
+            RawAttributeData::String(vec!["0,1,0".to_string()])
+        );
+
+        // Still owned by the caller, and serializes the same as when consumed.
+        assert_eq!(g.points[0].name, "a");
+        assert_eq!(
+            serde_json::to_string(&raw).unwrap(),
+            serde_json::to_string(&g.into_raw().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn borrowed_output_without_clone() {
+        /// A field type that can't be borrowed into the output.
+        #[derive(PartialEq, Debug)]
+        struct Weight(f32);
+
+        impl IntoAttributeData for Weight {
+            type DataType = f32;
+            fn into_attr_data(
+                data: impl Iterator<Item = Self>,
+            ) -> impl Iterator<Item = Self::DataType> {
+                data.map(|w| w.0)
+            }
+        }
+
+        #[derive(PartialEq, Debug, OutAttrs)]
+        struct WeightedPoint {
+            #[attr(name = "P")]
+            position: Vec3,
+            weight: Weight,
+        }
+
+        // Deriving doesn't need `Clone`, only borrowing the entities does.
+        impl Clone for WeightedPoint {
+            fn clone(&self) -> Self {
+                Self {
+                    position: self.position,
+                    weight: Weight(self.weight.0),
+                }
+            }
+        }
+
+        /// Implements only what is required, borrowing falls back to cloning the entities.
+        #[derive(Clone)]
+        struct ManualPoint(f32);
+
+        impl OutAttrs for ManualPoint {
+            fn into_attr(entities: Vec<Self>) -> Result<BTreeMap<&'static str, RawAttribute>> {
+                Ok(BTreeMap::from([(
+                    "weight",
+                    generate_to_attr(entities.into_iter().map(|p| p.0).collect()),
+                )]))
+            }
+        }
+
+        let weighted = Geometry::from(vec![WeightedPoint {
+            position: Vec3::X,
+            weight: Weight(0.5),
+        }]);
+        let manual = Geometry::from(vec![ManualPoint(0.5)]);
+        // Without a listing, the attributes are taken from the output.
+        assert_eq!(*out_attrs::<ManualPoint>(), [("weight", 1)]);
+        for raw in [weighted.to_raw().unwrap(), manual.to_raw().unwrap()] {
+            assert_eq!(
+                raw.points["weight"].data,
+                RawAttributeData::Float(vec![0.5])
+            );
+        }
+        assert_eq!(
+            WeightedPoint::single_attr_ref(&weighted.points, "weight")
+                .unwrap()
+                .unwrap()
+                .data,
+            RawAttributeData::Float(vec![0.5])
+        );
+    }
+
+    #[test]
+    fn deterministic_output() {
+        #[derive(PartialEq, Debug, Clone, OutAttrs)]
+        struct ManyAttrs {
+            e: i32,
+            b: i32,
+            d: i32,
+            a: i32,
+            c: i32,
+        }
+
+        let g = Geometry::<ManyAttrs> {
+            points: vec![ManyAttrs {
+                e: 0,
+                b: 1,
+                d: 2,
+                a: 3,
+                c: 4,
+            }],
+            vertices: vec![],
+            prims: vec![],
+            detail: (),
+            extra: GeometryExtra::default(),
+        };
+
+        let first = generate_for_testing(g.clone()).unwrap();
+        let second = generate_for_testing(g).unwrap();
+        assert_eq!(first, second);
+        assert!(first.starts_with(r#"[{"points":{"a":"#));
+    }
+
+    #[test]
+    fn point_only_from_vec() {
+        let geo: Geometry<GeoPoint> = vec![GeoPoint {
+            position: Vec3::X,
+            name: "a".to_string(),
+        }]
+        .into();
+        assert_eq!(geo.points.len(), 1);
+        assert!(geo.vertices.is_empty());
+        assert!(geo.prims.is_empty());
+
+        let out = geo.into_raw().unwrap();
+        assert_eq!(
+            out.points["P"].data.clone().float().unwrap(),
+            vec![1.0, 0.0, 0.0]
+        );
+        assert!(out.prims.is_empty());
+    }
+
+    #[test]
+    fn float_precision() {
+        #[derive(Clone, OutAttrs)]
+        struct Value {
+            v: f32,
+        }
+
+        let values = [1.2345678, -123456.7, 0.000123456, 0.0, f32::NAN, 2.5e-8];
+        let geo: Geometry<Value> = values
+            .iter()
+            .map(|&v| Value { v })
+            .collect::<Vec<_>>()
+            .into();
+        let full = serde_json::to_string(&geo.to_raw().unwrap()).unwrap();
+
+        for (precision, tolerance) in [
+            (FloatPrecision::SignificantDigits(3), 5e-3),
+            (FloatPrecision::Decimals(2), 5e-3),
+        ] {
+            let options = OutputOptions {
+                float_precision: Some(precision),
+                ..Default::default()
+            };
+            let out = geo.to_raw_with(options).unwrap();
+            let json = serde_json::to_string(&out).unwrap();
+            assert!(json.len() < full.len());
+
+            let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+            let parsed: RawAttribute = serde_json::from_value(json["points"]["v"].clone()).unwrap();
+            let parsed = parsed.data.float().unwrap();
+            for (v, rounded) in values.iter().zip(&parsed) {
+                if v.is_nan() {
+                    assert!(rounded.is_nan());
+                } else {
+                    let error = (v - rounded).abs();
+                    // Relative error for significant digits, absolute for decimals.
+                    let scale = match precision {
+                        FloatPrecision::SignificantDigits(_) => v.abs().max(f32::MIN_POSITIVE),
+                        FloatPrecision::Decimals(_) => 1.0,
+                    };
+                    assert!(error / scale <= tolerance, "{v} rounded to {rounded}");
+                }
+            }
+        }
+
+        assert_eq!(
+            FloatPrecision::SignificantDigits(3).round(-123456.7),
+            -123000.0
+        );
+        assert_eq!(
+            FloatPrecision::SignificantDigits(3).round(0.000123456),
+            0.000123
+        );
+        assert_eq!(FloatPrecision::Decimals(2).round(1.2345678), 1.23);
+        assert_eq!(FloatPrecision::Decimals(2).round(2.5e-8), 0.0);
+    }
+
+    #[test]
+    fn rename_output_attributes() {
+        let geo: Geometry<GeoPoint> = vec![GeoPoint {
+            position: Vec3::ZERO,
+            name: "a".to_string(),
+        }]
+        .into();
+
+        let mut options = OutputOptions::default();
+        options.renames.points.insert("name", "label".to_string());
+        let out = geo.to_raw_with(options.clone()).unwrap();
+        assert!(out.points.contains_key("label"));
+        assert!(!out.points.contains_key("name"));
+        assert!(matches!(out.points.keys().next(), Some(Cow::Borrowed("P"))));
+        let json = serde_json::to_string(&out).unwrap();
+        assert!(json.starts_with(r#"{"points":{"P":{"#));
+        assert!(json.contains(r#""label":{"tuple_size":1,"data":{"string":["a"]}}"#));
+
+        // Swapping names is fine, renaming onto an existing attribute is not.
+        options.renames.points.insert("P", "name".to_string());
+        let out = geo.to_raw_with(options.clone()).unwrap();
+        assert_eq!(out.points["name"].tuple_size, 3);
+
+        options.renames.points.remove("name");
+        let err = geo.to_raw_with(options).unwrap_err();
+        assert!(matches!(err, Error::AttrNameCollision(name) if name == "name"));
+    }
+
+    #[test]
+    fn missing_sections() {
+        const INPUT: &str = r#"
+        [
+            {
+                "points": {
+                    "P": { "tuple_size": 3, "data": { "float": [0.0, 1.0, 2.0] } },
+                    "name": { "tuple_size": 1, "data": { "string": ["a"] } }
+                },
+                "prims": null
+            }
+        ]
+        "#;
+
+        let raw = load_raw_from_reader(INPUT.as_bytes()).unwrap();
+        assert!(raw[0].vertices.is_empty());
+        assert!(raw[0].prims.is_empty());
+        assert!(raw[0].detail.is_empty());
+
+        let projected =
+            load_raw_projected(INPUT.as_bytes(), &[Geometry::<GeoPoint>::projection()]).unwrap();
+        assert_eq!(projected, raw);
+
+        let geo: Geometry<GeoPoint> = load_from_raw(raw.into_iter().next().unwrap(), 0).unwrap();
+        assert_eq!(geo.points.len(), 1);
+        assert!(geo.vertices.is_empty() && geo.prims.is_empty());
+
+        // A detail type with fields still needs its attributes.
+        let raw = load_raw_from_reader(INPUT.as_bytes()).unwrap();
+        let err = load_from_raw::<Geometry<GeoPoint, (), (), GeoDetail>>(
+            raw.into_iter().next().unwrap(),
+            0,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::MissingAttr {
+                attr: "some_detail",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn map_detail() {
+        let geo: Geometry<GeoPoint> = vec![GeoPoint {
+            position: Vec3::ZERO,
+            name: "a".to_string(),
+        }]
+        .into();
+        let point_count = geo.points.len();
+        let geo = geo.map_detail(|()| GeoDetail {
+            some_detail: format!("{point_count} points"),
+        });
+        assert_eq!(geo.detail.some_detail, "1 points");
+
+        let out = geo.into_raw().unwrap();
+        assert_eq!(
+            out.detail["some_detail"].data,
+            RawAttributeData::String(vec!["1 points".to_string()])
+        );
+    }
+
+    #[test]
+    fn with_capacity() {
+        let mut geo: Geometry<GeoPoint, (), (), ()> = Geometry::with_capacity(100, 200, 50);
+        assert!(geo.points.capacity() >= 100);
+        assert!(geo.vertices.capacity() >= 200);
+        assert!(geo.prims.capacity() >= 50);
+
+        geo.push_point(GeoPoint {
+            position: Vec3::ZERO,
+            name: "a".to_string(),
+        });
+        let capacity = geo.points.capacity();
+        let mapped = geo.map_points(|point| point.position);
+        assert_eq!(mapped.points, vec![Vec3::ZERO]);
+        assert_eq!(mapped.points.capacity(), capacity);
+        assert!(mapped.vertices.capacity() >= 200);
+    }
+
+    #[test]
+    fn empty_geometry() {
+        let geo: Geometry<GeoPoint> = Vec::new().into();
+        let output = geo.to_raw().unwrap();
+        assert_eq!(output, RawGeometryOutput::default());
+        assert_eq!(
+            serde_json::to_string(&output).unwrap(),
+            r#"{"points":{},"vertices":{},"prims":{},"detail":{}}"#
+        );
+
+        let s = generate_for_testing(geo.clone()).unwrap();
+        let read = load_from_reader::<Geometry<GeoPoint>>(s.as_bytes(), 0).unwrap();
+        assert_eq!(read, geo);
+    }
+
+    #[test]
+    fn inconsistent_out_attr() {
+        let mut geo: Geometry<GeoPoint> = Vec::new().into();
+        geo.set_point_attr("weight", generate_to_attr(Vec::<f32>::new()))
+            .unwrap();
+        assert!(geo.to_raw().unwrap().points.is_empty());
+
+        // Unlike `push_point`, this keeps the now outdated point attribute.
+        geo.points.push(GeoPoint {
+            position: Vec3::ZERO,
+            name: "a".to_string(),
+        });
+        let err = geo.to_raw().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InconsistentOutAttr {
+                entity: EntityKind::Point,
+                ref attr,
+                expected: 1,
+                actual: 0,
+            } if attr == "weight"
+        ));
+        assert!(geo.check_serializable().is_err());
+        assert!(geo.write_streamed(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn attribute_type_introspection() {
+        let cases = [
+            (
+                AttributeType::Float,
+                3,
+                ElementKind::Float,
+                Some("glam::Vec3"),
+            ),
+            (
+                AttributeType::Float,
+                16,
+                ElementKind::Float,
+                Some("glam::Mat4"),
+            ),
+            (AttributeType::Float, 5, ElementKind::Float, None),
+            (AttributeType::Int64, 1, ElementKind::Int, Some("i64")),
+            (
+                AttributeType::IndexedString,
+                1,
+                ElementKind::String,
+                Some("String"),
+            ),
+            (
+                AttributeType::PrimVertex,
+                1,
+                ElementKind::Index,
+                Some("Vec<usize>"),
+            ),
+        ];
+        for (kind, tuple_size, element_kind, rust_type) in cases {
+            assert_eq!(kind.element_kind(), element_kind);
+            assert_eq!(kind.expected_rust_type(tuple_size), rust_type);
+        }
+
+        assert_eq!(AttributeType::Float.value_size(), Some(4));
+        assert_eq!(AttributeType::Float64.value_size(), Some(8));
+        assert_eq!(AttributeType::StringArray.value_size(), None);
+        assert!(AttributeType::IntArray.is_array());
+        assert!(!AttributeType::Int.is_array());
+    }
+
+    #[test]
+    fn pretty_output() {
+        let geo: Geometry<GeoPoint> = vec![GeoPoint {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            name: "a".to_string(),
+        }]
+        .into();
+
+        let mut bytes = Vec::new();
+        generate_pretty_to_writer(&mut bytes, geo.clone()).unwrap();
+        let pretty = String::from_utf8(bytes).unwrap();
+        assert!(pretty.lines().count() > 1);
+
+        let raw: RawGeometry = serde_json::from_str(&pretty).unwrap();
+        let read: Geometry<GeoPoint> = load_from_raw(raw, 0).unwrap();
+        assert_eq!(read, geo);
+    }
+
+    #[test]
+    fn raw_passthrough() {
+        let raw = RawGeometry::fixture()
+            .point_attr("P", vec![Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::Z])
+            .polygons(vec![vec![2, 1, 0], vec![1, 2, 3]])
+            .prim_attr("name", vec!["a".to_string(), "b".to_string()])
+            .detail_attr("frame", 12)
+            .build();
+        let points = raw.points["P"].clone();
+
+        let output = raw.into_raw().unwrap();
+        assert_eq!(output.points["P"], points);
+        assert_eq!(
+            output.prims["points"].data,
+            RawAttributeData::PrimVertex(vec![[2, 1, 0][..].into(), [1, 2, 3][..].into()])
+        );
+        assert!(!output.prims.contains_key("vertices"));
+        assert_eq!(output.detail["frame"].data, RawAttributeData::Int(vec![12]));
+    }
+
+    #[test]
+    fn raw_passthrough_with_options() {
+        let raw = || {
+            RawGeometry::fixture()
+                .point_attr("P", vec![Vec3::splat(0.123), Vec3::X, Vec3::Y])
+                .polygons(vec![vec![0, 1, 2]])
+                .prim_attr("name", vec!["a".to_string()])
+                .build()
+        };
+        let mut options = OutputOptions {
+            float_precision: Some(FloatPrecision::Decimals(1)),
+            ..Default::default()
+        };
+        options.renames.prims.insert("name", "label".to_string());
+
+        let output = raw().into_raw_with(options.clone()).unwrap();
+        assert_eq!(
+            output.points["P"].data.clone().float().unwrap()[..3],
+            [0.1; 3]
+        );
+        assert!(output.prims.contains_key("label"));
+        assert!(!output.prims.contains_key("name"));
+
+        options.renames.prims.insert("name", "points".to_string());
+        let err = raw().into_raw_with(options).unwrap_err();
+        assert!(matches!(err, Error::AttrNameCollision(name) if name == "points"));
+
+        // Without `ptnum`, the vertices are only taken as points on request.
+        let without_ptnum = || {
+            let mut raw = raw();
+            raw.vertices.clear();
+            raw.vertices.insert(
+                "uv".to_string(),
+                generate_to_attr(vec![glam::Vec2::ZERO; 3]),
+            );
+            raw
+        };
+        let err = without_ptnum().into_raw().unwrap_err();
+        assert!(matches!(err, Error::MissingOutVertexPtnums));
+        let options = OutputOptions {
+            synthesize_ptnum: true,
+            ..Default::default()
+        };
+        let output = without_ptnum().into_raw_with(options).unwrap();
+        assert_eq!(
+            output.prims["points"].data,
+            RawAttributeData::PrimVertex(vec![[0, 1, 2][..].into()])
+        );
+    }
 }
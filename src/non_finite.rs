@@ -0,0 +1,194 @@
+//! Lossless transport of non-finite floats, both `f32` and `f64`.
+//!
+//! JSON has no representation for NaN and infinities (`serde_json` writes them as `null`), so they
+//! are transported as strings instead: `"NaN"`, `"inf"` and `"-inf"`. On input, any string that
+//! parses as a float is accepted, which also covers spellings like `"nan"` and `"Infinity"`.
+
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// `serde(with)` module for `Vec<f32>`.
+pub(crate) mod floats {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(v: &[f32], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(v.iter().copied().map(TransportFloat))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<f32>, D::Error> {
+        deserializer.deserialize_seq(SeqVisitor::<TransportFloat<f32>>(PhantomData))
+    }
+}
+
+/// `serde(with)` module for `Vec<f64>`.
+pub(crate) mod doubles {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(v: &[f64], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(v.iter().copied().map(TransportFloat))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<f64>, D::Error> {
+        deserializer.deserialize_seq(SeqVisitor::<TransportFloat<f64>>(PhantomData))
+    }
+}
+
+/// `serde(with)` module for `Vec<Vec<f32>>`.
+pub(crate) mod float_arrays {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        v: &[Vec<f32>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(v.iter().map(|v| FloatsRef(v)))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<f32>>, D::Error> {
+        deserializer.deserialize_seq(SeqVisitor::<TransportFloats>(PhantomData))
+    }
+}
+
+/// The float types that can be transported, `f32` and `f64`.
+trait Float: Copy + Display + FromStr {
+    fn is_finite(self) -> bool;
+    fn from_f64(v: f64) -> Self;
+    fn serialize<S: Serializer>(self, serializer: S) -> Result<S::Ok, S::Error>;
+}
+
+impl Float for f32 {
+    fn is_finite(self) -> bool {
+        self.is_finite()
+    }
+
+    fn from_f64(v: f64) -> Self {
+        v as f32
+    }
+
+    fn serialize<S: Serializer>(self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f32(self)
+    }
+}
+
+impl Float for f64 {
+    fn is_finite(self) -> bool {
+        self.is_finite()
+    }
+
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+
+    fn serialize<S: Serializer>(self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self)
+    }
+}
+
+struct TransportFloat<F>(F);
+
+impl<F: Float> Serialize for TransportFloat<F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_finite() {
+            self.0.serialize(serializer)
+        } else {
+            serializer.collect_str(&self.0)
+        }
+    }
+}
+
+impl<'de, F: Float> Deserialize<'de> for TransportFloat<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(TransportFloatVisitor(PhantomData))
+    }
+}
+
+struct TransportFloatVisitor<F>(PhantomData<F>);
+
+impl<F: Float> Visitor<'_> for TransportFloatVisitor<F> {
+    type Value = TransportFloat<F>;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "a number or a non-finite float as string")
+    }
+
+    fn visit_f64<E: Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(TransportFloat(F::from_f64(v)))
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(TransportFloat(F::from_f64(v as f64)))
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(TransportFloat(F::from_f64(v as f64)))
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse()
+            .map(TransportFloat)
+            .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+    }
+}
+
+struct FloatsRef<'a>(&'a [f32]);
+
+impl Serialize for FloatsRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        floats::serialize(self.0, serializer)
+    }
+}
+
+struct TransportFloats(Vec<f32>);
+
+impl<'de> Deserialize<'de> for TransportFloats {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        floats::deserialize(deserializer).map(TransportFloats)
+    }
+}
+
+/// Collects a sequence of transport wrappers into their inner values.
+struct SeqVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de> + Unwrap> Visitor<'de> for SeqVisitor<T> {
+    type Value = Vec<T::Inner>;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "a list")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(v) = seq.next_element::<T>()? {
+            values.push(v.unwrap());
+        }
+        Ok(values)
+    }
+}
+
+trait Unwrap {
+    type Inner;
+    fn unwrap(self) -> Self::Inner;
+}
+
+impl<F> Unwrap for TransportFloat<F> {
+    type Inner = F;
+    fn unwrap(self) -> F {
+        self.0
+    }
+}
+
+impl Unwrap for TransportFloats {
+    type Inner = Vec<f32>;
+    fn unwrap(self) -> Vec<f32> {
+        self.0
+    }
+}
@@ -0,0 +1,261 @@
+//! Parsing only the attributes that are actually used.
+//!
+//! Attributes outside of the projection are skipped by the parser instead of being materialized,
+//! which avoids allocating their data altogether.
+
+use crate::{InAttrs, RawAttribute, RawGeometry};
+use serde::Deserializer;
+use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use std::collections::HashMap;
+use std::fmt::Formatter;
+use std::io::Read;
+
+/// The attribute names to keep per entity. `None` keeps all attributes of that entity.
+///
+/// Usually created via [`crate::FromRawGeometry::projection`].
+#[derive(Debug, Clone, Default)]
+pub struct Projection {
+    pub points: Option<Vec<&'static str>>,
+    pub vertices: Option<Vec<&'static str>>,
+    pub prims: Option<Vec<&'static str>>,
+    pub detail: Option<Vec<&'static str>>,
+}
+
+impl Projection {
+    /// Extracts the names from an attribute listing like [`crate::InAttrs::IN_ATTRS`].
+    pub fn names(attrs: &[(&'static str, usize)]) -> Vec<&'static str> {
+        attrs.iter().map(|(name, _)| *name).collect()
+    }
+
+    /// The names read by `T` and `extra`, `None` if `T` doesn't list its attributes.
+    pub(crate) fn of<T: InAttrs>(
+        extra: impl IntoIterator<Item = &'static str>,
+    ) -> Option<Vec<&'static str>> {
+        let attrs = crate::in_attrs::<T>()?;
+        Some(Self::names(attrs).into_iter().chain(extra).collect())
+    }
+}
+
+/// Like [`crate::load_raw_from_stdin`], but only parses the attributes in the projection of the
+/// respective input. Inputs without a projection are parsed in full.
+///
+/// Note that attributes which are not read by the typed geometry are dropped, so an entity whose
+/// fields are all optional and missing will load as empty.
+pub fn load_raw_projected(
+    reader: impl Read,
+    projections: &[Projection],
+) -> crate::Result<Vec<RawGeometry>> {
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    GeometriesSeed(projections)
+        .deserialize(&mut de)
+        .map_err(crate::input_error)
+}
+
+struct GeometriesSeed<'a>(&'a [Projection]);
+
+impl<'de> DeserializeSeed<'de> for GeometriesSeed<'_> {
+    type Value = Vec<RawGeometry>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for GeometriesSeed<'_> {
+    type Value = Vec<RawGeometry>;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "a list of geometries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut geometries = Vec::new();
+        while let Some(geo) = seq.next_element_seed(GeometrySeed(
+            self.0.get(geometries.len()).cloned().unwrap_or_default(),
+        ))? {
+            geometries.push(geo);
+        }
+        Ok(geometries)
+    }
+}
+
+struct GeometrySeed(Projection);
+
+impl<'de> DeserializeSeed<'de> for GeometrySeed {
+    type Value = RawGeometry;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for GeometrySeed {
+    type Value = RawGeometry;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "a geometry")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut points = None;
+        let mut vertices = None;
+        let mut prims = None;
+        let mut detail = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            let (slot, names) = match key.as_str() {
+                "points" => (&mut points, &self.0.points),
+                "vertices" => (&mut vertices, &self.0.vertices),
+                "prims" => (&mut prims, &self.0.prims),
+                "detail" => (&mut detail, &self.0.detail),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                    continue;
+                }
+            };
+            *slot = Some(map.next_value_seed(AttributesSeed(names.as_deref()))?);
+        }
+
+        // Missing sections are empty, like in the `Deserialize` implementation of `RawGeometry`.
+        Ok(RawGeometry {
+            points: points.unwrap_or_default(),
+            vertices: vertices.unwrap_or_default(),
+            prims: prims.unwrap_or_default(),
+            detail: detail.unwrap_or_default(),
+        })
+    }
+}
+
+struct AttributesSeed<'a>(Option<&'a [&'static str]>);
+
+impl<'de> DeserializeSeed<'de> for AttributesSeed<'_> {
+    type Value = HashMap<String, RawAttribute>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_option(self)
+    }
+}
+
+impl<'de> Visitor<'de> for AttributesSeed<'_> {
+    type Value = HashMap<String, RawAttribute>;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "a map of attributes")
+    }
+
+    /// A `null` section is empty.
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(HashMap::new())
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut attrs = HashMap::new();
+        while let Some(name) = map.next_key::<String>()? {
+            match self.0 {
+                Some(names) if !names.contains(&name.as_str()) => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+                _ => {
+                    attrs.insert(name, map.next_value()?);
+                }
+            }
+        }
+        Ok(attrs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{FromRawGeometry, Geometry};
+    use glam::Vec3;
+    use houdini_node_macro::InAttrs;
+
+    #[derive(PartialEq, Debug, InAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Vec3,
+    }
+
+    const INPUT: &str = r#"
+    [
+        {
+            "points": {
+                "P": { "tuple_size": 3, "data": { "float": [0.0, 1.0, 2.0] } },
+                "name": { "tuple_size": 1, "data": { "string": ["a"] } }
+            },
+            "vertices": {},
+            "prims": {},
+            "detail": {
+                "info": { "tuple_size": 1, "data": { "string": ["hello"] } }
+            }
+        },
+        {
+            "points": {
+                "name": { "tuple_size": 1, "data": { "string": ["b"] } }
+            },
+            "vertices": {},
+            "prims": {},
+            "detail": {}
+        }
+    ]
+    "#;
+
+    #[test]
+    fn skips_unused_attributes() {
+        let raw = load_raw_projected(INPUT.as_bytes(), &[Geometry::<Point>::projection()]).unwrap();
+        assert_eq!(raw.len(), 2);
+
+        let [first, second] = <[RawGeometry; 2]>::try_from(raw).unwrap();
+        assert!(first.points.contains_key("P"));
+        assert!(!first.points.contains_key("name"));
+        assert!(first.detail.is_empty());
+
+        // No projection for the second input, so everything is kept.
+        assert!(second.points.contains_key("name"));
+
+        let geo = Geometry::<Point>::from_raw(first, 0).unwrap();
+        assert_eq!(geo.points[0].position, Vec3::new(0.0, 1.0, 2.0));
+    }
+
+    #[test]
+    fn unlisted_attributes_are_kept() {
+        /// Reads whatever it is given, so it can't list its attributes.
+        struct AttrCount(usize);
+
+        impl InAttrs for AttrCount {
+            fn from_attr(
+                attrs: HashMap<String, RawAttribute>,
+                _err_context: crate::ErrContext,
+            ) -> crate::Result<impl Iterator<Item = Self>> {
+                Ok(std::iter::once(AttrCount(attrs.len())))
+            }
+        }
+
+        let projection = Geometry::<AttrCount>::projection();
+        assert!(projection.points.is_none());
+        assert_eq!(projection.detail, Some(Vec::new()));
+
+        let raw = load_raw_projected(INPUT.as_bytes(), &[projection]).unwrap();
+        let geo = Geometry::<AttrCount>::from_raw(raw.into_iter().next().unwrap(), 0).unwrap();
+        assert_eq!(geo.points[0].0, 2);
+    }
+    #[test]
+    fn truncated_input() {
+        let projections = [Geometry::<Point>::projection()];
+        let truncated = &INPUT[..INPUT.find("1.0").unwrap()];
+        let err = load_raw_projected(truncated.as_bytes(), &projections).unwrap_err();
+        assert!(matches!(err, crate::Error::TruncatedInput { line: 5, .. }));
+
+        // Reading stops at the end of the input, as stdin stays open while the node runs.
+        let input = format!("{INPUT} not read");
+        let raw = load_raw_projected(input.as_bytes(), &projections).unwrap();
+        assert_eq!(raw.len(), 2);
+    }
+}
@@ -0,0 +1,286 @@
+//! Values spread over several attributes, like the ramps of Houdini.
+
+use crate::{ErrContext, Error, RawAttribute, RawAttributeData, Result};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A value that is stored in a group of attributes named `{name}{suffix}`, one per suffix, with
+/// one element per value.
+pub trait AttrGroup: Sized {
+    const SUFFIXES: &'static [&'static str];
+
+    /// Gets the attributes in the order of [`AttrGroup::SUFFIXES`]. `name` is the base name of the
+    /// group, for errors.
+    fn from_attrs(attrs: Vec<RawAttribute>, name: &'static str) -> Result<Vec<Self>>;
+
+    /// Returns the attributes in the order of [`AttrGroup::SUFFIXES`].
+    fn into_attrs(values: Vec<Self>) -> Vec<RawAttribute>;
+}
+
+/// Removes the attributes of the group `name` from `attrs` and reads them.
+pub fn load_attr_group<T: AttrGroup>(
+    attrs: &mut HashMap<String, RawAttribute>,
+    name: &'static str,
+    err_context: ErrContext,
+) -> Result<Vec<T>> {
+    let group = T::SUFFIXES
+        .iter()
+        .map(|suffix| {
+            attrs
+                .remove(&format!("{name}{suffix}"))
+                .ok_or(Error::MissingAttr {
+                    input_index: err_context.input_index,
+                    entity: err_context.entity,
+                    attr: name,
+                })
+        })
+        .collect::<Result<_>>()?;
+    T::from_attrs(group, name)
+}
+
+/// The attributes of the group `name`, e.g. for [`crate::RawGeometryOutput::insert_dynamic`].
+pub fn generate_attr_group<T: AttrGroup>(
+    name: &str,
+    values: Vec<T>,
+) -> Vec<(String, RawAttribute)> {
+    T::SUFFIXES
+        .iter()
+        .map(|suffix| format!("{name}{suffix}"))
+        .zip(T::into_attrs(values))
+        .collect()
+}
+
+/// The interpolation from a ramp key to the next one, as named by the `ramp_basis` of Houdini.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RampBasis {
+    Constant,
+    Linear,
+    CatmullRom,
+    MonotoneCubic,
+    Bezier,
+    BSpline,
+    Hermite,
+}
+
+impl Display for RampBasis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RampBasis::Constant => write!(f, "constant"),
+            RampBasis::Linear => write!(f, "linear"),
+            RampBasis::CatmullRom => write!(f, "catmull-rom"),
+            RampBasis::MonotoneCubic => write!(f, "monotonecubic"),
+            RampBasis::Bezier => write!(f, "bezier"),
+            RampBasis::BSpline => write!(f, "bspline"),
+            RampBasis::Hermite => write!(f, "hermite"),
+        }
+    }
+}
+
+impl FromStr for RampBasis {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        Ok(match s {
+            "constant" => RampBasis::Constant,
+            "linear" => RampBasis::Linear,
+            "catmull-rom" => RampBasis::CatmullRom,
+            "monotonecubic" => RampBasis::MonotoneCubic,
+            "bezier" => RampBasis::Bezier,
+            "bspline" => RampBasis::BSpline,
+            "hermite" => RampBasis::Hermite,
+            _ => return Err(()),
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RampKey {
+    pub position: f32,
+    pub value: f32,
+    pub basis: RampBasis,
+}
+
+/// A float ramp, stored in the array attributes `{name}_pos`, `{name}_value` and
+/// `{name}_interp`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ramp {
+    pub keys: Vec<RampKey>,
+}
+
+impl AttrGroup for Ramp {
+    const SUFFIXES: &'static [&'static str] = &["_pos", "_value", "_interp"];
+
+    fn from_attrs(attrs: Vec<RawAttribute>, name: &'static str) -> Result<Vec<Self>> {
+        let [positions, values, bases] = <[RawAttribute; 3]>::try_from(attrs)
+            .expect("one attribute per suffix")
+            .map(|attr| attr.data);
+        let (positions, values, bases) = (
+            positions.float_array()?,
+            values.float_array()?,
+            bases.string_array()?,
+        );
+        if values.len() != positions.len() || bases.len() != positions.len() {
+            return Err(Error::InvalidAttributeLength {
+                expected: positions.len(),
+                actual: values.len().min(bases.len()),
+            });
+        }
+
+        positions
+            .into_iter()
+            .zip(values)
+            .zip(bases)
+            .map(|((positions, values), bases)| {
+                if values.len() != positions.len() || bases.len() != positions.len() {
+                    return Err(Error::InvalidAttributeLength {
+                        expected: positions.len(),
+                        actual: values.len().min(bases.len()),
+                    });
+                }
+                let keys = positions
+                    .into_iter()
+                    .zip(values)
+                    .zip(bases)
+                    .map(|((position, value), basis)| {
+                        Ok(RampKey {
+                            position,
+                            value,
+                            basis: basis.parse().map_err(|_| Error::InvalidAttributeString {
+                                attr: name,
+                                value: basis,
+                            })?,
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(Ramp { keys })
+            })
+            .collect()
+    }
+
+    fn into_attrs(values: Vec<Self>) -> Vec<RawAttribute> {
+        let array = |data| RawAttribute {
+            tuple_size: 1,
+            data,
+            meta: None,
+        };
+        let column = |f: fn(&RampKey) -> f32| {
+            array(RawAttributeData::FloatArray(
+                values
+                    .iter()
+                    .map(|ramp| ramp.keys.iter().map(f).collect())
+                    .collect(),
+            ))
+        };
+        vec![
+            column(|key| key.position),
+            column(|key| key.value),
+            array(RawAttributeData::StringArray(
+                values
+                    .iter()
+                    .map(|ramp| ramp.keys.iter().map(|key| key.basis.to_string()).collect())
+                    .collect(),
+            )),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EntityKind, RawGeometryOutput};
+
+    const CONTEXT: ErrContext = ErrContext {
+        input_index: 0,
+        entity: EntityKind::Detail,
+    };
+
+    #[test]
+    fn ramp_round_trip() {
+        let ramp = Ramp {
+            keys: vec![
+                RampKey {
+                    position: 0.0,
+                    value: 0.0,
+                    basis: RampBasis::Linear,
+                },
+                RampKey {
+                    position: 0.5,
+                    value: 1.0,
+                    basis: RampBasis::CatmullRom,
+                },
+                RampKey {
+                    position: 1.0,
+                    value: 0.25,
+                    basis: RampBasis::Constant,
+                },
+            ],
+        };
+
+        let mut output = RawGeometryOutput::default();
+        output
+            .insert_dynamic(
+                EntityKind::Detail,
+                generate_attr_group("falloff", vec![ramp.clone()]),
+                1,
+            )
+            .unwrap();
+        assert_eq!(
+            output.detail.keys().collect::<Vec<_>>(),
+            vec!["falloff_interp", "falloff_pos", "falloff_value"]
+        );
+
+        let mut detail: HashMap<String, RawAttribute> = output
+            .detail
+            .into_iter()
+            .map(|(name, attr)| (name.into_owned(), attr))
+            .collect();
+        let read: Vec<Ramp> = load_attr_group(&mut detail, "falloff", CONTEXT).unwrap();
+        assert_eq!(read, vec![ramp]);
+        assert!(detail.is_empty());
+    }
+
+    #[test]
+    fn invalid_ramp() {
+        let mut detail = HashMap::from([
+            (
+                "falloff_pos".to_string(),
+                RawAttribute {
+                    tuple_size: 1,
+                    data: RawAttributeData::FloatArray(vec![vec![0.0]]),
+                    meta: None,
+                },
+            ),
+            (
+                "falloff_value".to_string(),
+                RawAttribute {
+                    tuple_size: 1,
+                    data: RawAttributeData::FloatArray(vec![vec![1.0]]),
+                    meta: None,
+                },
+            ),
+            (
+                "falloff_interp".to_string(),
+                RawAttribute {
+                    tuple_size: 1,
+                    data: RawAttributeData::StringArray(vec![vec!["cubic".to_string()]]),
+                    meta: None,
+                },
+            ),
+        ]);
+        let err = load_attr_group::<Ramp>(&mut detail.clone(), "falloff", CONTEXT).unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidAttributeString { attr: "falloff", value } if value == "cubic")
+        );
+
+        detail.remove("falloff_value");
+        let err = load_attr_group::<Ramp>(&mut detail, "falloff", CONTEXT).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::MissingAttr {
+                attr: "falloff",
+                ..
+            }
+        ));
+    }
+}
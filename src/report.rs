@@ -0,0 +1,96 @@
+//! Messages from the node to Houdini, sent over stderr.
+//!
+//! Every message is a single line starting with a prefix that tells the Houdini side how to route
+//! it:
+//!
+//! - `ERR {"message": "..."}`: the cook failed. Sent at most once, right before exiting.
+//! - `WARN {"message": "..."}`: shown as a node warning, the output geometry is still used.
+//! - `PROG <percent>`: progress between 0 and 100.
+//!
+//! Lines without a known prefix (like panic messages or plain `eprintln!` output) are treated as
+//! errors, as that's how all of stderr used to be handled.
+
+use crate::Error;
+use std::fmt::Display;
+use std::io::Write;
+
+pub const ERROR_PREFIX: &str = "ERR";
+pub const WARNING_PREFIX: &str = "WARN";
+pub const PROGRESS_PREFIX: &str = "PROG";
+
+/// Reports a failed cook. Called by the generated `main`, nodes return their errors instead.
+pub fn error(err: &Error) {
+    emit(&error_line(err));
+}
+
+/// Reports a warning to Houdini without failing the cook.
+pub fn warn(message: impl Display) {
+    emit(&warning_line(&message.to_string()));
+}
+
+/// Reports progress, `fraction` is clamped to `0.0..=1.0`.
+pub fn progress(fraction: f32) {
+    emit(&progress_line(fraction));
+}
+
+fn error_line(err: &Error) -> String {
+    message_line(ERROR_PREFIX, &err.to_string())
+}
+
+fn warning_line(message: &str) -> String {
+    message_line(WARNING_PREFIX, message)
+}
+
+fn progress_line(fraction: f32) -> String {
+    let percent = if fraction.is_nan() {
+        0.0
+    } else {
+        fraction.clamp(0.0, 1.0) * 100.0
+    };
+    format!("{PROGRESS_PREFIX} {percent:.0}")
+}
+
+/// JSON-encoding the message keeps it on a single line.
+fn message_line(prefix: &str, message: &str) -> String {
+    format!("{prefix} {}", serde_json::json!({ "message": message }))
+}
+
+fn emit(line: &str) {
+    // A closed stderr leaves nowhere to report to, so write failures are ignored.
+    let _ = writeln!(std::io::stderr().lock(), "{line}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload<'a>(line: &'a str, prefix: &str) -> &'a str {
+        line.strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix(' '))
+            .unwrap()
+    }
+
+    #[test]
+    fn error_line_is_single_line_json() {
+        let line = error_line(&Error::UserError("bad\nthings \"happened\"".to_string()));
+        assert!(!line.contains('\n'));
+
+        let json: serde_json::Value = serde_json::from_str(payload(&line, ERROR_PREFIX)).unwrap();
+        assert_eq!(json["message"], "bad\nthings \"happened\"");
+    }
+
+    #[test]
+    fn warning_line_is_json() {
+        let line = warning_line("few points");
+        let json: serde_json::Value = serde_json::from_str(payload(&line, WARNING_PREFIX)).unwrap();
+        assert_eq!(json["message"], "few points");
+    }
+
+    #[test]
+    fn progress_percent() {
+        assert_eq!(progress_line(0.426), "PROG 43");
+        assert_eq!(progress_line(2.0), "PROG 100");
+        assert_eq!(progress_line(-1.0), "PROG 0");
+        assert_eq!(progress_line(f32::NAN), "PROG 0");
+    }
+}
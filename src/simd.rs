@@ -0,0 +1,157 @@
+//! Parsing the input with `simd-json`. Requires the `simd` feature.
+//!
+//! `simd-json` parses in place, so the input is first read into a buffer. Since the bridge keeps
+//! stdin open while the node runs (see [`crate::cancel`]), the buffer can't be filled by reading
+//! to the end. Instead, the input is scanned for the end of the JSON value, like `serde_json` does
+//! when reading from a stream.
+
+use crate::{Error, RawGeometry, Result};
+use std::io::BufRead;
+
+/// Like the `serde_json` path of [`crate::load_raw_from_reader`]: reads up to the end of the JSON
+/// value, leaving anything after it in `reader`.
+pub(crate) fn load_raw_value(mut reader: impl BufRead) -> Result<Vec<RawGeometry>> {
+    let mut bytes = read_value(&mut reader)?;
+    simd_json::serde::from_slice(&mut bytes)
+        .map_err(|e| Error::Json(serde::de::Error::custom(e.to_string())))
+}
+
+/// Reads the bytes of the JSON value at the start of `reader`. Only looks at the structure, the
+/// value itself is validated by the parser.
+fn read_value(reader: &mut impl BufRead) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut scanner = Scanner::default();
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Err(truncated(&bytes));
+        }
+        let end = scanner.find_end(buf);
+        let len = end.unwrap_or(buf.len());
+        bytes.extend_from_slice(&buf[..len]);
+        reader.consume(len);
+        if end.is_some() {
+            return Ok(bytes);
+        }
+    }
+}
+
+/// [`Error::TruncatedInput`] at the end of `bytes`, with the line and column counted from 1.
+fn truncated(bytes: &[u8]) -> Error {
+    let line_start = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    Error::TruncatedInput {
+        line: bytes.iter().filter(|&&b| b == b'\n').count() + 1,
+        column: bytes.len() - line_start,
+    }
+}
+
+/// Tracks the nesting of a JSON value across the chunks of the input.
+#[derive(Default)]
+struct Scanner {
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl Scanner {
+    /// The length of the part of `buf` that completes the value, `None` if it continues.
+    fn find_end(&mut self, buf: &[u8]) -> Option<usize> {
+        let mut i = 0;
+        while i < buf.len() {
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                    i += 1;
+                    continue;
+                }
+                i += buf[i..].iter().position(|&b| b == b'"' || b == b'\\')?;
+                match buf[i] {
+                    b'\\' => self.escaped = true,
+                    _ => self.in_string = false,
+                }
+                i += 1;
+                continue;
+            }
+            // Inside the value, only the structural bytes matter. Skipping to them is what keeps
+            // the scan cheap compared to the parser.
+            if self.depth > 0 {
+                i += buf[i..].iter().position(|&b| STRUCTURAL[b as usize])?;
+            }
+            let b = buf[i];
+            i += 1;
+            match b {
+                b'"' => self.in_string = true,
+                b'[' | b'{' => self.depth += 1,
+                b']' | b'}' if self.depth > 0 => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        return Some(i);
+                    }
+                }
+                b' ' | b'\t' | b'\n' | b'\r' => {}
+                // Anything else at the top level is not a list of geometries, which the parser
+                // reports.
+                _ if self.depth == 0 => return Some(i),
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+/// The bytes that start or end a string, list or object.
+const STRUCTURAL: [bool; 256] = {
+    let mut table = [false; 256];
+    let mut i = 0;
+    while i < 5 {
+        table[b"\"[]{}"[i] as usize] = true;
+        i += 1;
+    }
+    table
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = r#"[
+        {
+            "points": {
+                "P": { "tuple_size": 3, "data": { "float": [0.1, 1e-7, 3.4028235e38] } },
+                "name": { "tuple_size": 1, "data": { "string": ["a \"quoted\" ]}", "\\"] } },
+                "d": { "tuple_size": 1, "data": { "float64": [0.30000000000000004] } }
+            },
+            "vertices": { "ptnum": { "tuple_size": 1, "data": { "index": [0] } } },
+            "prims": { "vertices": { "tuple_size": 1, "data": { "prim_vertex": [[0]] } } },
+            "detail": { "tags": { "tuple_size": 1, "data": { "string_array": [["x", "y"]] } } }
+        },
+        { "points": null, "detail": {} }
+    ]"#;
+
+    #[test]
+    fn same_as_serde_json() {
+        let expected: Vec<RawGeometry> = serde_json::from_str(INPUT).unwrap();
+        let mut reader = std::io::BufReader::with_capacity(7, INPUT.as_bytes());
+        assert_eq!(load_raw_value(&mut reader).unwrap(), expected);
+    }
+
+    #[test]
+    fn stops_at_end_of_value() {
+        let input = format!("{INPUT}\n[]");
+        let mut reader = input.as_bytes();
+        load_raw_value(&mut reader).unwrap();
+        assert_eq!(reader, b"\n[]");
+    }
+
+    #[test]
+    fn truncated_and_invalid_input() {
+        let truncated = &INPUT[..INPUT.find("1e-7").unwrap()];
+        let err = load_raw_value(truncated.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::TruncatedInput { line: 4, .. }));
+
+        let err = load_raw_value(&br#"[{"points": 1}]"#[..]).unwrap_err();
+        assert!(matches!(err, Error::Json(_)));
+        let err = load_raw_value(&b"12"[..]).unwrap_err();
+        assert!(matches!(err, Error::Json(_)));
+    }
+}
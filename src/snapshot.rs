@@ -0,0 +1,87 @@
+//! Cheap snapshots of a [`Geometry`], e.g. to keep the previous frame of a solver around.
+
+use crate::Geometry;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A geometry behind an [`Arc`], so that [`SharedGeometry::checkpoint`] only copies a pointer. The
+/// geometry itself is copied on the first [`SharedGeometry::make_mut`] while a checkpoint of it is
+/// still alive, and not at all otherwise.
+#[derive(Debug)]
+pub struct SharedGeometry<Pt, Vt = (), Pr = (), Dt = ()>(Arc<Geometry<Pt, Vt, Pr, Dt>>);
+
+impl<Pt, Vt, Pr, Dt> SharedGeometry<Pt, Vt, Pr, Dt> {
+    /// A snapshot of the current state, sharing the storage until either side is modified.
+    pub fn checkpoint(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+
+    /// Whether a checkpoint shares the storage, so the next [`SharedGeometry::make_mut`] copies
+    /// the geometry.
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.0) > 1
+    }
+}
+
+impl<Pt: Clone, Vt: Clone, Pr: Clone, Dt: Clone> SharedGeometry<Pt, Vt, Pr, Dt> {
+    /// The geometry for modification, copied first if it is shared.
+    pub fn make_mut(&mut self) -> &mut Geometry<Pt, Vt, Pr, Dt> {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// The geometry, copied if it is shared.
+    pub fn into_inner(self) -> Geometry<Pt, Vt, Pr, Dt> {
+        Arc::unwrap_or_clone(self.0)
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Clone for SharedGeometry<Pt, Vt, Pr, Dt> {
+    fn clone(&self) -> Self {
+        self.checkpoint()
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Deref for SharedGeometry<Pt, Vt, Pr, Dt> {
+    type Target = Geometry<Pt, Vt, Pr, Dt>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> From<Geometry<Pt, Vt, Pr, Dt>> for SharedGeometry<Pt, Vt, Pr, Dt> {
+    fn from(geometry: Geometry<Pt, Vt, Pr, Dt>) -> Self {
+        Self(Arc::new(geometry))
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Moves the geometry behind an [`Arc`] for cheap snapshots, see [`SharedGeometry`].
+    pub fn into_shared(self) -> SharedGeometry<Pt, Vt, Pr, Dt> {
+        self.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    #[test]
+    fn checkpoint_is_independent() {
+        let mut live = Geometry::from(vec![Vec3::ZERO, Vec3::X]).into_shared();
+        assert!(!live.is_shared());
+
+        let previous = live.checkpoint();
+        assert!(live.is_shared());
+        assert!(std::ptr::eq(&live.points, &previous.points));
+
+        live.make_mut().points[1] = Vec3::Y;
+        live.make_mut().points.push(Vec3::Z);
+        assert!(!live.is_shared() && !previous.is_shared());
+        assert_eq!(live.points, vec![Vec3::ZERO, Vec3::Y, Vec3::Z]);
+        assert_eq!(previous.points, vec![Vec3::ZERO, Vec3::X]);
+
+        assert_eq!(previous.into_inner().points.len(), 2);
+    }
+}
@@ -0,0 +1,402 @@
+//! Spatial queries on the points of a [`Geometry`].
+
+use crate::Geometry;
+use glam::Vec3;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::OnceLock;
+
+/// Access to the position of a point.
+///
+/// Can be derived for structs that have a `Vec3` field marked with `#[attr(position)]`, which also
+/// derives [`PointPositionMut`].
+pub trait PointPosition {
+    fn position(&self) -> Vec3;
+}
+
+/// Modification of the position of a point, for points that store their position.
+pub trait PointPositionMut: PointPosition {
+    fn position_mut(&mut self) -> &mut Vec3;
+}
+
+impl<Pt: PointPosition, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Builds a k-d tree over the current point positions. The index is a snapshot, so it has to
+    /// be rebuilt after points are moved, added or removed.
+    pub fn build_point_index(&self) -> PointIndex {
+        PointIndex::new(self.points.iter().map(PointPosition::position).collect())
+    }
+
+    /// Like [`Geometry::build_point_index`], but builds the index only once and keeps it in
+    /// [`crate::GeometryExtra::point_cache`]. Modify the points through [`Geometry::points_mut`]
+    /// so that the index is rebuilt, changes made directly to `points` are not noticed.
+    pub fn point_index(&self) -> &PointIndex {
+        self.extra
+            .point_cache
+            .index
+            .get_or_init(|| self.build_point_index())
+    }
+
+    /// The minimum and maximum corner of the point positions, `None` if there are no points.
+    pub fn point_bbox(&self) -> Option<(Vec3, Vec3)> {
+        self.points
+            .iter()
+            .map(PointPosition::position)
+            .fold(None, |bbox, p| match bbox {
+                Some((min, max)) => Some((p.min(min), p.max(max))),
+                None => Some((p, p)),
+            })
+    }
+}
+
+/// Data derived from the points, built on first use. Cleared when the points are modified through
+/// the methods of [`Geometry`]. Always compares equal, as it doesn't hold any data of its own.
+#[derive(Debug, Clone, Default)]
+pub struct PointCache {
+    index: OnceLock<PointIndex>,
+}
+
+impl PointCache {
+    pub fn clear(&mut self) {
+        self.index = OnceLock::new();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.get().is_none()
+    }
+}
+
+impl PartialEq for PointCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// A k-d tree over point positions, see [`Geometry::build_point_index`]. Queries return point
+/// indices.
+#[derive(Debug, Clone)]
+pub struct PointIndex {
+    positions: Vec<Vec3>,
+    /// The point indices in tree order: the median of every range is the splitting node, with
+    /// the lower half of the range to its left and the upper half to its right.
+    tree: Vec<usize>,
+}
+
+impl PointIndex {
+    pub fn new(positions: Vec<Vec3>) -> Self {
+        let mut tree: Vec<_> = (0..positions.len()).collect();
+        build(&mut tree, &positions, 0);
+        Self { positions, tree }
+    }
+
+    /// All points within `radius` of `center` (inclusive), in ascending index order.
+    pub fn points_within(&self, center: Vec3, radius: f32) -> Vec<usize> {
+        let mut found = Vec::new();
+        self.within(&self.tree, 0, center, radius, &mut found);
+        found.sort_unstable();
+        found
+    }
+
+    /// The `k` points closest to `center`, nearest first.
+    pub fn nearest(&self, center: Vec3, k: usize) -> Vec<usize> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut heap = BinaryHeap::with_capacity(k.min(self.positions.len()) + 1);
+        self.nearest_in(&self.tree, 0, center, k, &mut heap);
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|candidate| candidate.index)
+            .collect()
+    }
+
+    fn within(
+        &self,
+        range: &[usize],
+        depth: usize,
+        center: Vec3,
+        radius: f32,
+        found: &mut Vec<usize>,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+
+        let mid = range.len() / 2;
+        let index = range[mid];
+        let position = self.positions[index];
+        if position.distance_squared(center) <= radius * radius {
+            found.push(index);
+        }
+
+        let axis = depth % 3;
+        if center[axis] - radius <= position[axis] {
+            self.within(&range[..mid], depth + 1, center, radius, found);
+        }
+        if center[axis] + radius >= position[axis] {
+            self.within(&range[mid + 1..], depth + 1, center, radius, found);
+        }
+    }
+
+    fn nearest_in(
+        &self,
+        range: &[usize],
+        depth: usize,
+        center: Vec3,
+        k: usize,
+        heap: &mut BinaryHeap<Candidate>,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+
+        let mid = range.len() / 2;
+        let index = range[mid];
+        let position = self.positions[index];
+        heap.push(Candidate {
+            distance_squared: position.distance_squared(center),
+            index,
+        });
+        if heap.len() > k {
+            heap.pop();
+        }
+
+        let axis = depth % 3;
+        let offset = center[axis] - position[axis];
+        let (near, far) = if offset <= 0.0 {
+            (&range[..mid], &range[mid + 1..])
+        } else {
+            (&range[mid + 1..], &range[..mid])
+        };
+
+        self.nearest_in(near, depth + 1, center, k, heap);
+        let worst = heap.peek().map_or(f32::INFINITY, |c| c.distance_squared);
+        // Points on the splitting plane can tie with the worst candidate and win on the index.
+        if heap.len() < k || offset * offset <= worst {
+            self.nearest_in(far, depth + 1, center, k, heap);
+        }
+    }
+}
+
+fn build(range: &mut [usize], positions: &[Vec3], depth: usize) {
+    if range.len() <= 1 {
+        return;
+    }
+
+    let axis = depth % 3;
+    let mid = range.len() / 2;
+    range.select_nth_unstable_by(mid, |&a, &b| {
+        positions[a][axis].total_cmp(&positions[b][axis])
+    });
+
+    let (lower, upper) = range.split_at_mut(mid);
+    build(lower, positions, depth + 1);
+    build(&mut upper[1..], positions, depth + 1);
+}
+
+/// A point found by a nearest-neighbor query, ordered by distance so that the heap keeps the
+/// farthest one on top.
+#[derive(Debug, Copy, Clone)]
+struct Candidate {
+    distance_squared: f32,
+    index: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_squared
+            .total_cmp(&other.distance_squared)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use houdini_node_macro::PointPosition;
+
+    #[derive(Debug, Clone, PointPosition)]
+    struct Point {
+        #[attr(name = "P", position)]
+        p: Vec3,
+    }
+
+    /// A 5x5x5 grid with unit spacing, so point `i` is at `(i % 5, i / 5 % 5, i / 25)`.
+    fn grid() -> Geometry<Point> {
+        (0..125)
+            .map(|i| Point {
+                p: Vec3::new((i % 5) as f32, (i / 5 % 5) as f32, (i / 25) as f32),
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn brute_force_within(geo: &Geometry<Point>, center: Vec3, radius: f32) -> Vec<usize> {
+        (0..geo.points.len())
+            .filter(|&i| geo.points[i].p.distance(center) <= radius)
+            .collect()
+    }
+
+    #[test]
+    fn radius_query() {
+        let geo = grid();
+        let index = geo.build_point_index();
+
+        // The center point and its 6 direct neighbors.
+        let center = Vec3::new(2.0, 2.0, 2.0);
+        let found = index.points_within(center, 1.0);
+        assert_eq!(found.len(), 7);
+        assert_eq!(found, brute_force_within(&geo, center, 1.0));
+
+        for (center, radius) in [
+            (Vec3::ZERO, 1.5),
+            (Vec3::new(4.2, 0.3, 1.7), 2.1),
+            (Vec3::splat(10.0), 1.0),
+        ] {
+            assert_eq!(
+                index.points_within(center, radius),
+                brute_force_within(&geo, center, radius)
+            );
+        }
+    }
+
+    #[test]
+    fn nearest_query() {
+        let geo = grid();
+        let index = geo.build_point_index();
+
+        let nearest = index.nearest(Vec3::new(0.1, 0.0, 0.0), 3);
+        assert_eq!(nearest[0], 0);
+        assert_eq!(nearest[1], 1);
+        // Both (0, 1, 0) and (0, 0, 1) are equally far, ties are broken by index.
+        assert_eq!(nearest[2], 5);
+
+        let all = index.nearest(Vec3::splat(2.0), 200);
+        assert_eq!(all.len(), 125);
+        assert_eq!(all[0], 62);
+        assert!(index.nearest(Vec3::ZERO, 0).is_empty());
+        assert_eq!(index.nearest(Vec3::splat(2.0), usize::MAX), all);
+    }
+
+    #[test]
+    fn nearest_equidistant() {
+        let geo = grid();
+        let index = geo.build_point_index();
+
+        // Centers between the grid points are equally far from several of them, the result must
+        // only depend on the distance and the index.
+        for i in 0..9 * 9 * 9 {
+            let center = Vec3::new((i % 9) as f32, (i / 9 % 9) as f32, (i / 81) as f32) * 0.5;
+            let mut expected: Vec<usize> = (0..geo.points.len()).collect();
+            expected.sort_by(|&a, &b| {
+                let distance = |i: usize| geo.points[i].p.distance_squared(center);
+                distance(a).total_cmp(&distance(b)).then(a.cmp(&b))
+            });
+            for k in [1, 2, 4, 8] {
+                assert_eq!(index.nearest(center, k), expected[..k], "{center} {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn bounding_box() {
+        assert_eq!(grid().point_bbox(), Some((Vec3::ZERO, Vec3::splat(4.0))));
+
+        let mut geo = grid();
+        geo.points.truncate(2);
+        geo.points[1].p = Vec3::new(-1.0, 3.0, 0.5);
+        assert_eq!(
+            geo.point_bbox(),
+            Some((Vec3::new(-1.0, 0.0, 0.0), Vec3::new(0.0, 3.0, 0.5)))
+        );
+
+        geo.points.clear();
+        assert_eq!(geo.point_bbox(), None);
+
+        /// A point whose position is computed, so it can't implement `PointPositionMut`.
+        struct Polar {
+            radius: f32,
+            angle: f32,
+        }
+
+        impl PointPosition for Polar {
+            fn position(&self) -> Vec3 {
+                let (sin, cos) = self.angle.sin_cos();
+                Vec3::new(cos, sin, 0.0) * self.radius
+            }
+        }
+
+        let geo: Geometry<Polar> = vec![Polar {
+            radius: 2.0,
+            angle: 0.0,
+        }]
+        .into();
+        assert_eq!(
+            geo.point_bbox(),
+            Some((Vec3::new(2.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0)))
+        );
+    }
+
+    #[test]
+    fn appended_points() {
+        let mut geo = grid();
+        let index = geo.build_point_index();
+        geo.extra.point_attrs.insert(
+            "density",
+            crate::generate_to_attr(vec![1.0f32; geo.points.len()]),
+        );
+
+        geo.extend_points([]);
+        assert!(geo.extra.point_attrs.contains_key("density"));
+
+        geo.push_point(Point {
+            p: Vec3::splat(-1.0),
+        });
+        geo.extend_points((0..2).map(|i| Point {
+            p: Vec3::splat(10.0 + i as f32),
+        }));
+        assert_eq!(geo.points.len(), 128);
+        assert!(geo.extra.point_attrs.is_empty());
+
+        // The old index is a snapshot, a rebuilt one sees the new points.
+        assert_eq!(index.nearest(Vec3::splat(-1.0), 1), vec![0]);
+        let index = geo.build_point_index();
+        assert_eq!(index.nearest(Vec3::splat(-1.0), 1), vec![125]);
+        assert_eq!(index.points_within(Vec3::splat(10.5), 1.0), vec![126, 127]);
+    }
+
+    #[test]
+    fn cached_point_index() {
+        let mut geo = grid();
+        assert!(geo.extra.point_cache.is_empty());
+        let index = geo.point_index() as *const PointIndex;
+        assert!(std::ptr::eq(index, geo.point_index()));
+        assert_eq!(geo.point_index().nearest(Vec3::splat(-1.0), 1), vec![0]);
+
+        // Moving a point through `points_mut` drops the stale index.
+        geo.points_mut()[124].p = Vec3::splat(-1.5);
+        assert!(geo.extra.point_cache.is_empty());
+        assert_eq!(geo.point_index().nearest(Vec3::splat(-1.0), 1), vec![124]);
+
+        geo.push_point(Point {
+            p: Vec3::splat(-1.0),
+        });
+        assert!(geo.extra.point_cache.is_empty());
+    }
+}
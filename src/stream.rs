@@ -0,0 +1,271 @@
+//! Writing the output one attribute at a time, for geometry that is too large to be held in memory
+//! twice.
+
+use crate::{
+    AttrMeta, AttrMetaMap, Geometry, OutAttrs, OutputOptions, OutputRenames, RawAttribute, Result,
+    VertexToPoint,
+};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+impl<Pt, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt>
+where
+    Pt: OutAttrs + Clone,
+    Vt: OutAttrs + Clone,
+    Pr: OutAttrs + Clone,
+    Dt: OutAttrs + Clone,
+{
+    /// Writes the same JSON as serializing [`Geometry::to_raw`], but only builds one attribute at
+    /// a time and writes it before building the next one. The topology is checked before anything
+    /// is written.
+    pub fn write_streamed(&self, writer: impl Write) -> Result<()> {
+        self.write_streamed_with(writer, OutputOptions::default())
+    }
+
+    /// Like [`Geometry::write_streamed`], with the same JSON as [`Geometry::to_raw_with`].
+    pub fn write_streamed_with(&self, writer: impl Write, options: OutputOptions) -> Result<()> {
+        self.check_serializable_with(options.clone())?;
+        let options = options.for_vertices::<Vt>();
+        let [points, vertices, prims, detail] = self.out_attr_names();
+        let renames = &options.renames;
+        let meta = &self.extra.attr_meta;
+        let mut section = Section {
+            writer,
+            options: &options,
+        };
+
+        write!(section.writer, r#"{{"points":"#)?;
+        let points = OutputRenames::rename(points, &renames.points)?;
+        section.write(points, &meta.points, |name| {
+            match self.extra.point_attrs.get(name) {
+                Some(attr) => Ok(Some(attr.clone())),
+                None => Pt::single_attr_ref(&self.points, name),
+            }
+        })?;
+
+        write!(section.writer, r#","vertices":"#)?;
+        let vertices = OutputRenames::rename(vertices, &renames.vertices)?;
+        section.write(vertices, &meta.vertices, |name| {
+            Vt::single_attr_ref(&self.vertices, name)
+        })?;
+
+        // As in `assemble_output`, the prim vertices are written as point indices.
+        write!(section.writer, r#","prims":"#)?;
+        let prims = OutputRenames::rename(prims, &renames.prims)?;
+        section.write(prims, &meta.prims, |name| {
+            if name != "points" {
+                return Pr::single_attr_ref(&self.prims, name);
+            }
+            let Some(mut primverts) = Pr::single_attr_ref(&self.prims, "vertices")? else {
+                return Ok(None);
+            };
+            let ptnum = self.out_ptnum()?;
+            let counts = (self.points.len(), self.vertices.len());
+            let vert2pt = VertexToPoint::new(ptnum.as_ref(), counts, options.synthesize_ptnum)?;
+            for primvert in primverts.data.prim_vertex_iter_mut()? {
+                for v in primvert {
+                    *v = vert2pt.get(*v)?;
+                }
+            }
+            Ok(Some(primverts))
+        })?;
+
+        write!(section.writer, r#","detail":"#)?;
+        let detail = OutputRenames::rename(detail, &renames.detail)?;
+        section.write(detail, &meta.detail, |name| {
+            Dt::single_attr_ref(std::slice::from_ref(&self.detail), name)
+        })?;
+
+        write!(section.writer, "}}")?;
+        section.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes the sections of the output, see [`Geometry::write_streamed_with`].
+struct Section<'a, W> {
+    writer: W,
+    options: &'a OutputOptions,
+}
+
+impl<W: Write> Section<'_, W> {
+    /// Writes an object with the attribute of every name, keyed by the output name and skipping
+    /// names without an attribute.
+    fn write(
+        &mut self,
+        names: BTreeMap<Cow<'static, str>, &'static str>,
+        meta: &HashMap<String, AttrMeta>,
+        attr: impl Fn(&str) -> Result<Option<RawAttribute>>,
+    ) -> Result<()> {
+        let writer = &mut self.writer;
+        write!(writer, "{{")?;
+        let mut first = true;
+        for (out_name, name) in names {
+            let Some(mut attr) = attr(name)? else {
+                continue;
+            };
+            if let Some(precision) = self.options.float_precision {
+                attr.data.round_floats(precision);
+            }
+            AttrMetaMap::apply_attr(meta.get(out_name.as_ref()), &mut attr);
+
+            if !first {
+                write!(writer, ",")?;
+            }
+            first = false;
+            serde_json::to_writer(&mut *writer, &out_name)?;
+            write!(writer, ":")?;
+            serde_json::to_writer(&mut *writer, &attr)?;
+        }
+        write!(writer, "}}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use crate::{
+        AttrMeta, Error, FloatPrecision, Geometry, OutAttrs, OutputOptions, generate_to_attr,
+    };
+    use glam::Vec3;
+
+    #[derive(Clone, OutAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Vec3,
+        #[attr(name = "Cd", component = 0)]
+        red: f32,
+        #[attr(name = "Cd", component = 1)]
+        green: f32,
+    }
+
+    #[derive(Clone, OutAttrs)]
+    struct Vertex {
+        ptnum: usize,
+        uv: glam::Vec2,
+    }
+
+    #[derive(Clone, OutAttrs)]
+    struct Prim {
+        vertices: Vec<usize>,
+        name: String,
+    }
+
+    #[derive(Clone, OutAttrs)]
+    struct Detail {
+        label: String,
+    }
+
+    fn mesh() -> Geometry<Point, Vertex, Prim, Detail> {
+        let mut geo = Geometry {
+            points: (0..4)
+                .map(|i| Point {
+                    position: Vec3::splat(i as f32),
+                    red: i as f32,
+                    green: 0.5,
+                })
+                .collect(),
+            vertices: [3, 2, 1, 0]
+                .into_iter()
+                .map(|ptnum| Vertex {
+                    ptnum,
+                    uv: glam::Vec2::splat(ptnum as f32),
+                })
+                .collect(),
+            prims: vec![Prim {
+                vertices: vec![0, 1, 2, 3],
+                name: "quad".to_string(),
+            }],
+            detail: Detail {
+                label: "streamed \"mesh\"".to_string(),
+            },
+            extra: Default::default(),
+        };
+        geo.set_point_attr("weight", generate_to_attr(vec![1.0f32, 2.0, 3.0, 4.0]))
+            .unwrap();
+        geo.extra.attr_meta.points.insert(
+            "P".to_string(),
+            AttrMeta {
+                typeinfo: Some("point".to_string()),
+                ..Default::default()
+            },
+        );
+        geo
+    }
+
+    fn streamed<Pt, Vt, Pr, Dt>(
+        geo: &Geometry<Pt, Vt, Pr, Dt>,
+        options: OutputOptions,
+    ) -> crate::Result<String>
+    where
+        Pt: OutAttrs + Clone,
+        Vt: OutAttrs + Clone,
+        Pr: OutAttrs + Clone,
+        Dt: OutAttrs + Clone,
+    {
+        let mut bytes = Vec::new();
+        geo.write_streamed_with(&mut bytes, options)?;
+        Ok(String::from_utf8(bytes).unwrap())
+    }
+
+    fn assert_matches_batch<Pt, Vt, Pr, Dt>(geo: &Geometry<Pt, Vt, Pr, Dt>, options: OutputOptions)
+    where
+        Pt: OutAttrs + Clone,
+        Vt: OutAttrs + Clone,
+        Pr: OutAttrs + Clone,
+        Dt: OutAttrs + Clone,
+    {
+        let batch = serde_json::to_string(&geo.to_raw_with(options.clone()).unwrap()).unwrap();
+        assert_eq!(streamed(geo, options).unwrap(), batch);
+    }
+
+    #[test]
+    fn matches_batch_output() {
+        let geo = mesh();
+        assert_matches_batch(&geo, OutputOptions::default());
+
+        let points_only: Geometry<Point> = geo.points.clone().into();
+        assert_matches_batch(&points_only, OutputOptions::default());
+
+        // All prims deleted, with and without their vertices.
+        let mut geo = mesh();
+        geo.prims.clear();
+        assert_matches_batch(&geo, OutputOptions::default());
+        geo.vertices.clear();
+        assert_matches_batch(&geo, OutputOptions::default());
+
+        // Renames change the order of the attributes, and the metadata follows the output name.
+        let mut options = OutputOptions {
+            float_precision: Some(FloatPrecision::Decimals(1)),
+            ..Default::default()
+        };
+        options.renames.points.insert("Cd", "a_color".to_string());
+        options.renames.points.insert("P", "rest".to_string());
+        options.renames.prims.insert("name", "a_name".to_string());
+        let mut geo = mesh();
+        geo.points[1].red = 0.123;
+        geo.extra.attr_meta.points.insert(
+            "rest".to_string(),
+            AttrMeta {
+                typeinfo: Some("point".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_matches_batch(&geo, options.clone());
+
+        options.renames.points.insert("weight", "rest".to_string());
+        let err = streamed(&geo, options).unwrap_err();
+        assert!(matches!(err, Error::AttrNameCollision(name) if name == "rest"));
+    }
+
+    #[test]
+    fn checks_topology_first() {
+        let mut geo = mesh();
+        geo.prims[0].vertices.push(4);
+        let err = streamed(&geo, OutputOptions::default()).unwrap_err();
+        assert!(matches!(err, Error::InvalidOutPrimVertex(4)));
+    }
+}
@@ -0,0 +1,143 @@
+//! Builders for geometry fixtures, for use in tests. Requires the `testing` feature.
+
+use crate::{IntoAttributeData, RawAttribute, RawGeometry, RawGeometryOutput, generate_to_attr};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+/// Builds a geometry from typed attribute values, as an input ([`RawGeometry`]) or as an output
+/// ([`RawGeometryOutput`]).
+#[derive(Debug, Default)]
+pub struct GeometryFixture {
+    geometry: RawGeometryOutput,
+}
+
+impl RawGeometry {
+    /// Starts building a fixture, see [`GeometryFixture`].
+    pub fn fixture() -> GeometryFixture {
+        GeometryFixture::default()
+    }
+}
+
+impl GeometryFixture {
+    pub fn point_attr<T: IntoAttributeData>(mut self, name: &'static str, values: Vec<T>) -> Self {
+        self.geometry
+            .points
+            .insert(name.into(), generate_to_attr(values));
+        self
+    }
+
+    pub fn vertex_attr<T: IntoAttributeData>(mut self, name: &'static str, values: Vec<T>) -> Self {
+        self.geometry
+            .vertices
+            .insert(name.into(), generate_to_attr(values));
+        self
+    }
+
+    pub fn prim_attr<T: IntoAttributeData>(mut self, name: &'static str, values: Vec<T>) -> Self {
+        self.geometry
+            .prims
+            .insert(name.into(), generate_to_attr(values));
+        self
+    }
+
+    pub fn detail_attr<T: IntoAttributeData>(mut self, name: &'static str, value: T) -> Self {
+        self.geometry
+            .detail
+            .insert(name.into(), generate_to_attr(vec![value]));
+        self
+    }
+
+    /// Adds polygons given by their point numbers. Every polygon gets its own vertices, which are
+    /// stored in the `ptnum` and `vertices` pseudo-attributes like the Houdini side does.
+    pub fn polygons(self, polygons: Vec<Vec<usize>>) -> Self {
+        let mut ptnums = Vec::new();
+        let prim_vertices = polygons
+            .into_iter()
+            .map(|points| {
+                let start = ptnums.len();
+                ptnums.extend(points);
+                (start..ptnums.len()).collect::<Vec<_>>()
+            })
+            .collect();
+
+        self.vertex_attr("ptnum", ptnums)
+            .prim_attr("vertices", prim_vertices)
+    }
+
+    /// Builds an input geometry.
+    pub fn build(self) -> RawGeometry {
+        fn convert(
+            attrs: BTreeMap<Cow<'static, str>, RawAttribute>,
+        ) -> HashMap<String, RawAttribute> {
+            attrs
+                .into_iter()
+                .map(|(name, attr)| (name.to_string(), attr))
+                .collect()
+        }
+
+        RawGeometry {
+            points: convert(self.geometry.points),
+            vertices: convert(self.geometry.vertices),
+            prims: convert(self.geometry.prims),
+            detail: convert(self.geometry.detail),
+        }
+    }
+
+    /// Builds an output geometry. Note that unlike [`crate::IntoRawGeometry::into_raw`], this
+    /// keeps the topology pseudo-attributes as they are.
+    pub fn build_output(self) -> RawGeometryOutput {
+        self.geometry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{FromRawGeometry, Geometry};
+    use glam::Vec3;
+    use houdini_node_macro::InAttrs;
+
+    #[derive(PartialEq, Debug, InAttrs)]
+    struct Point {
+        #[attr(name = "P")]
+        position: Vec3,
+    }
+
+    #[derive(PartialEq, Debug, InAttrs)]
+    struct Vertex {
+        ptnum: usize,
+    }
+
+    #[derive(PartialEq, Debug, InAttrs)]
+    struct Prim {
+        vertices: Vec<usize>,
+        material: String,
+    }
+
+    #[derive(PartialEq, Debug, InAttrs)]
+    struct Detail {
+        frame: i32,
+    }
+
+    #[test]
+    fn fixture_loads_into_geometry() {
+        let raw = RawGeometry::fixture()
+            .point_attr("P", vec![Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::ONE])
+            .polygons(vec![vec![0, 1, 2], vec![1, 3, 2]])
+            .prim_attr("material", vec!["a".to_string(), "b".to_string()])
+            .detail_attr("frame", 12)
+            .build();
+
+        let geo = Geometry::<Point, Vertex, Prim, Detail>::from_raw(raw, 0).unwrap();
+        assert_eq!(geo.points.len(), 4);
+        assert_eq!(
+            geo.vertices.iter().map(|v| v.ptnum).collect::<Vec<_>>(),
+            vec![0, 1, 2, 1, 3, 2]
+        );
+        assert_eq!(geo.prims[1].vertices, vec![3, 4, 5]);
+        assert_eq!(geo.prims[1].material, "b");
+        assert_eq!(geo.detail.frame, 12);
+    }
+}
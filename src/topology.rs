@@ -0,0 +1,1299 @@
+//! Typed access to the topology pseudo-attributes and operations built on top of it.
+
+use crate::{EntityKind, Error, Geometry, GeometryExtra, PointPosition, PointPositionMut, Result};
+use glam::Vec3;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::ops::{Add, Mul};
+
+/// Access to the `vertices` pseudo-attribute of a prim, i.e. the indices of its vertices.
+///
+/// Can be derived for structs that have a field mapping to the `vertices` attribute.
+pub trait PrimTopology {
+    fn vertices(&self) -> &[usize];
+    fn vertices_mut(&mut self) -> &mut Vec<usize>;
+}
+
+/// Access to the `ptnum` pseudo-attribute of a vertex, i.e. the index of the point it references.
+///
+/// Can be derived for structs that have a field mapping to the `ptnum` attribute.
+pub trait VertexTopology {
+    fn ptnum(&self) -> usize;
+    fn ptnum_mut(&mut self) -> &mut usize;
+}
+
+impl<Pt, Vt, Pr: PrimTopology, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// The vertex indices of every prim, in prim order.
+    pub fn prim_vertices(&self) -> impl Iterator<Item = &[usize]> {
+        self.prims.iter().map(PrimTopology::vertices)
+    }
+
+    /// Flips the orientation of every prim by reversing its vertex order. With `keep_first`, the
+    /// first vertex stays in place like with Houdini's Reverse SOP, so `[0, 1, 2, 3]` becomes
+    /// `[0, 3, 2, 1]` instead of `[3, 2, 1, 0]`.
+    ///
+    /// Only the order within each prim changes, so this works the same whether the lists refer to
+    /// vertices or, after remapping, directly to points.
+    pub fn reverse_winding(&mut self, keep_first: bool) {
+        for prim in &mut self.prims {
+            let vertices = prim.vertices_mut();
+            if keep_first && !vertices.is_empty() {
+                vertices[1..].reverse();
+            } else {
+                vertices.reverse();
+            }
+        }
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt>
+where
+    Pt: Clone,
+    Vt: Clone + VertexTopology,
+    Pr: Clone + PrimTopology,
+    Dt: Clone,
+{
+    /// Partitions the prims by `key` into separate geometries. Each geometry only contains the
+    /// vertices and points referenced by its prims, renumbered in order of first use. The detail
+    /// is copied into every geometry.
+    ///
+    /// Fails with [`Error::InvalidInPrimVertex`] if a prim refers to a vertex that doesn't exist,
+    /// and with [`Error::InvalidPointRef`] if a vertex refers to a point that doesn't exist.
+    pub fn split_by<K: Eq + Hash>(&self, key: impl Fn(&Pr) -> K) -> Result<HashMap<K, Self>> {
+        let mut groups: HashMap<K, Vec<usize>> = HashMap::new();
+        for (i, prim) in self.prims.iter().enumerate() {
+            groups.entry(key(prim)).or_default().push(i);
+        }
+
+        groups
+            .into_iter()
+            .map(|(k, prims)| Ok((k, self.extract_prims(&prims)?)))
+            .collect()
+    }
+
+    /// Builds a new geometry from the given points, in the given order, and the prims on them as
+    /// decided by `policy`. Vertices and points are renumbered, the detail is copied. Repeated
+    /// point indices are only used once.
+    ///
+    /// Fails with [`Error::InvalidPointRef`] if a point index is out of range or a vertex refers
+    /// to a point that doesn't exist, and with [`Error::InvalidInPrimVertex`] if a prim refers to
+    /// a vertex that doesn't exist.
+    pub fn subset(&self, point_indices: &[usize], policy: SubsetPolicy) -> Result<Self> {
+        let point_count = self.points.len();
+        let mut point_map = vec![None; point_count];
+        let mut points = Vec::with_capacity(point_indices.len());
+        for &ptnum in point_indices {
+            let mapped = point_map.get_mut(ptnum).ok_or(Error::InvalidPointRef {
+                index: ptnum,
+                point_count,
+            })?;
+            mapped.get_or_insert_with(|| {
+                points.push(self.points[ptnum].clone());
+                points.len() - 1
+            });
+        }
+
+        let mut vertices = Vec::new();
+        let mut prims = Vec::new();
+        for (prim_index, prim) in self.prims.iter().enumerate() {
+            let mut selected = Vec::with_capacity(prim.vertices().len());
+            for &v in prim.vertices() {
+                let vertex = self.vertices.get(v).ok_or(Error::InvalidInPrimVertex {
+                    prim_index,
+                    vertex: v,
+                })?;
+                let ptnum = vertex.ptnum();
+                let mapped = point_map.get(ptnum).ok_or(Error::InvalidPointRef {
+                    index: ptnum,
+                    point_count,
+                })?;
+                if mapped.is_some() {
+                    selected.push(v);
+                }
+            }
+            let keep = match policy {
+                SubsetPolicy::Contained => selected.len() == prim.vertices().len(),
+                SubsetPolicy::Clip => !selected.is_empty(),
+            };
+            if !keep {
+                continue;
+            }
+
+            let mut prim = prim.clone();
+            *prim.vertices_mut() = selected
+                .into_iter()
+                .map(|v| {
+                    let mut vertex = self.vertices[v].clone();
+                    let ptnum = vertex.ptnum_mut();
+                    *ptnum = point_map[*ptnum].unwrap();
+                    vertices.push(vertex);
+                    vertices.len() - 1
+                })
+                .collect();
+            prims.push(prim);
+        }
+
+        Ok(Self {
+            points,
+            vertices,
+            prims,
+            detail: self.detail.clone(),
+            extra: GeometryExtra {
+                attr_info: self.extra.attr_info.clone(),
+                attr_meta: self.extra.attr_meta.clone(),
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Builds a new geometry from the given prims, pulling along their vertices and points. Fails
+    /// like [`Geometry::split_by`].
+    fn extract_prims(&self, prim_indices: &[usize]) -> Result<Self> {
+        let point_count = self.points.len();
+        let mut vertex_map = vec![None; self.vertices.len()];
+        let mut point_map = vec![None; point_count];
+
+        let mut points = Vec::new();
+        let mut vertices = Vec::new();
+        let mut prims = Vec::with_capacity(prim_indices.len());
+
+        for &prim_index in prim_indices {
+            let mut prim = self.prims[prim_index].clone();
+
+            for v in prim.vertices_mut() {
+                let mapped = vertex_map.get_mut(*v).ok_or(Error::InvalidInPrimVertex {
+                    prim_index,
+                    vertex: *v,
+                })?;
+                if mapped.is_none() {
+                    let mut vertex = self.vertices[*v].clone();
+                    let ptnum = vertex.ptnum_mut();
+                    let point = point_map.get_mut(*ptnum).ok_or(Error::InvalidPointRef {
+                        index: *ptnum,
+                        point_count,
+                    })?;
+                    *ptnum = *point.get_or_insert_with(|| {
+                        points.push(self.points[*ptnum].clone());
+                        points.len() - 1
+                    });
+                    vertices.push(vertex);
+                    *mapped = Some(vertices.len() - 1);
+                }
+                *v = mapped.unwrap();
+            }
+
+            prims.push(prim);
+        }
+
+        Ok(Self {
+            points,
+            vertices,
+            prims,
+            detail: self.detail.clone(),
+            // The computed point attributes don't match the extracted points anymore.
+            extra: GeometryExtra {
+                attr_info: self.extra.attr_info.clone(),
+                attr_meta: self.extra.attr_meta.clone(),
+                ..Default::default()
+            },
+        })
+    }
+}
+
+/// Which prims [`Geometry::subset`] keeps.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SubsetPolicy {
+    /// Only prims whose points are all selected.
+    Contained,
+    /// Every prim with at least one selected point, without the vertices of the other points.
+    /// This can leave open or degenerate prims.
+    Clip,
+}
+
+/// The first difference found by [`Geometry::assert_same_topology`].
+#[derive(Debug, Copy, Clone)]
+pub enum TopologyChange {
+    Count {
+        entity: EntityKind,
+        expected: usize,
+        actual: usize,
+    },
+    /// The vertex list of this prim differs.
+    PrimVertices(usize),
+    /// This vertex references a different point.
+    VertexPtnum(usize),
+}
+
+impl Display for TopologyChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TopologyChange::Count {
+                entity,
+                expected,
+                actual,
+            } => write!(f, "{entity} count changed from {expected} to {actual}"),
+            TopologyChange::PrimVertices(prim) => write!(f, "vertices of prim {prim} changed"),
+            TopologyChange::VertexPtnum(vertex) => write!(f, "point of vertex {vertex} changed"),
+        }
+    }
+}
+
+impl<Pt, Vt: VertexTopology, Pr: PrimTopology, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Checks that the geometry has the same points, vertices and prims as `input`, with the same
+    /// connections between them. Meant for nodes that only deform their input, to guard against
+    /// accidentally adding or removing entities. Attribute values are not compared.
+    pub fn assert_same_topology<Pt2, Vt2, Pr2, Dt2>(
+        &self,
+        input: &Geometry<Pt2, Vt2, Pr2, Dt2>,
+    ) -> Result<()>
+    where
+        Vt2: VertexTopology,
+        Pr2: PrimTopology,
+    {
+        let changed = |change| Err(Error::TopologyChanged(change));
+        let counts = [
+            (EntityKind::Point, input.points.len(), self.points.len()),
+            (
+                EntityKind::Vertex,
+                input.vertices.len(),
+                self.vertices.len(),
+            ),
+            (EntityKind::Prim, input.prims.len(), self.prims.len()),
+        ];
+        for (entity, expected, actual) in counts {
+            if expected != actual {
+                return changed(TopologyChange::Count {
+                    entity,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        let mut prims = self.prims.iter().zip(&input.prims);
+        if let Some(i) = prims.position(|(ours, theirs)| ours.vertices() != theirs.vertices()) {
+            return changed(TopologyChange::PrimVertices(i));
+        }
+        let mut vertices = self.vertices.iter().zip(&input.vertices);
+        if let Some(i) = vertices.position(|(ours, theirs)| ours.ptnum() != theirs.ptnum()) {
+            return changed(TopologyChange::VertexPtnum(i));
+        }
+
+        Ok(())
+    }
+}
+
+/// How [`Geometry::promote_vertex_to_point`] combines the values of all vertices of a point.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Promotion {
+    /// The value of the first vertex referencing the point.
+    First,
+    Average,
+    Sum,
+}
+
+impl<Pt, Vt: VertexTopology, Pr, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Copies a point value to every vertex referencing the point, returning one value per vertex.
+    pub fn promote_point_to_vertex<T>(&self, value: impl Fn(&Pt) -> T) -> Result<Vec<T>> {
+        self.vertices
+            .iter()
+            .map(|vertex| self.vertex_point(vertex).map(&value))
+            .collect()
+    }
+
+    /// Combines the values of all vertices referencing a point according to `policy`, returning
+    /// one value per point. Points without vertices get `T::default()`.
+    pub fn promote_vertex_to_point<T>(
+        &self,
+        value: impl Fn(&Vt) -> T,
+        policy: Promotion,
+    ) -> Result<Vec<T>>
+    where
+        T: Default + Add<Output = T> + Mul<f32, Output = T>,
+    {
+        let mut values: Vec<Option<T>> = std::iter::repeat_with(|| None)
+            .take(self.points.len())
+            .collect();
+        let mut counts = vec![0usize; self.points.len()];
+
+        for vertex in &self.vertices {
+            self.vertex_point(vertex)?;
+            let ptnum = vertex.ptnum();
+            counts[ptnum] += 1;
+            values[ptnum] = Some(match (values[ptnum].take(), policy) {
+                (None, _) => value(vertex),
+                (Some(first), Promotion::First) => first,
+                (Some(total), Promotion::Average | Promotion::Sum) => total + value(vertex),
+            });
+        }
+
+        Ok(values
+            .into_iter()
+            .zip(counts)
+            .map(|(value, count)| match (value, policy) {
+                (Some(total), Promotion::Average) => total * (1.0 / count as f32),
+                (value, _) => value.unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    fn vertex_point(&self, vertex: &Vt) -> Result<&Pt> {
+        self.points
+            .get(vertex.ptnum())
+            .ok_or(Error::InvalidPointRef {
+                index: vertex.ptnum(),
+                point_count: self.points.len(),
+            })
+    }
+}
+
+impl<Pt, Vt: VertexTopology, Pr: PrimTopology, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// The neighbors of every point, i.e. the points it shares a prim edge with, sorted by index.
+    /// Every prim is treated as a closed polygon, like the Python bridge creates them.
+    pub fn build_adjacency(&self) -> Result<Vec<Vec<usize>>> {
+        self.build_adjacency_with(|_| true)
+    }
+
+    /// Like [`Geometry::build_adjacency`], with open polylines for the prims where `closed` returns
+    /// `false`. Their last point is not connected to their first one.
+    pub fn build_adjacency_with(&self, closed: impl Fn(&Pr) -> bool) -> Result<Vec<Vec<usize>>> {
+        let mut neighbors = vec![Vec::new(); self.points.len()];
+        for (prim_index, prim) in self.prims.iter().enumerate() {
+            let points = prim
+                .vertices()
+                .iter()
+                .map(|&vertex| {
+                    let vertex = self
+                        .vertices
+                        .get(vertex)
+                        .ok_or(Error::InvalidInPrimVertex { prim_index, vertex })?;
+                    self.vertex_point(vertex)?;
+                    Ok(vertex.ptnum())
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let closing = match points[..] {
+                [first, .., last] if closed(prim) => Some((last, first)),
+                _ => None,
+            };
+            let edges = points.windows(2).map(|edge| (edge[0], edge[1]));
+            for (a, b) in edges.chain(closing) {
+                if a != b {
+                    neighbors[a].push(b);
+                    neighbors[b].push(a);
+                }
+            }
+        }
+
+        for points in &mut neighbors {
+            points.sort_unstable();
+            points.dedup();
+        }
+        Ok(neighbors)
+    }
+}
+
+impl<Pt: PointPositionMut, Vt: VertexTopology, Pr: PrimTopology, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Laplacian smoothing: every iteration moves each point by `strength` towards the average
+    /// position of its neighbors, see [`Geometry::build_adjacency`]. A `strength` of 1 moves the
+    /// points onto the average. All points are moved at once, based on the positions of the
+    /// previous iteration. Points without neighbors stay in place.
+    pub fn smooth_positions(&mut self, iterations: usize, strength: f32) -> Result<()> {
+        let neighbors = self.build_adjacency()?;
+        for _ in 0..iterations {
+            let positions: Vec<Vec3> = self.points.iter().map(PointPosition::position).collect();
+            for (point, neighbors) in self.points_mut().iter_mut().zip(&neighbors) {
+                if neighbors.is_empty() {
+                    continue;
+                }
+                let average =
+                    neighbors.iter().map(|&i| positions[i]).sum::<Vec3>() / neighbors.len() as f32;
+                let position = point.position_mut();
+                *position = position.lerp(average, strength);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<Pt, Vt: Clone + VertexTopology, Pr: Clone + PrimTopology, Dt> Geometry<Pt, Vt, Pr, Dt> {
+    /// Splits every prim into a fan of triangles around its first vertex, so a quad `[0, 1, 2, 3]`
+    /// becomes `[0, 1, 2]` and `[0, 2, 3]`. Each triangle copies the attributes of its prim and
+    /// gets its own copies of the vertices, as a vertex can only belong to a single prim. The
+    /// points stay untouched. Prims with less than 3 vertices are dropped, as are vertices that
+    /// don't belong to any prim.
+    ///
+    /// Fails without modifying `self` if a prim refers to a vertex that doesn't exist.
+    pub fn triangulate(&mut self) -> Result<()> {
+        for (prim_index, prim) in self.prims.iter().enumerate() {
+            for &vertex in prim.vertices() {
+                self.vertices
+                    .get(vertex)
+                    .ok_or(Error::InvalidInPrimVertex { prim_index, vertex })?;
+            }
+        }
+
+        let vertices = std::mem::take(&mut self.vertices);
+        let prims = std::mem::take(&mut self.prims);
+
+        for prim in prims {
+            let fan = prim.vertices();
+            for i in 2..fan.len() {
+                let mut triangle = prim.clone();
+                *triangle.vertices_mut() = [fan[0], fan[i - 1], fan[i]]
+                    .into_iter()
+                    .map(|vertex| {
+                        self.vertices.push(vertices[vertex].clone());
+                        self.vertices.len() - 1
+                    })
+                    .collect();
+                self.prims.push(triangle);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<Pt, Vt, Pr, Dt> Geometry<Pt, Vt, Pr, Dt>
+where
+    Vt: Default + VertexTopology,
+    Pr: Default + PrimTopology,
+{
+    /// Adds a prim on the given existing points, creating one new vertex per point. The vertices
+    /// and the prim start out with default attributes, the returned prim can be used to set them.
+    /// Fails without modifying `self` if a point does not exist.
+    pub fn append_polygon(&mut self, point_indices: &[usize]) -> Result<&mut Pr> {
+        if let Some(&index) = point_indices.iter().find(|&&i| i >= self.points.len()) {
+            return Err(Error::InvalidPointRef {
+                index,
+                point_count: self.points.len(),
+            });
+        }
+
+        let first_vertex = self.vertices.len();
+        self.vertices.extend(point_indices.iter().map(|&ptnum| {
+            let mut vertex = Vt::default();
+            *vertex.ptnum_mut() = ptnum;
+            vertex
+        }));
+
+        let mut prim = Pr::default();
+        *prim.vertices_mut() = (first_vertex..self.vertices.len()).collect();
+        self.prims.push(prim);
+        Ok(self.prims.last_mut().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate self as houdini_node;
+
+    use super::*;
+    use crate::{
+        Error, FromRawGeometry, IntoRawGeometry, OutAttrs, OutputOptions, PrimVertices,
+        RawAttribute, RawAttributeData, RawGeometry,
+    };
+    use glam::Vec3;
+    use houdini_node_macro::{InAttrs, PointPosition, PrimTopology, VertexTopology};
+    use std::collections::BTreeMap;
+
+    #[derive(PartialEq, Debug, Clone, InAttrs, OutAttrs, PointPosition)]
+    struct Point {
+        #[attr(name = "P", position)]
+        position: Vec3,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Default, InAttrs, OutAttrs, VertexTopology)]
+    struct Vertex {
+        ptnum: usize,
+    }
+
+    #[derive(PartialEq, Debug, Clone, Default, InAttrs, OutAttrs, PrimTopology)]
+    struct Prim {
+        vertices: Vec<usize>,
+        material: String,
+    }
+
+    /// The prim vertex data for `prims`, whichever type [`PrimVertices`] is.
+    fn prim_vertices<const N: usize>(prims: [Vec<usize>; N]) -> Vec<PrimVertices> {
+        prims.iter().map(|vertices| vertices[..].into()).collect()
+    }
+
+    /// Two quads sharing an edge, each with a different material.
+    ///
+    /// ```text
+    /// 3 - 4 - 5
+    /// | a | b |
+    /// 0 - 1 - 2
+    /// ```
+    fn two_material_mesh() -> Geometry<Point, Vertex, Prim> {
+        let points = (0..6)
+            .map(|i| Point {
+                position: Vec3::new((i % 3) as f32, (i / 3) as f32, 0.0),
+            })
+            .collect();
+
+        let vertices = [0, 1, 4, 3, 1, 2, 5, 4]
+            .into_iter()
+            .map(|ptnum| Vertex { ptnum })
+            .collect();
+
+        Geometry {
+            points,
+            vertices,
+            prims: vec![
+                Prim {
+                    vertices: vec![0, 1, 2, 3],
+                    material: "a".to_string(),
+                },
+                Prim {
+                    vertices: vec![4, 5, 6, 7],
+                    material: "b".to_string(),
+                },
+            ],
+            detail: (),
+            extra: Default::default(),
+        }
+    }
+
+    #[test]
+    fn split_by_material() {
+        let geo = two_material_mesh();
+        let parts = geo.split_by(|prim| prim.material.clone()).unwrap();
+
+        assert_eq!(parts.len(), 2);
+
+        for (material, part) in &parts {
+            assert_eq!(part.points.len(), 4);
+            assert_eq!(part.vertices.len(), 4);
+            assert_eq!(part.prims.len(), 1);
+            assert_eq!(&part.prims[0].material, material);
+            assert_eq!(part.prims[0].vertices, vec![0, 1, 2, 3]);
+        }
+
+        let b = &parts["b"];
+        let positions: Vec<_> = b.prims[0]
+            .vertices
+            .iter()
+            .map(|&v| b.points[b.vertices[v].ptnum].position)
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(2.0, 1.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0),
+            ]
+        );
+
+        let mut broken = two_material_mesh();
+        broken.vertices[5].ptnum = 9;
+        let err = broken.split_by(|prim| prim.material.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidPointRef {
+                index: 9,
+                point_count: 6
+            }
+        ));
+        broken.prims[0].vertices.push(8);
+        let err = broken.split_by(|_| ()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidInPrimVertex {
+                prim_index: 0,
+                vertex: 8
+            }
+        ));
+    }
+
+    #[test]
+    fn subset_contained() {
+        let geo = two_material_mesh();
+        let part = geo
+            .subset(&[5, 1, 2, 4, 2], SubsetPolicy::Contained)
+            .unwrap();
+
+        assert_eq!(part.points.len(), 4);
+        assert_eq!(part.points[0].position, Vec3::new(2.0, 1.0, 0.0));
+        assert_eq!(part.prims.len(), 1);
+        assert_eq!(part.prims[0].material, "b");
+        assert_eq!(part.prims[0].vertices, vec![0, 1, 2, 3]);
+        let ptnums: Vec<_> = part.vertices.iter().map(|v| v.ptnum).collect();
+        assert_eq!(ptnums, vec![1, 2, 0, 3]);
+
+        // Points without prims are kept.
+        let part = geo.subset(&[0, 1], SubsetPolicy::Contained).unwrap();
+        assert_eq!(part.points.len(), 2);
+        assert!(part.prims.is_empty());
+        assert!(part.vertices.is_empty());
+
+        let err = geo.subset(&[0, 6], SubsetPolicy::Contained).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidPointRef {
+                index: 6,
+                point_count: 6
+            }
+        ));
+
+        let mut broken = two_material_mesh();
+        broken.vertices[0].ptnum = 9;
+        let err = broken.subset(&[0], SubsetPolicy::Contained).unwrap_err();
+        assert!(matches!(err, Error::InvalidPointRef { index: 9, .. }));
+        broken.prims[1].vertices.push(8);
+        broken.vertices[0].ptnum = 0;
+        let err = broken.subset(&[0], SubsetPolicy::Clip).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidInPrimVertex {
+                prim_index: 1,
+                vertex: 8
+            }
+        ));
+    }
+
+    #[test]
+    fn subset_clip() {
+        let geo = two_material_mesh();
+        let part = geo.subset(&[1, 4, 2], SubsetPolicy::Clip).unwrap();
+
+        assert_eq!(part.prims.len(), 2);
+        let positions: Vec<Vec<Vec3>> = part
+            .prim_vertices()
+            .map(|vertices| {
+                vertices
+                    .iter()
+                    .map(|&v| part.points[part.vertices[v].ptnum].position)
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                vec![Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0)],
+                vec![
+                    Vec3::new(1.0, 0.0, 0.0),
+                    Vec3::new(2.0, 0.0, 0.0),
+                    Vec3::new(1.0, 1.0, 0.0),
+                ],
+            ]
+        );
+        assert_eq!(part.vertices.len(), 5);
+        assert!(
+            geo.subset(&[1, 4, 2], SubsetPolicy::Contained)
+                .unwrap()
+                .prims
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn prim_topology_round_trip() {
+        // Same layout as sent by the Houdini side: vertices reference points via `ptnum`, prims
+        // reference vertices via `vertices`.
+        let input = r#"
+        {
+            "points": {
+                "P": { "tuple_size": 3, "data": { "float": [0, 0, 0, 1, 0, 0, 2, 0, 0, 0, 1, 0, 1, 1, 0, 2, 1, 0] } }
+            },
+            "vertices": {
+                "ptnum": { "tuple_size": 1, "data": { "index": [0, 1, 4, 3, 1, 2, 5, 4] } }
+            },
+            "prims": {
+                "vertices": { "tuple_size": 1, "data": { "prim_vertex": [[0, 1, 2, 3], [4, 5, 6, 7]] } },
+                "material": { "tuple_size": 1, "data": { "string": ["a", "b"] } }
+            },
+            "detail": {}
+        }
+        "#;
+        let raw: RawGeometry = serde_json::from_str(input).unwrap();
+        let geo = Geometry::<Point, Vertex, Prim>::from_raw(raw, 0).unwrap();
+
+        assert_eq!(geo, two_material_mesh());
+        assert_eq!(
+            geo.prim_vertices().collect::<Vec<_>>(),
+            vec![&[0, 1, 2, 3][..], &[4, 5, 6, 7][..]]
+        );
+
+        // On output, the prim vertices are resolved to point numbers.
+        let out = geo.into_raw().unwrap();
+        let RawAttributeData::PrimVertex(points) = &out.prims["points"].data else {
+            panic!("expected prim points");
+        };
+        assert_eq!(points, &prim_vertices([vec![0, 1, 4, 3], vec![1, 2, 5, 4]]));
+    }
+
+    #[test]
+    fn prim_size_validation() {
+        #[derive(Debug, InAttrs)]
+        #[attr(prim_size = 3)]
+        struct Triangle {
+            #[allow(dead_code)]
+            vertices: Vec<usize>,
+        }
+
+        let input = r#"
+        {
+            "points": {},
+            "vertices": {},
+            "prims": {
+                "vertices": { "tuple_size": 1, "data": { "prim_vertex": [[0, 1, 2], [3, 4, 5, 6]] } }
+            },
+            "detail": {}
+        }
+        "#;
+        let raw: RawGeometry = serde_json::from_str(input).unwrap();
+        let err = Geometry::<(), (), Triangle>::from_raw(raw, 0).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::InvalidPrimSize {
+                prim_index: 1,
+                expected: 3,
+                actual: 4
+            }
+        ));
+    }
+
+    #[test]
+    fn reverse_quad_winding() {
+        let mut geo = two_material_mesh();
+        geo.reverse_winding(true);
+        assert_eq!(geo.prims[0].vertices, vec![0, 3, 2, 1]);
+        assert_eq!(geo.prims[1].vertices, vec![4, 7, 6, 5]);
+
+        let mut geo = two_material_mesh();
+        geo.reverse_winding(false);
+        assert_eq!(geo.prims[0].vertices, vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn synthesized_ptnum() {
+        #[derive(Debug, Clone, OutAttrs)]
+        struct UvVertex {
+            uv: glam::Vec2,
+        }
+
+        let quad = |vertex_count: usize| Geometry::<Point, UvVertex, Prim> {
+            points: two_material_mesh().points[..4].to_vec(),
+            vertices: (0..vertex_count)
+                .map(|i| UvVertex {
+                    uv: glam::Vec2::splat(i as f32),
+                })
+                .collect(),
+            prims: vec![Prim {
+                vertices: vec![0, 1, 3, 2],
+                material: "a".to_string(),
+            }],
+            detail: (),
+            extra: Default::default(),
+        };
+        let options = OutputOptions {
+            synthesize_ptnum: true,
+            ..Default::default()
+        };
+
+        // Opt-in only.
+        let err = quad(4).into_raw().unwrap_err();
+        assert!(matches!(err, crate::Error::MissingOutVertexPtnums));
+
+        let out = quad(4).into_raw_with(options.clone()).unwrap();
+        let RawAttributeData::PrimVertex(points) = &out.prims["points"].data else {
+            panic!("expected prim points");
+        };
+        assert_eq!(points, &prim_vertices([vec![0, 1, 3, 2]]));
+
+        let err = quad(3).into_raw_with(options).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::PtnumSynthesisMismatch {
+                points: 4,
+                vertices: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn prims_without_vertex_entity() {
+        // Triangles generated straight from the points, without a vertex type.
+        let mesh = two_material_mesh();
+        let geo = Geometry::<Point, (), Prim> {
+            points: mesh.points.clone(),
+            vertices: Vec::new(),
+            prims: [[0, 1, 4], [0, 4, 3]]
+                .into_iter()
+                .map(|points| Prim {
+                    vertices: points.to_vec(),
+                    material: "a".to_string(),
+                })
+                .collect(),
+            detail: (),
+            extra: Default::default(),
+        };
+
+        let mut broken = geo.clone();
+        check_mirrors_into_raw(geo.clone(), OutputOptions::default()).unwrap();
+        let out = geo.into_raw().unwrap();
+        let RawAttributeData::PrimVertex(points) = &out.prims["points"].data else {
+            panic!("expected prim points");
+        };
+        assert_eq!(points, &prim_vertices([vec![0, 1, 4], vec![0, 4, 3]]));
+        assert!(out.vertices.is_empty());
+
+        // The prim vertices are still checked against the points.
+        broken.prims[1].vertices[2] = 6;
+        let err = check_mirrors_into_raw(broken, OutputOptions::default()).unwrap_err();
+        assert!(matches!(err, Error::InvalidOutPrimVertex(6)));
+
+        // A vertex type without any vertices is most likely a mistake.
+        let geo = Geometry::<Point, Vertex, Prim> {
+            vertices: Vec::new(),
+            ..mesh
+        };
+        let err = check_mirrors_into_raw(geo, OutputOptions::default()).unwrap_err();
+        assert!(matches!(err, Error::MissingOutVertices));
+        assert!(err.to_string().contains("synthesize_ptnum"));
+    }
+
+    /// Checks the geometry and asserts that `into_raw` and `write_streamed` come to the same
+    /// result.
+    fn check_mirrors_into_raw<Vt: OutAttrs + Clone, Pr: OutAttrs + Clone>(
+        geo: Geometry<Point, Vt, Pr>,
+        options: OutputOptions,
+    ) -> crate::Result<()> {
+        let checked = geo.check_serializable_with(options.clone());
+        let mut streamed = Vec::new();
+        let streamed_result = geo.write_streamed_with(&mut streamed, options.clone());
+        let converted = geo.into_raw_with(options);
+        assert_eq!(format!("{checked:?}"), format!("{streamed_result:?}"));
+        assert_eq!(
+            format!("{checked:?}"),
+            format!("{:?}", converted.as_ref().map(|_| ()))
+        );
+        if let Ok(converted) = converted {
+            assert_eq!(streamed, serde_json::to_vec(&converted).unwrap());
+        }
+        checked
+    }
+
+    #[test]
+    fn check_serializable() {
+        let options = OutputOptions::default();
+        let synthesize = OutputOptions {
+            synthesize_ptnum: true,
+            ..Default::default()
+        };
+
+        check_mirrors_into_raw(two_material_mesh(), options.clone()).unwrap();
+
+        let mut geo = two_material_mesh();
+        geo.prims[1].vertices.push(8);
+        let err = check_mirrors_into_raw(geo, options.clone()).unwrap_err();
+        assert!(matches!(err, Error::InvalidOutPrimVertex(8)));
+
+        // Without prims, there is no topology to check.
+        let mut geo = two_material_mesh();
+        geo.prims.clear();
+        check_mirrors_into_raw(geo.clone(), options.clone()).unwrap();
+        geo.vertices.clear();
+        check_mirrors_into_raw(geo, options.clone()).unwrap();
+
+        let mut renamed = options.clone();
+        renamed.renames.prims.insert("material", "shop".to_string());
+        check_mirrors_into_raw(two_material_mesh(), renamed.clone()).unwrap();
+        renamed
+            .renames
+            .prims
+            .insert("material", "points".to_string());
+        let err = check_mirrors_into_raw(two_material_mesh(), renamed).unwrap_err();
+        assert!(matches!(err, Error::AttrNameCollision(name) if name == "points"));
+
+        #[derive(Clone, OutAttrs)]
+        struct UvVertex {
+            uv: glam::Vec2,
+        }
+        let uv_mesh = |vertex_count: usize| Geometry {
+            points: two_material_mesh().points,
+            vertices: vec![
+                UvVertex {
+                    uv: glam::Vec2::ZERO
+                };
+                vertex_count
+            ],
+            prims: two_material_mesh().prims,
+            detail: (),
+            extra: Default::default(),
+        };
+        let err = check_mirrors_into_raw(uv_mesh(8), options.clone()).unwrap_err();
+        assert!(matches!(err, Error::MissingOutVertexPtnums));
+        let err = check_mirrors_into_raw(uv_mesh(8), synthesize.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PtnumSynthesisMismatch {
+                points: 6,
+                vertices: 8
+            }
+        ));
+        // Without ptnums, the prims refer to the points directly, and there is no point 6 or 7.
+        let err = check_mirrors_into_raw(uv_mesh(0), synthesize).unwrap_err();
+        assert!(matches!(err, Error::InvalidOutPrimVertex(6)));
+
+        #[derive(Clone, OutAttrs)]
+        struct IntPtnumVertex {
+            ptnum: i32,
+        }
+        let geo = Geometry {
+            points: two_material_mesh().points,
+            vertices: vec![IntPtnumVertex { ptnum: 0 }; 8],
+            prims: two_material_mesh().prims,
+            detail: (),
+            extra: Default::default(),
+        };
+        let err = check_mirrors_into_raw(geo, options.clone()).unwrap_err();
+        assert!(matches!(err, Error::InvalidOutVertexPtnum));
+
+        #[derive(Clone, OutAttrs)]
+        struct MaterialPrim {
+            material: String,
+        }
+        let geo = Geometry {
+            points: two_material_mesh().points,
+            vertices: two_material_mesh().vertices,
+            prims: vec![MaterialPrim {
+                material: "a".to_string(),
+            }],
+            detail: (),
+            extra: Default::default(),
+        };
+        let err = check_mirrors_into_raw(geo, options.clone()).unwrap_err();
+        assert!(matches!(err, Error::MissingOutPrimVertices));
+
+        // The derive rejects a `points` field, so this needs a manual implementation.
+        #[derive(Clone)]
+        struct PointsPrim {
+            vertices: Vec<usize>,
+            points: i32,
+        }
+        impl OutAttrs for PointsPrim {
+            const OUT_ATTRS: &'static [(&'static str, usize)] = &[("vertices", 1), ("points", 1)];
+
+            fn into_attr(entities: Vec<Self>) -> Result<BTreeMap<&'static str, RawAttribute>> {
+                Self::into_attr_ref(&entities)
+            }
+
+            fn into_attr_ref(entities: &[Self]) -> Result<BTreeMap<&'static str, RawAttribute>> {
+                Ok(BTreeMap::from([
+                    (
+                        "vertices",
+                        crate::generate_to_attr(
+                            entities.iter().map(|p| p.vertices.clone()).collect(),
+                        ),
+                    ),
+                    (
+                        "points",
+                        crate::generate_to_attr(entities.iter().map(|p| p.points).collect()),
+                    ),
+                ]))
+            }
+        }
+        let geo = Geometry {
+            points: two_material_mesh().points,
+            vertices: two_material_mesh().vertices,
+            prims: vec![PointsPrim {
+                vertices: vec![0, 1, 2, 3],
+                points: 4,
+            }],
+            detail: (),
+            extra: Default::default(),
+        };
+        let err = check_mirrors_into_raw(geo, options).unwrap_err();
+        assert!(matches!(err, Error::AttrNameCollision(name) if name == "points"));
+    }
+
+    #[test]
+    fn triangulate_quad() {
+        let mut geo = two_material_mesh();
+        geo.prims[1].vertices.truncate(2);
+        geo.triangulate().unwrap();
+
+        // The second prim was reduced to a degenerate line and is dropped with its vertices.
+        assert_eq!(geo.prims.len(), 2);
+        assert_eq!(geo.vertices.len(), 6);
+        assert!(geo.prims.iter().all(|prim| prim.material == "a"));
+
+        let ptnums: Vec<Vec<usize>> = geo
+            .prim_vertices()
+            .map(|vertices| vertices.iter().map(|&v| geo.vertices[v].ptnum).collect())
+            .collect();
+        assert_eq!(ptnums, vec![vec![0, 1, 4], vec![0, 4, 3]]);
+        assert_eq!(geo.prims[0].vertices, vec![0, 1, 2]);
+        assert_eq!(geo.prims[1].vertices, vec![3, 4, 5]);
+        assert_eq!(geo.points.len(), 6);
+
+        let mut broken = two_material_mesh();
+        broken.prims[1].vertices.push(8);
+        let err = broken.triangulate().unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidInPrimVertex {
+                prim_index: 1,
+                vertex: 8
+            }
+        ));
+        // Nothing was split before the error.
+        assert_eq!(broken.prims.len(), 2);
+        assert_eq!(broken.vertices.len(), 8);
+    }
+
+    #[test]
+    fn promote_attributes() {
+        #[derive(VertexTopology)]
+        struct UvVertex {
+            ptnum: usize,
+            u: f32,
+        }
+
+        let mesh = two_material_mesh();
+        let heights = mesh
+            .promote_point_to_vertex(|point| point.position.y)
+            .unwrap();
+        assert_eq!(heights, vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0]);
+
+        let mut geo = Geometry {
+            vertices: mesh
+                .vertices
+                .iter()
+                .enumerate()
+                .map(|(i, vertex)| UvVertex {
+                    ptnum: vertex.ptnum,
+                    u: i as f32,
+                })
+                .collect(),
+            points: mesh.points,
+            prims: mesh.prims,
+            detail: (),
+            extra: mesh.extra,
+        };
+        // An extra point that no vertex references.
+        geo.points.push(Point {
+            position: Vec3::ZERO,
+        });
+
+        // Points 1 and 4 are shared by both quads, through vertices 1 and 4, and 2 and 7.
+        let first = geo
+            .promote_vertex_to_point(|vertex| vertex.u, Promotion::First)
+            .unwrap();
+        assert_eq!(first, vec![0.0, 1.0, 5.0, 3.0, 2.0, 6.0, 0.0]);
+        let average = geo
+            .promote_vertex_to_point(|vertex| vertex.u, Promotion::Average)
+            .unwrap();
+        assert_eq!(average, vec![0.0, 2.5, 5.0, 3.0, 4.5, 6.0, 0.0]);
+        let sum = geo
+            .promote_vertex_to_point(|vertex| Vec3::splat(vertex.u), Promotion::Sum)
+            .unwrap();
+        assert_eq!(sum[4], Vec3::splat(9.0));
+
+        geo.vertices[0].ptnum = 7;
+        let err = geo
+            .promote_vertex_to_point(|vertex| vertex.u, Promotion::First)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidPointRef {
+                index: 7,
+                point_count: 7
+            }
+        ));
+    }
+
+    #[test]
+    fn same_topology() {
+        let input = two_material_mesh();
+        let mut output = two_material_mesh();
+        for point in &mut output.points {
+            point.position.z += 1.0;
+        }
+        output.prims[0].material = "c".to_string();
+        output.assert_same_topology(&input).unwrap();
+
+        let mut output = two_material_mesh();
+        output.points.pop();
+        let err = output.assert_same_topology(&input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Topology changed: point count changed from 6 to 5"
+        );
+
+        let mut output = two_material_mesh();
+        output.reverse_winding(false);
+        let err = output.assert_same_topology(&input).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TopologyChanged(TopologyChange::PrimVertices(0))
+        ));
+
+        let mut output = two_material_mesh();
+        output.vertices[5].ptnum = 0;
+        let err = output.assert_same_topology(&input).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TopologyChanged(TopologyChange::VertexPtnum(5))
+        ));
+    }
+
+    #[test]
+    fn gapped_point_numbers() {
+        let raw = |polygons: Vec<Vec<usize>>| {
+            RawGeometry::fixture()
+                .point_attr("P", vec![Vec3::X, Vec3::Y, Vec3::Z])
+                .point_attr(crate::ELEMNUM_ATTR, vec![10usize, 20, 40])
+                .polygons(polygons)
+                .prim_attr("material", vec!["a".to_string()])
+                .build()
+        };
+
+        let geo =
+            Geometry::<Point, Vertex, Prim>::from_raw(raw(vec![vec![40, 10, 20]]), 0).unwrap();
+        let ptnums: Vec<_> = geo.vertices.iter().map(|v| v.ptnum).collect();
+        assert_eq!(ptnums, vec![2, 0, 1]);
+        assert_eq!(geo.points[geo.vertices[0].ptnum].position, Vec3::Z);
+
+        let err =
+            Geometry::<Point, Vertex, Prim>::from_raw(raw(vec![vec![10, 30, 40]]), 0).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::UnknownElementNumber {
+                entity: EntityKind::Point,
+                number: 30
+            }
+        ));
+    }
+
+    #[test]
+    fn out_of_range_prim_vertex() {
+        let raw = || {
+            RawGeometry::fixture()
+                .point_attr("P", vec![Vec3::X, Vec3::Y, Vec3::Z])
+                .polygons(vec![vec![0, 1, 2], vec![2, 1, 0]])
+                .prim_attr("material", vec!["a".to_string(), "b".to_string()])
+                .build()
+        };
+        let mut broken = raw();
+        let RawAttributeData::PrimVertex(prims) =
+            &mut broken.prims.get_mut("vertices").unwrap().data
+        else {
+            panic!("expected prim vertices");
+        };
+        prims[1][2] = 6;
+
+        Geometry::<Point, Vertex, Prim>::from_raw(raw(), 0).unwrap();
+        let err = Geometry::<Point, Vertex, Prim>::from_raw(broken, 0).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidInPrimVertex {
+                prim_index: 1,
+                vertex: 6
+            }
+        ));
+    }
+
+    #[test]
+    fn append_triangle() {
+        let mut geo = two_material_mesh();
+        geo.append_polygon(&[2, 5, 4]).unwrap().material = "c".to_string();
+
+        assert_eq!(geo.vertices.len(), 11);
+        assert_eq!(geo.prims[2].vertices, vec![8, 9, 10]);
+        let output = geo.into_raw().unwrap();
+        assert_eq!(
+            output.prims["points"].data,
+            RawAttributeData::PrimVertex(prim_vertices([
+                vec![0, 1, 4, 3],
+                vec![1, 2, 5, 4],
+                vec![2, 5, 4],
+            ]))
+        );
+
+        let mut geo = two_material_mesh();
+        let err = geo.append_polygon(&[0, 6, 1]).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidPointRef {
+                index: 6,
+                point_count: 6
+            }
+        ));
+        assert_eq!(geo, two_material_mesh());
+    }
+
+    #[test]
+    fn adjacency() {
+        let mut geo = two_material_mesh();
+        geo.prims.truncate(1);
+        assert_eq!(
+            geo.build_adjacency().unwrap(),
+            vec![
+                vec![1, 3],
+                vec![0, 4],
+                vec![],
+                vec![0, 4],
+                vec![1, 3],
+                vec![]
+            ]
+        );
+
+        let geo = two_material_mesh();
+        let neighbors = geo
+            .build_adjacency_with(|prim| prim.material == "b")
+            .unwrap();
+        // The open prim `a` has no edge between its last and first point.
+        assert_eq!(neighbors[0], vec![1]);
+        assert_eq!(neighbors[3], vec![4]);
+        assert_eq!(neighbors[4], vec![1, 3, 5]);
+        assert_eq!(neighbors[5], vec![2, 4]);
+    }
+
+    #[test]
+    fn smooth_noisy_grid() {
+        // A 3x3 grid of points forming 4 quads, with the center point 4 moved off the plane.
+        let mut geo = Geometry {
+            points: (0..9)
+                .map(|i| Point {
+                    position: Vec3::new((i % 3) as f32, (i / 3) as f32, 0.0),
+                })
+                .collect(),
+            vertices: [0, 1, 4, 3, 1, 2, 5, 4, 3, 4, 7, 6, 4, 5, 8, 7]
+                .into_iter()
+                .map(|ptnum| Vertex { ptnum })
+                .collect(),
+            prims: (0..4)
+                .map(|i| Prim {
+                    vertices: (i * 4..i * 4 + 4).collect(),
+                    material: String::new(),
+                })
+                .collect(),
+            detail: (),
+            extra: Default::default(),
+        };
+        geo.points[4].position = Vec3::new(1.3, 0.8, 1.0);
+        let center = Vec3::new(1.0, 1.0, 0.0);
+
+        let mut distances = Vec::new();
+        for _ in 0..4 {
+            let mut smoothed = geo.clone();
+            smoothed.smooth_positions(1, 0.5).unwrap();
+            distances.push(smoothed.points[4].position.distance(center));
+            geo = smoothed;
+        }
+        assert!(distances.windows(2).all(|d| d[1] < d[0]));
+
+        // With full strength, the center point lands on the average of its neighbors.
+        let mut full = geo.clone();
+        full.smooth_positions(1, 1.0).unwrap();
+        let neighbors = [1, 3, 5, 7].map(|i| geo.points[i].position);
+        let average = neighbors.into_iter().sum::<Vec3>() / 4.0;
+        assert!(full.points[4].position.distance(average) < 1e-6);
+    }
+}
@@ -0,0 +1,79 @@
+//! Runs the `main` generated by `#[houdini_node_main]` as a separate process, the way the bridge in
+//! Houdini does.
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Separates the output of the test harness from the output of the node.
+const OUTPUT_MARKER: &str = "--- node output ---\n";
+
+/// How long the node gets to finish before the test fails.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs the ignored test `node_test` of the current test binary, which calls [`node_process`],
+/// with `input` on stdin, and returns what the node wrote to stdout. Like the bridge, stdin is
+/// kept open until the node has exited.
+pub fn run_node(node_test: &str, input: &[u8]) -> Vec<u8> {
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        .args([
+            node_test,
+            "--exact",
+            "--ignored",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(input).unwrap();
+    stdin.flush().unwrap();
+
+    // Read on threads, so that the node doesn't block on a full pipe.
+    let read_all = |mut stream: Box<dyn Read + Send>| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            stream.read_to_end(&mut bytes).unwrap();
+            bytes
+        })
+    };
+    let stdout = read_all(Box::new(child.stdout.take().unwrap()));
+    let stderr = read_all(Box::new(child.stderr.take().unwrap()));
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if start.elapsed() > TIMEOUT {
+            child.kill().unwrap();
+            panic!("the node didn't finish, is it waiting for stdin to be closed?");
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    drop(stdin);
+
+    let stdout = stdout.join().unwrap();
+    let stderr = String::from_utf8_lossy(&stderr.join().unwrap()).into_owned();
+    assert!(status.success(), "the node failed: {stderr}");
+
+    let start = stdout
+        .windows(OUTPUT_MARKER.len())
+        .position(|window| window == OUTPUT_MARKER.as_bytes())
+        .expect("the node process didn't start");
+    stdout[start + OUTPUT_MARKER.len()..].to_vec()
+}
+
+/// The body of the ignored test that [`run_node`] runs: calls the generated `main`, then exits
+/// before the test harness writes anything else to stdout.
+pub fn node_process(main: fn()) {
+    print!("{OUTPUT_MARKER}");
+    std::io::stdout().flush().unwrap();
+    main();
+    std::process::exit(0);
+}
@@ -0,0 +1,41 @@
+use glam::Vec3;
+use houdini_node::binary::{Endianness, load_raw_binary, write_raw_binary};
+use houdini_node::{FromRawGeometry, Geometry, IntoRawGeometry};
+use houdini_node_macro::{InAttrs, OutAttrs, houdini_node_main};
+mod common;
+
+#[derive(InAttrs, OutAttrs)]
+struct Point {
+    #[attr(name = "P")]
+    position: Vec3,
+}
+
+// There can only be one `#[houdini_node_main]` per test.
+#[houdini_node_main(format = "binary")]
+fn lift(mut geo: Geometry<Point>) -> Result<Geometry<Point>, String> {
+    for point in &mut geo.points {
+        point.position.y += 1.0;
+    }
+    Ok(geo)
+}
+
+#[test]
+#[ignore = "the node process of `binary_transport`"]
+fn node_process() {
+    common::node_process(main);
+}
+
+#[test]
+fn binary_transport() {
+    let geo = Geometry::<Point>::from(vec![Point {
+        position: Vec3::ZERO,
+    }])
+    .into_raw()
+    .unwrap();
+    let mut input = Vec::new();
+    write_raw_binary(&mut input, &[geo], Endianness::Big).unwrap();
+    let output = common::run_node("node_process", &input);
+    let raw = load_raw_binary(output.as_slice()).unwrap().remove(0);
+    let geo = Geometry::<Point>::from_raw(raw, 0).unwrap();
+    assert_eq!(geo.points[0].position, Vec3::Y);
+}
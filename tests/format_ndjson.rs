@@ -0,0 +1,44 @@
+use glam::Vec3;
+use houdini_node::{FromRawGeometry, Geometry, IntoRawGeometry, RawGeometry};
+use houdini_node_macro::{InAttrs, OutAttrs, houdini_node_main};
+mod common;
+
+#[derive(InAttrs, OutAttrs)]
+struct Point {
+    #[attr(name = "P")]
+    position: Vec3,
+}
+
+// There can only be one `#[houdini_node_main]` per test.
+#[houdini_node_main(format = "ndjson")]
+fn lift(mut geo: Geometry<Point>) -> Result<Geometry<Point>, String> {
+    for point in &mut geo.points {
+        point.position.y += 1.0;
+    }
+    Ok(geo)
+}
+
+#[test]
+#[ignore = "the node process of `ndjson_transport`"]
+fn node_process() {
+    common::node_process(main);
+}
+
+#[test]
+fn ndjson_transport() {
+    let geo = Geometry::<Point>::from(vec![Point {
+        position: Vec3::ZERO,
+    }])
+    .into_raw()
+    .unwrap();
+    // The count of geometries first, as stdin stays open.
+    let input = format!("1\n{}\n", serde_json::to_string(&geo).unwrap()).into_bytes();
+    let output = common::run_node("node_process", &input);
+    let output = String::from_utf8(output).unwrap();
+    let [line] = output.lines().collect::<Vec<_>>()[..] else {
+        panic!("expected a single geometry, got {output:?}");
+    };
+    let raw: RawGeometry = serde_json::from_str(line).unwrap();
+    let geo = Geometry::<Point>::from_raw(raw, 0).unwrap();
+    assert_eq!(geo.points[0].position, Vec3::Y);
+}
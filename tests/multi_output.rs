@@ -0,0 +1,71 @@
+use glam::Vec3;
+use houdini_node::{Geometry, IntoNodeOutput, NodeOutput};
+use houdini_node_macro::{InAttrs, OutAttrs, houdini_node_main};
+
+#[derive(InAttrs, OutAttrs)]
+struct Point {
+    #[attr(name = "P")]
+    position: Vec3,
+}
+
+#[derive(OutAttrs)]
+struct MeshDetail {
+    #[attr(name = "mesh_name")]
+    name: String,
+}
+
+#[derive(OutAttrs)]
+struct StatsDetail {
+    point_count: i32,
+}
+
+type MeshGeometry = Geometry<Point, (), (), MeshDetail>;
+type StatsGeometry = Geometry<(), (), (), StatsDetail>;
+
+#[houdini_node_main]
+fn split_node(geo: Geometry<Point>) -> Result<(MeshGeometry, StatsGeometry), String> {
+    let point_count = geo.points.len() as i32;
+    Ok((
+        Geometry {
+            points: geo.points,
+            vertices: vec![],
+            prims: vec![],
+            detail: MeshDetail {
+                name: "mesh".to_string(),
+            },
+            extra: geo.extra,
+        },
+        Geometry {
+            points: vec![],
+            vertices: vec![],
+            prims: vec![],
+            detail: StatsDetail { point_count },
+            extra: Default::default(),
+        },
+    ))
+}
+
+#[test]
+fn returns_two_geometries() {
+    let geo = Geometry {
+        points: vec![Point {
+            position: Vec3::ONE,
+        }],
+        vertices: vec![],
+        prims: vec![],
+        detail: (),
+        extra: Default::default(),
+    };
+
+    let output = split_node(geo).unwrap().into_node_output().unwrap();
+    let NodeOutput::Multiple(outputs) = &output else {
+        panic!("expected one geometry per output");
+    };
+    assert_eq!(outputs.len(), 2);
+    assert!(outputs[0].detail.contains_key("mesh_name"));
+    assert!(outputs[1].detail.contains_key("point_count"));
+
+    let json = serde_json::to_value(&output).unwrap();
+    assert!(json.is_array());
+    assert_eq!(json[1]["detail"]["point_count"]["data"]["int"][0], 1);
+}
@@ -0,0 +1,56 @@
+use houdini_node::{Geometry, IntoNodeOutput, NodeOutput};
+use houdini_node_macro::{OutAttrs, houdini_node_main};
+
+#[derive(OutAttrs)]
+struct ReportDetail {
+    point_count: i32,
+}
+
+#[houdini_node_main]
+fn write_report(geo: Geometry<()>) -> Result<(), String> {
+    if geo.points.is_empty() {
+        return Err("nothing to report".to_string());
+    }
+    Ok(())
+}
+
+// Returned the same way from a `#[houdini_node_main]` function, there can only be one per test.
+fn count_points(geo: Geometry<()>) -> Result<Geometry<(), (), (), ReportDetail>, String> {
+    Ok(Geometry {
+        points: vec![],
+        vertices: vec![],
+        prims: vec![],
+        detail: ReportDetail {
+            point_count: geo.points.len() as i32,
+        },
+        extra: Default::default(),
+    })
+}
+
+#[test]
+fn side_effect_only_node() {
+    assert!(write_report(Geometry::default()).is_err());
+    write_report(vec![()].into()).unwrap();
+
+    let output = ().into_node_output().unwrap();
+    let json = serde_json::to_value(&output).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "points": {}, "vertices": {}, "prims": {}, "detail": {} })
+    );
+}
+
+#[test]
+fn detail_only_node() {
+    let output = count_points(vec![(), ()].into())
+        .unwrap()
+        .into_node_output()
+        .unwrap();
+    let NodeOutput::Single(raw) = &output else {
+        panic!("expected a single geometry");
+    };
+    assert!(raw.points.is_empty());
+
+    let json = serde_json::to_value(&output).unwrap();
+    assert_eq!(json["detail"]["point_count"]["data"]["int"][0], 2);
+}
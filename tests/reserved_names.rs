@@ -0,0 +1,7 @@
+/// The derives reject fields mapping to the pre-defined attribute names at compile time, see
+/// `Error::AttrNameCollision`.
+#[test]
+fn reserved_names() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,7 @@
+#[derive(houdini_node::InAttrs)]
+struct Point {
+    #[attr(name = "__elemnum")]
+    number: usize,
+}
+
+fn main() {}
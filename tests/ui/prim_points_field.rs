@@ -0,0 +1,7 @@
+#[derive(houdini_node::OutAttrs)]
+struct Prim {
+    vertices: Vec<usize>,
+    points: Vec<usize>,
+}
+
+fn main() {}